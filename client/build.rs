@@ -0,0 +1,31 @@
+//! Generates the klend program errors from its IDL.
+
+use std::{env, fmt::Write as _, fs, path::Path};
+
+use serde_json::Value;
+
+type Result<T> = core::result::Result<T, Box<dyn core::error::Error>>;
+
+fn main() -> Result<()> {
+    let idl_path = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("../klend_idl.json");
+    let idl: Value = serde_json::from_str(&fs::read_to_string(&idl_path)?)?;
+
+    let mut variants = String::new();
+    for error in idl["errors"].as_array().ok_or("the IDL has no errors")? {
+        let name = error["name"].as_str().ok_or("an error has no name")?;
+        let code = error["code"].as_u64().ok_or("an error has no code")?;
+        let msg = error["msg"].as_str().unwrap_or(name);
+        writeln!(variants, "        {name} = {code} => {msg:?},")?;
+    }
+    let errors = format!(
+        "program_errors! {{\n    /// Errors of the klend program, generated from its IDL.\n    \
+         KlendError {{\n{variants}    }}\n}}\n"
+    );
+    fs::write(
+        Path::new(&env::var("OUT_DIR")?).join("klend_errors.rs"),
+        errors,
+    )?;
+
+    println!("cargo::rerun-if-changed={}", idl_path.display());
+    Ok(())
+}
@@ -0,0 +1,40 @@
+//! (De)serializes the addresses as base 58 strings, with `#[serde(with = "address")]`.
+
+use serde::{Deserialize as _, Deserializer, Serializer, de};
+use solana_sdk::pubkey::Pubkey;
+
+/// Serializes an address.
+pub fn serialize<S: Serializer>(address: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(address)
+}
+
+/// Deserializes an address.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(de::Error::custom)
+}
+
+/// (De)serializes an optional address, with `#[serde(default, with = "address::optional")]`.
+pub mod optional {
+    use super::*;
+
+    /// Serializes an optional address.
+    #[expect(clippy::ref_option)]
+    pub fn serialize<S: Serializer>(
+        address: &Option<Pubkey>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match address {
+            Some(address) => super::serialize(address, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    /// Deserializes an optional address.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Pubkey>, D::Error> {
+        super::deserialize(deserializer).map(Some)
+    }
+}
@@ -0,0 +1,190 @@
+//! An offline stand-in for the RPC of a cluster, used by the tests.
+//!
+//! The RPC client is built over a sender serving scripted responses, either loaded from the
+//! fixture files of `fixtures/rpc` or given by the test. The requests without a scripted response
+//! get the default responses of the mock client of `solana-client`. Every request made is
+//! recorded, so that the tests can check what was sent.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs,
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use async_trait::async_trait;
+use serde_json::Value;
+use solana_client::{
+    client_error::Result as ClientResult,
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::RpcClientConfig,
+    rpc_request::RpcRequest,
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Keypair};
+
+use super::{COMMITMENT_LEVEL, Context};
+use crate::error::{Error, Result};
+
+/// The requests that can be scripted, by their method name.
+const SCRIPTABLE: [RpcRequest; 10] = [
+    RpcRequest::GetAccountInfo,
+    RpcRequest::GetMultipleAccounts,
+    RpcRequest::GetLatestBlockhash,
+    RpcRequest::GetBlockHeight,
+    RpcRequest::SendTransaction,
+    RpcRequest::GetSignatureStatuses,
+    RpcRequest::SimulateTransaction,
+    RpcRequest::GetRecentPrioritizationFees,
+    RpcRequest::GetBalance,
+    RpcRequest::GetProgramAccounts,
+];
+
+/// The responses served to the RPC requests, by request.
+///
+/// The responses of a request are served in order, the last one being repeated.
+#[derive(Debug, Clone, Default)]
+pub struct MockRpc {
+    responses: HashMap<RpcRequest, VecDeque<Value>>,
+}
+
+/// The requests received by a [`MockRpc`] (but for the version checks of the client), with their
+/// parameters.
+#[derive(Debug, Clone, Default)]
+pub struct Requests(Arc<Mutex<Vec<(RpcRequest, Value)>>>);
+
+impl MockRpc {
+    /// Loads the responses of a fixture file of `fixtures/rpc`.
+    ///
+    /// The file is a JSON object mapping method names (`getAccountInfo`…) to the list of their
+    /// responses, i.e. the `result` of the JSON RPC responses.
+    ///
+    /// # Parameters
+    /// * `name` - Name of the fixture, without its `.json` extension.
+    ///
+    /// # Errors
+    /// If the file could not be read or holds an unknown method.
+    #[expect(clippy::result_large_err)]
+    pub fn fixture(name: &str) -> Result<Self> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/rpc")
+            .join(name)
+            .with_extension("json");
+        let content = fs::read_to_string(&path)
+            .map_err(|err| Error::Config(format!("could not read {}: {err}", path.display())))?;
+        let fixture: BTreeMap<String, Vec<Value>> = serde_json::from_str(&content)
+            .map_err(|err| Error::Config(format!("could not parse {}: {err}", path.display())))?;
+
+        let mut rpc = Self::default();
+        for (method, responses) in fixture {
+            let request = SCRIPTABLE
+                .into_iter()
+                .find(|request| request.to_string() == method)
+                .ok_or_else(|| Error::Config(format!("unknown RPC method {method}")))?;
+            rpc.responses.entry(request).or_default().extend(responses);
+        }
+
+        Ok(rpc)
+    }
+
+    /// Adds a response to a request, served after those already given.
+    pub fn respond(mut self, request: RpcRequest, response: Value) -> Self {
+        self.responses
+            .entry(request)
+            .or_default()
+            .push_back(response);
+        self
+    }
+
+    /// A context whose RPC client is served by the scripted responses.
+    ///
+    /// # Parameters
+    /// * `payer` - The payer of the transactions.
+    ///
+    /// # Returns
+    /// The context, and the requests it will make.
+    pub fn context(self, payer: Keypair) -> (Context, Requests) {
+        let requests = Requests::default();
+        let sender = MockSender {
+            responses: Mutex::new(self.responses),
+            requests: requests.clone(),
+            fallback: RpcClient::new_mock("succeeds".to_owned()),
+        };
+        let rpc = RpcClient::new_sender(
+            sender,
+            RpcClientConfig::with_commitment(CommitmentConfig {
+                commitment: COMMITMENT_LEVEL,
+            }),
+        );
+        let ctx = Context {
+            rpc,
+            ..Context::new(String::new(), String::new(), payer)
+        };
+
+        (ctx, requests)
+    }
+}
+
+impl Requests {
+    /// The requests received, in order.
+    pub fn methods(&self) -> Vec<RpcRequest> {
+        self.lock().iter().map(|(request, _)| *request).collect()
+    }
+
+    /// The parameters of each call to a request, in order.
+    pub fn params(&self, request: RpcRequest) -> Vec<Value> {
+        self.lock()
+            .iter()
+            .filter(|(received, _)| *received == request)
+            .map(|(_, params)| params.clone())
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<(RpcRequest, Value)>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The sender of the RPC client of a [`MockRpc`].
+struct MockSender {
+    responses: Mutex<HashMap<RpcRequest, VecDeque<Value>>>,
+    requests: Requests,
+    /// Answers the requests without scripted responses.
+    fallback: RpcClient,
+}
+
+#[async_trait]
+impl RpcSender for MockSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        // the client checks the version of the node before some requests
+        if request != RpcRequest::GetVersion {
+            self.requests.lock().push((request, params.clone()));
+        }
+
+        let scripted = {
+            let mut responses = self
+                .responses
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            responses.get_mut(&request).and_then(|queue| {
+                if queue.len() > 1 {
+                    queue.pop_front()
+                } else {
+                    queue.front().cloned()
+                }
+            })
+        };
+        match scripted {
+            Some(response) => Ok(response),
+            None => self.fallback.send(request, params).await,
+        }
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        RpcTransportStats::default()
+    }
+
+    fn url(&self) -> String {
+        "mock".to_owned()
+    }
+}
@@ -0,0 +1,167 @@
+pub mod address;
+#[cfg(test)]
+pub mod mock;
+pub mod profile;
+
+use solana_client::nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient};
+use solana_sdk::pubkey;
+use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    pubkey::Pubkey,
+    signature::{Keypair, read_keypair_file},
+};
+use tracing::{debug, instrument};
+
+use profile::{
+    DEVNET_AMM_V4_PROGRAM, DEVNET_CLMM_PROGRAM, DEVNET_CPMM_FEE_RECEIVER, DEVNET_CPMM_PROGRAM,
+    DEVNET_KLEND_PROGRAM, Profile,
+};
+
+use crate::{
+    error::{Error, Result, program::Programs},
+    transaction::TransactionOptions,
+};
+
+/// The commitment level set for the RPC client
+pub const COMMITMENT_LEVEL: CommitmentLevel = CommitmentLevel::Processed;
+
+/// Everything needed to talk to a cluster: its RPC, the payer of the transactions and the
+/// programs used.
+///
+/// It is passed to every operation reaching the cluster, so that several clusters or payers can
+/// be used by the same process.
+pub struct Context {
+    /// The RPC client, with the commitment level of the requests.
+    pub rpc: RpcClient,
+    /// Address of the Solana RPC via `WS`.
+    pub ws_url: String,
+    /// The payer (and first signer) of all the transactions.
+    pub payer: Keypair,
+    /// The klend program.
+    pub klend_program: Pubkey,
+    /// The Raydium constant product (CPMM) program.
+    pub cpmm_program: Pubkey,
+    /// The Raydium concentrated liquidity (CLMM) program.
+    pub clmm_program: Pubkey,
+    /// The Raydium AMM v4 program.
+    pub amm_v4_program: Pubkey,
+    /// The account receiving the CPMM pool creation fees.
+    pub cpmm_fee_receiver: Pubkey,
+    /// Address lookup tables used to compile v0 transactions (legacy transactions if none).
+    pub lookup_tables: Vec<Pubkey>,
+    /// How the transactions are built and sent.
+    pub options: TransactionOptions,
+}
+
+impl Context {
+    /// A context with the default commitment level, devnet programs and transaction options.
+    ///
+    /// # Parameters
+    /// * `rpc_url` - Address of the Solana RPC via HTTP,
+    /// * `ws_url` - Address of the Solana RPC via `WS`,
+    /// * `payer` - The payer of the transactions.
+    pub fn new(rpc_url: String, ws_url: String, payer: Keypair) -> Self {
+        debug!("getting RPC client at {rpc_url}");
+        Self {
+            rpc: RpcClient::new_with_commitment(
+                rpc_url,
+                CommitmentConfig {
+                    commitment: COMMITMENT_LEVEL,
+                },
+            ),
+            ws_url,
+            payer,
+            klend_program: DEVNET_KLEND_PROGRAM,
+            cpmm_program: DEVNET_CPMM_PROGRAM,
+            clmm_program: DEVNET_CLMM_PROGRAM,
+            amm_v4_program: DEVNET_AMM_V4_PROGRAM,
+            cpmm_fee_receiver: DEVNET_CPMM_FEE_RECEIVER,
+            lookup_tables: vec![],
+            options: TransactionOptions::default(),
+        }
+    }
+
+    /// A context with the cluster and programs of a profile, and the default transaction options.
+    ///
+    /// # Parameters
+    /// * `profile` - The cluster and programs to use,
+    /// * `payer` - The payer of the transactions.
+    pub fn from_profile(profile: &Profile, payer: Keypair) -> Self {
+        Self {
+            rpc: RpcClient::new_with_commitment(
+                profile.rpc_url.clone(),
+                CommitmentConfig {
+                    commitment: profile.commitment,
+                },
+            ),
+            klend_program: profile.klend_program,
+            cpmm_program: profile.cpmm_program,
+            clmm_program: profile.clmm_program,
+            amm_v4_program: profile.amm_v4_program,
+            cpmm_fee_receiver: profile.cpmm_fee_receiver,
+            ..Self::new(profile.rpc_url.clone(), profile.ws_url.clone(), payer)
+        }
+    }
+
+    /// The programs whose errors are decoded.
+    pub const fn programs(&self) -> Programs {
+        Programs {
+            klend: self.klend_program,
+            cpmm: self.cpmm_program,
+            clmm: self.clmm_program,
+            amm_v4: self.amm_v4_program,
+        }
+    }
+
+    /// Get the client to subscribe to for event monitoring.
+    ///
+    /// # Errors
+    /// If the client could not connect.
+    #[instrument(skip(self), fields(url = %self.ws_url))]
+    pub async fn pubsub(&self) -> Result<PubsubClient> {
+        debug!("getting pubsub client");
+        PubsubClient::new(&self.ws_url).await.map_err(Error::Pubsub)
+    }
+}
+
+/// Read a keypair from a JSON file, as written by `solana-keygen`.
+///
+/// # Errors
+/// If the file could not be read or does not hold a keypair.
+#[instrument]
+pub fn read_keypair(path: &str) -> Result<Keypair> {
+    read_keypair_file(path).map_err(|_err| Error::Keypair)
+}
+
+/// Wrapped Solana SPL token mint
+pub const WSOL_MINT: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+/// bSOL SPL tokens mint
+pub const BSOL_MINT: Pubkey = pubkey!("bSo13r4TkiE4KumL71LsHTPpL2euBYLFx6h9HP3piy1");
+
+#[cfg(test)]
+// 4vCvi2VqJbRSe33F9kfVQY6R8MX25eDK1f1TtuFngbwp
+pub const SOURCE: &[u8; 64] = &[
+    153, 169, 33, 203, 65, 237, 143, 120, 101, 196, 21, 52, 129, 16, 190, 228, 144, 255, 214, 140,
+    104, 62, 163, 219, 170, 188, 135, 201, 214, 53, 120, 155, 58, 52, 84, 19, 58, 63, 157, 208,
+    177, 90, 191, 85, 184, 176, 84, 9, 101, 26, 217, 116, 178, 202, 196, 191, 128, 102, 53, 45, 71,
+    2, 144, 91,
+];
+
+#[cfg(test)]
+// 5rfMKbh1EYjjmE86fxGfHTbU2ENm8w7nUYmGXd1gStnp
+pub const TARGET: &[u8; 64] = &[
+    53, 100, 38, 40, 243, 211, 25, 37, 144, 107, 36, 140, 140, 197, 128, 180, 20, 16, 173, 209, 63,
+    156, 111, 137, 85, 135, 69, 130, 43, 38, 166, 245, 72, 39, 145, 250, 253, 19, 207, 237, 58, 50,
+    49, 190, 197, 138, 64, 98, 168, 106, 171, 46, 62, 148, 200, 85, 72, 126, 19, 226, 16, 143, 218,
+    229,
+];
+
+/// A context paid by the `SOURCE` keypair, whose RPC serves scripted responses.
+///
+/// # Returns
+/// The context, and the requests it will make.
+#[cfg(test)]
+#[expect(clippy::result_large_err)]
+pub fn test_context(rpc: mock::MockRpc) -> Result<(Context, mock::Requests)> {
+    Ok(rpc.context(Keypair::from_bytes(SOURCE).map_err(|_err| Error::Keypair)?))
+}
@@ -0,0 +1,392 @@
+use core::fmt;
+use std::{collections::BTreeMap, fs, path::Path};
+
+use serde::Deserialize;
+use solana_sdk::{commitment_config::CommitmentLevel, pubkey, pubkey::Pubkey};
+use tracing::{debug, instrument};
+
+use crate::{
+    config::{BSOL_MINT, COMMITMENT_LEVEL, address},
+    error::{Error, Result},
+};
+
+/// The klend program deployed on devnet.
+pub const DEVNET_KLEND_PROGRAM: Pubkey = pubkey!("5Xs3m9xLbGFYY8C62PxuqAZjwmHnQuAzdjq6xtoKmVbF");
+/// The Raydium constant product (CPMM) program on devnet.
+pub const DEVNET_CPMM_PROGRAM: Pubkey = pubkey!("CPMDWBwJDtYax9qW7AyRuVC19Cc4L4Vcy4n2BHAbHkCW");
+/// The Raydium concentrated liquidity (CLMM) program on devnet.
+pub const DEVNET_CLMM_PROGRAM: Pubkey = pubkey!("devi51mZmdwUJGU9hjN27vEz64Gps7uUefqxg27EAtH");
+/// The Raydium AMM v4 program on devnet.
+pub const DEVNET_AMM_V4_PROGRAM: Pubkey = pubkey!("HWy1jotHpo6UqeQxx49dpYYdQB8wj9Qk9MdxwjLvDHB8");
+/// The WSOL account receiving the CPMM pool creation fees on devnet.
+pub const DEVNET_CPMM_FEE_RECEIVER: Pubkey =
+    pubkey!("G11FKBRaAkHAKuLCgLM6K6NUc9rTjPAznRCjZifrTQe2");
+/// The admin's WSOL account on devnet, funding the tests.
+const DEVNET_WSOL_SOURCE: Pubkey = pubkey!("CzHgrJsCNMayNCfxLZiyghyasDw3TkDGhJKDHZDQr8qd");
+/// The admin's bSOL account on devnet, funding the tests.
+const DEVNET_BSOL_SOURCE: Pubkey = pubkey!("FtyYfaF1w7qZVHjLwB9mb4mhSjiFh1Fc1dWbQyrhN6dT");
+/// The Pyth product account of bSOL on devnet.
+const DEVNET_PYTH_PRODUCT: Pubkey = pubkey!("8yrQMUyJRnCJ72NWwMiPV9dNGw465Z8bKUvnUC8P5L6F");
+/// The Pyth price account of bSOL on devnet.
+const DEVNET_PYTH_PRICE: Pubkey = pubkey!("BdgHsXrH1mXqhdosXavYxZgX6bGqTdj5mh2sxDhF8bJy");
+
+/// A configuration file, holding named profiles.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    profiles: BTreeMap<String, Profile>,
+}
+
+/// The cluster, programs, accounts and keypairs used by the commands.
+///
+/// The default profile targets devnet.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// Address of the Solana RPC via HTTP.
+    pub rpc_url: String,
+    /// Address of the Solana RPC via `WS`.
+    pub ws_url: String,
+    /// Commitment level of the RPC requests.
+    #[serde(default = "default_commitment")]
+    pub commitment: CommitmentLevel,
+    /// The klend program.
+    #[serde(deserialize_with = "address::deserialize")]
+    pub klend_program: Pubkey,
+    /// The Raydium constant product (CPMM) program.
+    #[serde(deserialize_with = "address::deserialize")]
+    pub cpmm_program: Pubkey,
+    /// The Raydium concentrated liquidity (CLMM) program.
+    #[serde(deserialize_with = "address::deserialize")]
+    pub clmm_program: Pubkey,
+    /// The Raydium AMM v4 program.
+    #[serde(deserialize_with = "address::deserialize")]
+    pub amm_v4_program: Pubkey,
+    /// The account receiving the CPMM pool creation fees.
+    #[serde(deserialize_with = "address::deserialize")]
+    pub cpmm_fee_receiver: Pubkey,
+    /// Mint of the bSOL tokens.
+    #[serde(deserialize_with = "address::deserialize")]
+    pub bsol_mint: Pubkey,
+    /// The admin's WSOL account funding the tests.
+    #[serde(default, deserialize_with = "address::optional::deserialize")]
+    pub wsol_source: Option<Pubkey>,
+    /// The admin's bSOL account funding the tests.
+    #[serde(default, deserialize_with = "address::optional::deserialize")]
+    pub bsol_source: Option<Pubkey>,
+    /// The Pyth product account of bSOL.
+    #[serde(default, deserialize_with = "address::optional::deserialize")]
+    pub pyth_product: Option<Pubkey>,
+    /// The Pyth price account of bSOL.
+    #[serde(default, deserialize_with = "address::optional::deserialize")]
+    pub pyth_price: Option<Pubkey>,
+    /// The lending market used by the commands.
+    #[serde(default, deserialize_with = "address::optional::deserialize")]
+    pub market: Option<Pubkey>,
+    /// The SOL reserve of the lending market.
+    #[serde(default, deserialize_with = "address::optional::deserialize")]
+    pub sol_reserve: Option<Pubkey>,
+    /// The bSOL reserve of the lending market.
+    #[serde(default, deserialize_with = "address::optional::deserialize")]
+    pub bsol_reserve: Option<Pubkey>,
+    /// Path to the keypair of the admin, paying for the transactions.
+    pub admin: String,
+    /// Path to the keypair of the user.
+    pub user: String,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            rpc_url: "https://api.devnet.solana.com".to_owned(),
+            ws_url: "wss://api.devnet.solana.com/".to_owned(),
+            commitment: COMMITMENT_LEVEL,
+            klend_program: DEVNET_KLEND_PROGRAM,
+            cpmm_program: DEVNET_CPMM_PROGRAM,
+            clmm_program: DEVNET_CLMM_PROGRAM,
+            amm_v4_program: DEVNET_AMM_V4_PROGRAM,
+            cpmm_fee_receiver: DEVNET_CPMM_FEE_RECEIVER,
+            bsol_mint: BSOL_MINT,
+            wsol_source: Some(DEVNET_WSOL_SOURCE),
+            bsol_source: Some(DEVNET_BSOL_SOURCE),
+            pyth_product: Some(DEVNET_PYTH_PRODUCT),
+            pyth_price: Some(DEVNET_PYTH_PRICE),
+            market: None,
+            sol_reserve: None,
+            bsol_reserve: None,
+            admin: "admin.json".to_owned(),
+            user: "user.json".to_owned(),
+        }
+    }
+}
+
+impl Profile {
+    /// Load a profile from a configuration file.
+    ///
+    /// The file is parsed as JSON if its extension is `json`, as TOML otherwise. The profiles are
+    /// the tables of its `profiles` table.
+    ///
+    /// # Parameters
+    /// * `path` - The configuration file,
+    /// * `name` - The name of the profile.
+    ///
+    /// # Errors
+    /// If the file could not be read or parsed, or it has no such profile.
+    #[instrument]
+    pub fn load(path: &Path, name: &str) -> Result<Self> {
+        debug!("loading profile");
+        let content = fs::read_to_string(path)
+            .map_err(|err| Error::Config(format!("could not read {}: {err}", path.display())))?;
+        let mut file = Self::parse(&content, path.extension().is_some_and(|ext| ext == "json"))
+            .map_err(|err| Error::Config(format!("could not parse {}: {err}", path.display())))?;
+
+        file.profiles.remove(name).ok_or_else(|| {
+            Error::Config(format!(
+                "no profile {name} in {} (available: {})",
+                path.display(),
+                file.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+            ))
+        })
+    }
+
+    fn parse(content: &str, json: bool) -> core::result::Result<ConfigFile, String> {
+        if json {
+            serde_json::from_str(content).map_err(|err| err.to_string())
+        } else {
+            toml::from_str(content).map_err(|err| err.to_string())
+        }
+    }
+
+    /// Check that the profile can be used.
+    ///
+    /// # Errors
+    /// If an URL does not have the expected scheme, the commitment level is deprecated, two
+    /// programs are the same, a keypair path is empty or the two reserves are the same.
+    #[expect(clippy::result_large_err)]
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |reason: String| Err(Error::Config(reason));
+
+        if !["http://", "https://"]
+            .iter()
+            .any(|scheme| self.rpc_url.starts_with(scheme))
+        {
+            return invalid(format!("the RPC URL {} is not an HTTP one", self.rpc_url));
+        }
+        if !["ws://", "wss://"]
+            .iter()
+            .any(|scheme| self.ws_url.starts_with(scheme))
+        {
+            return invalid(format!("the WS URL {} is not a websocket one", self.ws_url));
+        }
+        if !matches!(
+            self.commitment,
+            CommitmentLevel::Processed | CommitmentLevel::Confirmed | CommitmentLevel::Finalized
+        ) {
+            return invalid(format!("the commitment {} is deprecated", self.commitment));
+        }
+        let programs = [
+            self.klend_program,
+            self.cpmm_program,
+            self.clmm_program,
+            self.amm_v4_program,
+        ];
+        for (i, program) in programs.iter().enumerate() {
+            if programs[..i].contains(program) {
+                return invalid(format!("the program {program} is given twice"));
+            }
+        }
+        if self.admin.is_empty() || self.user.is_empty() {
+            return invalid("the admin and user keypairs must be given".to_owned());
+        }
+        if self.sol_reserve.is_some() && self.sol_reserve == self.bsol_reserve {
+            return invalid("the SOL and bSOL reserves must be different".to_owned());
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let optional = |address: Option<Pubkey>| {
+            address.map_or_else(|| "-".to_owned(), |address| address.to_string())
+        };
+
+        writeln!(
+            f,
+            "Profile ({} / {}, {})",
+            self.rpc_url, self.ws_url, self.commitment
+        )?;
+        writeln!(f, "  klend program: {}", self.klend_program)?;
+        writeln!(
+            f,
+            "  raydium programs: CPMM {} (fee receiver {}), CLMM {}, AMM v4 {}",
+            self.cpmm_program, self.cpmm_fee_receiver, self.clmm_program, self.amm_v4_program
+        )?;
+        writeln!(f, "  bSOL mint: {}", self.bsol_mint)?;
+        writeln!(
+            f,
+            "  sources: WSOL {}, bSOL {}",
+            optional(self.wsol_source),
+            optional(self.bsol_source)
+        )?;
+        writeln!(
+            f,
+            "  pyth: product {}, price {}",
+            optional(self.pyth_product),
+            optional(self.pyth_price)
+        )?;
+        writeln!(
+            f,
+            "  market: {} (SOL reserve {}, bSOL reserve {})",
+            optional(self.market),
+            optional(self.sol_reserve),
+            optional(self.bsol_reserve)
+        )?;
+        write!(f, "  keypairs: admin {}, user {}", self.admin, self.user)
+    }
+}
+
+const fn default_commitment() -> CommitmentLevel {
+    COMMITMENT_LEVEL
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+
+    use std::assert_matches;
+
+    use test_log::test;
+
+    use super::*;
+
+    /// The fields of a localnet profile, in TOML.
+    const LOCALNET: &str = r#"
+        rpc_url = "http://127.0.0.1:8899"
+        ws_url = "ws://127.0.0.1:8900"
+        commitment = "confirmed"
+        klend_program = "5Xs3m9xLbGFYY8C62PxuqAZjwmHnQuAzdjq6xtoKmVbF"
+        cpmm_program = "CPMDWBwJDtYax9qW7AyRuVC19Cc4L4Vcy4n2BHAbHkCW"
+        clmm_program = "devi51mZmdwUJGU9hjN27vEz64Gps7uUefqxg27EAtH"
+        amm_v4_program = "HWy1jotHpo6UqeQxx49dpYYdQB8wj9Qk9MdxwjLvDHB8"
+        cpmm_fee_receiver = "G11FKBRaAkHAKuLCgLM6K6NUc9rTjPAznRCjZifrTQe2"
+        bsol_mint = "bSo13r4TkiE4KumL71LsHTPpL2euBYLFx6h9HP3piy1"
+        market = "4vCvi2VqJbRSe33F9kfVQY6R8MX25eDK1f1TtuFngbwp"
+        admin = "admin.json"
+        user = "user.json"
+    "#;
+
+    #[test]
+    fn profiles_from_toml_and_json() -> core::result::Result<(), String> {
+        // Given
+        let toml = format!("[profiles.localnet]\n{LOCALNET}");
+        let json = r#"{"profiles": {"localnet": {
+            "rpc_url": "http://127.0.0.1:8899",
+            "ws_url": "ws://127.0.0.1:8900",
+            "commitment": "confirmed",
+            "klend_program": "5Xs3m9xLbGFYY8C62PxuqAZjwmHnQuAzdjq6xtoKmVbF",
+            "cpmm_program": "CPMDWBwJDtYax9qW7AyRuVC19Cc4L4Vcy4n2BHAbHkCW",
+            "clmm_program": "devi51mZmdwUJGU9hjN27vEz64Gps7uUefqxg27EAtH",
+            "amm_v4_program": "HWy1jotHpo6UqeQxx49dpYYdQB8wj9Qk9MdxwjLvDHB8",
+            "cpmm_fee_receiver": "G11FKBRaAkHAKuLCgLM6K6NUc9rTjPAznRCjZifrTQe2",
+            "bsol_mint": "bSo13r4TkiE4KumL71LsHTPpL2euBYLFx6h9HP3piy1",
+            "market": "4vCvi2VqJbRSe33F9kfVQY6R8MX25eDK1f1TtuFngbwp",
+            "admin": "admin.json",
+            "user": "user.json"
+        }}}"#;
+
+        // When
+        let from_toml = Profile::parse(&toml, false)?;
+        let from_json = Profile::parse(json, true)?;
+        let example = Profile::parse(include_str!("../../../config.toml"), false)?;
+
+        // Then
+        let localnet = Profile {
+            rpc_url: "http://127.0.0.1:8899".to_owned(),
+            ws_url: "ws://127.0.0.1:8900".to_owned(),
+            commitment: CommitmentLevel::Confirmed,
+            market: Some(pubkey!("4vCvi2VqJbRSe33F9kfVQY6R8MX25eDK1f1TtuFngbwp")),
+            wsol_source: None,
+            bsol_source: None,
+            pyth_product: None,
+            pyth_price: None,
+            ..Profile::default()
+        };
+        assert_eq!(from_toml.profiles.get("localnet"), Some(&localnet));
+        assert_eq!(from_json.profiles.get("localnet"), Some(&localnet));
+        assert_eq!(
+            example.profiles.get("devnet"),
+            Some(&Profile::default()),
+            "the example devnet profile is the default one"
+        );
+        for profile in example.profiles.values() {
+            assert_matches!(profile.validate(), Ok(()), "{profile}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_profiles() {
+        // Given
+        let unknown_field = format!("[profiles.localnet]\n{LOCALNET}\nrpc = \"http://127.0.0.1\"");
+        let invalid_address =
+            LOCALNET.replace("4vCvi2VqJbRSe33F9kfVQY6R8MX25eDK1f1TtuFngbwp", "nope");
+        let missing_field = LOCALNET.replace("klend_program", "# klend_program");
+        let missing_raydium = LOCALNET.replace("clmm_program", "# clmm_program");
+        let reserve = Some(Pubkey::new_unique());
+
+        // When
+        let wrong_scheme = Profile {
+            rpc_url: "wss://api.devnet.solana.com".to_owned(),
+            ..Profile::default()
+        };
+        let same_programs = Profile {
+            amm_v4_program: DEVNET_CPMM_PROGRAM,
+            ..Profile::default()
+        };
+        let same_reserves = Profile {
+            sol_reserve: reserve,
+            bsol_reserve: reserve,
+            ..Profile::default()
+        };
+
+        // Then
+        assert_matches!(
+            Profile::parse(&unknown_field, false),
+            Err(_),
+            "unknown field"
+        );
+        assert_matches!(
+            Profile::parse(&format!("[profiles.localnet]\n{invalid_address}"), false),
+            Err(_),
+            "invalid address"
+        );
+        assert_matches!(
+            Profile::parse(&format!("[profiles.localnet]\n{missing_field}"), false),
+            Err(_),
+            "missing program"
+        );
+        assert_matches!(
+            Profile::parse(&format!("[profiles.localnet]\n{missing_raydium}"), false),
+            Err(_),
+            "missing Raydium program"
+        );
+        assert_matches!(Profile::default().validate(), Ok(()), "devnet profile");
+        assert_matches!(
+            wrong_scheme.validate(),
+            Err(Error::Config(_)),
+            "RPC URL scheme"
+        );
+        assert_matches!(
+            same_programs.validate(),
+            Err(Error::Config(_)),
+            "same programs"
+        );
+        assert_matches!(
+            same_reserves.validate(),
+            Err(Error::Config(_)),
+            "same reserves"
+        );
+    }
+}
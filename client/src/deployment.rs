@@ -0,0 +1,426 @@
+//! The accounts created by `init`, persisted between the runs of the client.
+
+use core::fmt;
+use std::{fs, io::ErrorKind, path::Path};
+
+use ::klend::state::{LendingMarket, Reserve};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+use tracing::{info, instrument, warn};
+
+use crate::{
+    config::{Context, address},
+    error::{Error, Result},
+    klend::{init_lending_market, init_reserve, pda, state as klend_state},
+    lending::account_exists,
+};
+
+/// The lending market, reserves and pools deployed on a cluster.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Deployment {
+    /// The klend program owning the market and its reserves.
+    #[serde(with = "address")]
+    pub klend_program: Pubkey,
+    /// The lending market, if it has been created.
+    #[serde(
+        default,
+        with = "address::optional",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub market: Option<Pubkey>,
+    /// Whether the creation of the market was sent but not confirmed.
+    #[serde(default, skip_serializing_if = "core::ops::Not::not")]
+    pub market_pending: bool,
+    /// The reserves of the market.
+    #[serde(default)]
+    pub reserves: Vec<DeployedReserve>,
+    /// The Raydium pools used with the market.
+    #[serde(default)]
+    pub pools: Vec<DeployedPool>,
+}
+
+/// A reserve of the deployed lending market.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeployedReserve {
+    /// Mint of the tokens held by the reserve.
+    #[serde(with = "address")]
+    pub liquidity_mint: Pubkey,
+    /// The reserve account.
+    #[serde(with = "address")]
+    pub address: Pubkey,
+    /// Mint of the collateral tokens given in exchange for deposits.
+    #[serde(with = "address")]
+    pub collateral_mint: Pubkey,
+    /// Whether the creation of the reserve was sent but not confirmed.
+    #[serde(default, skip_serializing_if = "core::ops::Not::not")]
+    pub pending: bool,
+}
+
+/// A Raydium pool used with the deployed lending market.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeployedPool {
+    /// The Raydium program owning the pool.
+    #[serde(with = "address")]
+    pub program: Pubkey,
+    /// The pool state account.
+    #[serde(with = "address")]
+    pub address: Pubkey,
+    /// Mint of the LP tokens of the pool.
+    #[serde(with = "address")]
+    pub lp_mint: Pubkey,
+}
+
+impl Deployment {
+    /// An empty deployment, nothing having been created yet.
+    ///
+    /// # Parameters
+    /// * `klend_program` - The klend program the market will be created with.
+    pub const fn new(klend_program: Pubkey) -> Self {
+        Self {
+            klend_program,
+            market: None,
+            market_pending: false,
+            reserves: Vec::new(),
+            pools: Vec::new(),
+        }
+    }
+
+    /// Loads the state file of a deployment.
+    ///
+    /// # Parameters
+    /// * `path` - Path of the state file,
+    /// * `klend_program` - The klend program in use.
+    ///
+    /// # Returns
+    /// An empty deployment if the file does not exist.
+    ///
+    /// # Errors
+    /// If the file could not be read or parsed, or was written for another klend program.
+    #[expect(clippy::result_large_err)]
+    pub fn load(path: &Path, klend_program: Pubkey) -> Result<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Self::new(klend_program)),
+            Err(err) => {
+                return Err(Error::Deployment(format!(
+                    "could not read {}: {err}",
+                    path.display()
+                )));
+            }
+        };
+        let deployment: Self = serde_json::from_str(&content).map_err(|err| {
+            Error::Deployment(format!("could not parse {}: {err}", path.display()))
+        })?;
+        if deployment.klend_program != klend_program {
+            return Err(Error::Deployment(format!(
+                "{} was written for the klend program {}, not {klend_program}",
+                path.display(),
+                deployment.klend_program
+            )));
+        }
+
+        Ok(deployment)
+    }
+
+    /// Saves the deployment in its state file.
+    ///
+    /// The file is written next to its destination first, then renamed, so that an interrupted
+    /// write never loses the previous state.
+    ///
+    /// # Parameters
+    /// * `path` - Path of the state file.
+    ///
+    /// # Errors
+    /// If the file could not be written.
+    #[expect(clippy::result_large_err)]
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content =
+            serde_json::to_string_pretty(self).map_err(|err| Error::Deployment(err.to_string()))?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, format!("{content}\n"))
+            .and_then(|()| fs::rename(&tmp, path))
+            .map_err(|err| Error::Deployment(format!("could not write {}: {err}", path.display())))
+    }
+
+    /// The reserve of a mint, if it has been created.
+    pub fn reserve(&self, liquidity_mint: &Pubkey) -> Option<&DeployedReserve> {
+        self.reserves
+            .iter()
+            .find(|reserve| reserve.liquidity_mint == *liquidity_mint)
+    }
+
+    /// The first pool registered, if any.
+    pub fn pool(&self) -> Option<&DeployedPool> {
+        self.pools.first()
+    }
+
+    /// Registers a pool, replacing any pool with the same address.
+    pub fn add_pool(&mut self, pool: DeployedPool) {
+        self.pools.retain(|known| known.address != pool.address);
+        self.pools.push(pool);
+    }
+
+    /// Creates the lending market, or checks the one already created.
+    ///
+    /// A new market is recorded as pending before its creation is sent, so that it is found on
+    /// the next run if the confirmation times out; a pending market that does not exist is
+    /// created again. In dry-run mode, the creation is only simulated and nothing is recorded.
+    ///
+    /// # Parameters
+    /// * `ctx` - The cluster to use, its payer owning the market,
+    /// * `save` - Saves the state file.
+    ///
+    /// # Returns
+    /// The address of the market, `None` if its creation was only simulated.
+    ///
+    /// # Errors
+    /// If the market could not be created, or does not match the recorded one.
+    #[instrument(skip_all)]
+    pub async fn ensure_market<F>(&mut self, ctx: &Context, save: F) -> Result<Option<Pubkey>>
+    where
+        F: Fn(&Self) -> Result<()>,
+    {
+        if let Some(market) = self.market {
+            if !self.market_pending || account_exists(ctx, &market).await? {
+                let state = fetch_recorded::<LendingMarket>(ctx, &market, "lending market").await?;
+                if state.lending_market_owner != ctx.payer.pubkey() {
+                    return Err(Error::Deployment(format!(
+                        "the lending market {market} is owned by {}, not by the admin",
+                        state.lending_market_owner
+                    )));
+                }
+                self.market_pending = false;
+                info!(%market, "lending market already created");
+                return Ok(Some(market));
+            }
+            warn!(%market, "the creation of the lending market did not land");
+            self.market = None;
+            self.market_pending = false;
+        }
+
+        let market = Keypair::new();
+        if !ctx.options.dry_run {
+            self.market = Some(market.pubkey());
+            self.market_pending = true;
+            save(self)?;
+        }
+        init_lending_market(ctx, &ctx.payer, &market).await?;
+        if ctx.options.dry_run {
+            return Ok(None);
+        }
+        info!("Market address: {}", market.pubkey());
+        self.market_pending = false;
+        save(self)?;
+
+        Ok(Some(market.pubkey()))
+    }
+
+    /// Creates the reserve of a mint in the lending market, or checks the one already created.
+    ///
+    /// Like the market, a new reserve is recorded as pending before its creation is sent, and
+    /// nothing is recorded in dry-run mode.
+    ///
+    /// # Parameters
+    /// * `ctx` - The cluster to use, its payer owning the market,
+    /// * `liquidity_mint` - Mint of the tokens held by the reserve,
+    /// * `save` - Saves the state file.
+    ///
+    /// # Returns
+    /// The reserve, `None` if its creation was only simulated.
+    ///
+    /// # Errors
+    /// If the market has not been created, or if the reserve could not be created or does not
+    /// match the recorded one.
+    #[instrument(skip(self, ctx, save))]
+    pub async fn ensure_reserve<F>(
+        &mut self,
+        ctx: &Context,
+        liquidity_mint: Pubkey,
+        save: F,
+    ) -> Result<Option<DeployedReserve>>
+    where
+        F: Fn(&Self) -> Result<()>,
+    {
+        let market = self
+            .market
+            .filter(|_| !self.market_pending)
+            .ok_or_else(|| Error::Deployment("the lending market is not created".to_owned()))?;
+
+        if let Some(index) = self
+            .reserves
+            .iter()
+            .position(|reserve| reserve.liquidity_mint == liquidity_mint)
+        {
+            let reserve = self.reserves[index];
+            if !reserve.pending || account_exists(ctx, &reserve.address).await? {
+                let state = fetch_recorded::<Reserve>(ctx, &reserve.address, "reserve").await?;
+                reserve.check(&market, &state)?;
+                self.reserves[index].pending = false;
+                info!(reserve = %reserve.address, "reserve already created");
+                return Ok(Some(self.reserves[index]));
+            }
+            warn!(reserve = %reserve.address, "the creation of the reserve did not land");
+            self.reserves.remove(index);
+        }
+
+        let keypair = Keypair::new();
+        let mut reserve = DeployedReserve {
+            liquidity_mint,
+            address: keypair.pubkey(),
+            collateral_mint: pda::ReservePdas::new(&ctx.klend_program, &market, &liquidity_mint)
+                .collateral_mint,
+            pending: true,
+        };
+        if !ctx.options.dry_run {
+            self.reserves.push(reserve);
+            save(self)?;
+        }
+        init_reserve(ctx, &ctx.payer, market, &keypair, liquidity_mint).await?;
+        if ctx.options.dry_run {
+            return Ok(None);
+        }
+        info!("Reserve address: {}", reserve.address);
+        reserve.pending = false;
+        self.reserves
+            .retain(|known| known.liquidity_mint != liquidity_mint);
+        self.reserves.push(reserve);
+        save(self)?;
+
+        Ok(Some(reserve))
+    }
+}
+
+impl DeployedReserve {
+    /// Checks that the on-chain state of the reserve matches the recorded one.
+    ///
+    /// # Parameters
+    /// * `market` - The lending market the reserve belongs to,
+    /// * `state` - The decoded reserve account.
+    ///
+    /// # Errors
+    /// If the reserve belongs to another market, or holds other mints.
+    #[expect(clippy::result_large_err)]
+    pub fn check(&self, market: &Pubkey, state: &Reserve) -> Result<()> {
+        let fields = [
+            ("lending market", *market, state.lending_market),
+            (
+                "liquidity mint",
+                self.liquidity_mint,
+                state.liquidity.mint_pubkey,
+            ),
+            (
+                "collateral mint",
+                self.collateral_mint,
+                state.collateral.mint_pubkey,
+            ),
+        ];
+        for (name, expected, actual) in fields {
+            if expected != actual {
+                return Err(Error::Deployment(format!(
+                    "the {name} of the reserve {} is {actual}, {expected} was recorded",
+                    self.address
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Fetches a recorded account, which must still exist.
+async fn fetch_recorded<T>(ctx: &Context, address: &Pubkey, name: &str) -> Result<T>
+where
+    T: anchor_client::anchor_lang::AccountDeserialize + anchor_client::anchor_lang::Discriminator,
+{
+    if !account_exists(ctx, address).await? {
+        return Err(Error::Deployment(format!(
+            "the recorded {name} {address} does not exist, is the state file for another cluster?"
+        )));
+    }
+    klend_state::fetch(ctx, address).await
+}
+
+impl fmt::Display for Deployment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "klend program: {}", self.klend_program)?;
+        match self.market {
+            Some(market) => write!(f, ", market: {market}")?,
+            None => write!(f, ", no market")?,
+        }
+        for reserve in &self.reserves {
+            write!(
+                f,
+                ", reserve of {}: {} (collateral mint {})",
+                reserve.liquidity_mint, reserve.address, reserve.collateral_mint
+            )?;
+        }
+        for pool in &self.pools {
+            write!(
+                f,
+                ", pool {} of {} (LP mint {})",
+                pool.address, pool.program, pool.lp_mint
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use std::assert_matches;
+
+    use test_log::test;
+
+    use super::*;
+
+    type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
+
+    #[test]
+    fn state_file_round_trip() -> TestResult {
+        // Given
+        let dir = std::env::temp_dir().join(format!("deployment-{}", Pubkey::new_unique()));
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("deployment.json");
+        let program = Pubkey::new_unique();
+        let reserve = DeployedReserve {
+            liquidity_mint: Pubkey::new_unique(),
+            address: Pubkey::new_unique(),
+            collateral_mint: Pubkey::new_unique(),
+            pending: true,
+        };
+        let pool = DeployedPool {
+            program: Pubkey::new_unique(),
+            address: Pubkey::new_unique(),
+            lp_mint: Pubkey::new_unique(),
+        };
+
+        // When
+        let empty = Deployment::load(&path, program)?;
+        let mut deployment = Deployment {
+            market: Some(Pubkey::new_unique()),
+            market_pending: true,
+            reserves: vec![reserve],
+            ..empty.clone()
+        };
+        deployment.add_pool(pool);
+        deployment.add_pool(pool);
+        deployment.save(&path)?;
+        let loaded = Deployment::load(&path, program)?;
+        let other_program = Deployment::load(&path, Pubkey::new_unique());
+        fs::remove_dir_all(&dir)?;
+
+        // Then
+        assert_eq!(empty, Deployment::new(program));
+        assert_eq!(loaded, deployment);
+        assert_eq!(loaded.reserve(&reserve.liquidity_mint), Some(&reserve));
+        assert_eq!(loaded.reserve(&Pubkey::new_unique()), None);
+        assert_eq!(loaded.pools, vec![pool]);
+        assert_matches!(other_program, Err(Error::Deployment(_)));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+pub mod program;
+
+use std::io;
+
+use derive_more::derive::{Display, From};
+use program::{ProgramError, Programs};
+use solana_client::{
+    pubsub_client::PubsubClientError,
+    rpc_request::{RpcError, RpcResponseErrorData},
+};
+use solana_rpc_client_api::client_error;
+use solana_sdk::{
+    instruction::InstructionError, pubkey::Pubkey, signer::SignerError,
+    transaction::TransactionError,
+};
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Errors of the Save / Raydium application.
+#[derive(Debug, Display, From)]
+#[display("{_variant}")]
+pub enum Error {
+    #[display("invalid keypair")]
+    Keypair,
+    #[display("could not get the pubsub client: {_0}")]
+    #[from]
+    Pubsub(PubsubClientError),
+    /// A `RpcRequestError` or `ForUser` RPC error.
+    #[from]
+    #[display("error in the Solana RPC: {}", _0)]
+    Rpc(RpcError),
+    /// An error in the Solana Client
+    #[from]
+    #[display("error in the Solana Client: {}", _0)]
+    SolanaClient(client_error::Error),
+    /// Error happened while handling a specific instruction.
+    #[display(
+        "error in an instruction: {}\n{}",
+        program_error.map_or_else(|| error.to_string(), |program_error| program_error.to_string()),
+        logs.join("\n")
+    )]
+    SolanaInstruction {
+        error: InstructionError,
+        /// The error of the program, if it is a known one.
+        program_error: Option<ProgramError>,
+        logs: Vec<String>,
+    },
+    /// A transaction could not be signed.
+    #[from]
+    #[display("could not sign the transaction: {}", _0)]
+    Signer(SignerError),
+    /// Error happened at the transaction level.
+    #[from]
+    #[display("transaction error: {}", _0)]
+    SolanaTransaction(TransactionError),
+    /// Error while serializing or deserializing binary data with Borsh.
+    #[from]
+    #[display("Borsh could not (de)serialize data: {}", _0)]
+    BorshSerialization(io::Error),
+    /// An RPC error that is not handled by a more precise error.
+    #[display("misc Solana RPC error: {}", _0)]
+    RpcMisc(String),
+    /// The RPC failed to parse an account.
+    #[display("RPC failed to parse data: {}", _0)]
+    RpcParse(String),
+    /// An instruction could not be built.
+    #[display("could not build instruction: {}", _0)]
+    Instruction(String),
+    /// An account's data could not be decoded into the expected type.
+    #[display("could not decode account {address}: {reason}")]
+    AccountDecode { address: Pubkey, reason: String },
+    /// A value is out of the domain of a computation.
+    #[display("math error: {}", _0)]
+    Math(String),
+    /// The configuration file could not be loaded, or holds invalid values.
+    #[display("invalid configuration: {}", _0)]
+    Config(String),
+    /// An oracle price would be rejected: stale, too uncertain or not trading.
+    #[display("unusable oracle price: {}", _0)]
+    Oracle(String),
+    /// The health of the followed obligations could not be computed.
+    #[display("obligation monitoring failed: {}", _0)]
+    Monitor(String),
+    /// An obligation could not be deleveraged.
+    #[display("cannot deleverage the obligation: {}", _0)]
+    Protect(String),
+    /// An unhealthy obligation could not be liquidated.
+    #[display("cannot liquidate the obligation: {}", _0)]
+    Liquidation(String),
+    /// The deployment state file could not be used, or does not match the cluster.
+    #[display("invalid deployment state: {}", _0)]
+    Deployment(String),
+    /// A transaction was not confirmed before the expiry of any of its blockhashes.
+    #[display("transaction not confirmed after {} attempts", _0)]
+    TransactionExpired(u8),
+}
+
+impl Error {
+    /// Whether sending the transaction again might succeed.
+    ///
+    /// The transactions referencing an unknown blockhash, rejected by a busy cluster or lost by
+    /// the network can be sent again, those failing during their execution cannot.
+    pub const fn is_retryable(&self) -> bool {
+        match self {
+            Self::SolanaTransaction(error) => matches!(
+                error,
+                TransactionError::BlockhashNotFound
+                    | TransactionError::WouldExceedMaxBlockCostLimit
+                    | TransactionError::WouldExceedMaxAccountCostLimit
+                    | TransactionError::WouldExceedMaxVoteCostLimit
+                    | TransactionError::WouldExceedAccountDataBlockLimit
+                    | TransactionError::ClusterMaintenance
+            ),
+            Self::Rpc(
+                RpcError::RpcRequestError(_)
+                | RpcError::RpcResponseError {
+                    data: RpcResponseErrorData::NodeUnhealthy { .. },
+                    ..
+                },
+            )
+            | Self::RpcMisc(_)
+            | Self::TransactionExpired(_) => true,
+            _ => false,
+        }
+    }
+
+    /// The error, with the error of the failing program found from the logs if it is one of the
+    /// known programs.
+    ///
+    /// # Parameters
+    /// * `programs` - The programs of the context.
+    #[must_use]
+    pub fn with_program_error(self, programs: &Programs) -> Self {
+        match self {
+            Self::SolanaInstruction {
+                error,
+                program_error: None,
+                logs,
+            } => Self::SolanaInstruction {
+                program_error: ProgramError::from_logs(programs, &error, &logs),
+                error,
+                logs,
+            },
+            error => error,
+        }
+    }
+}
+
+impl core::error::Error for Error {}
@@ -0,0 +1,365 @@
+//! Flash loans: liquidity borrowed from a reserve and repaid in the same transaction.
+//!
+//! klend finds the repayment of a flash borrow, and the borrow of a flash repay, through the
+//! instructions sysvar: the repay instruction holds the index of the borrow one in the
+//! transaction, which the compute budget instructions put before all the others shift.
+
+use ::klend::state::Reserve;
+use anchor_client::anchor_lang::{Discriminator, InstructionData};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    sysvar,
+};
+use tracing::{debug, info, instrument};
+
+use super::{pda, refresh};
+use crate::{
+    config::Context,
+    error::{Error, Result},
+    lending::create_ata,
+    transaction::execute_instructions,
+};
+
+/// The scale of the fractions of klend (60 fractional bits).
+const FRACTION_ONE: u128 = 1 << 60;
+/// The flash loan fee of the reserves whose flash loans are disabled.
+const DISABLED_FEE_SF: u64 = u64::MAX;
+
+/// A flash loan of the liquidity of a reserve, wrapping the instructions using it.
+///
+/// The transaction holds, in order: the instructions to run before the loan (the refresh of the
+/// reserve, the creation of the user's token account…), the flash borrow, the wrapped
+/// instructions and the flash repay. The borrowed amount and the fees are taken back from the
+/// user's token account by the repay instruction.
+#[derive(Debug, Clone)]
+pub struct FlashLoan {
+    program: Pubkey,
+    lending_market: Pubkey,
+    reserve: Pubkey,
+    liquidity_mint: Pubkey,
+    supply_vault: Pubkey,
+    fee_vault: Pubkey,
+    fee_sf: u64,
+    user: Pubkey,
+    amount: u64,
+    before: Vec<Instruction>,
+    inner: Vec<Instruction>,
+}
+
+impl FlashLoan {
+    /// A flash loan without any instruction using it yet.
+    ///
+    /// # Parameters
+    /// * `program` - The klend program,
+    /// * `reserve` - Address of the reserve lending the liquidity,
+    /// * `state` - The reserve, giving its market, vaults and flash loan fee,
+    /// * `user` - Owner of the token account receiving and repaying the liquidity,
+    /// * `amount` - Amount of tokens borrowed.
+    pub const fn new(
+        program: Pubkey,
+        reserve: Pubkey,
+        state: &Reserve,
+        user: Pubkey,
+        amount: u64,
+    ) -> Self {
+        Self {
+            program,
+            lending_market: state.lending_market,
+            reserve,
+            liquidity_mint: state.liquidity.mint_pubkey,
+            supply_vault: state.liquidity.supply_vault,
+            fee_vault: state.liquidity.fee_vault,
+            fee_sf: state.config.fees.flash_loan_fee_sf,
+            user,
+            amount,
+            before: vec![],
+            inner: vec![],
+        }
+    }
+
+    /// Adds instructions to run before the flash borrow.
+    pub fn before<I: IntoIterator<Item = Instruction>>(mut self, instructions: I) -> Self {
+        self.before.extend(instructions);
+        self
+    }
+
+    /// Adds instructions to run with the borrowed liquidity, after those already added.
+    pub fn with<I: IntoIterator<Item = Instruction>>(mut self, instructions: I) -> Self {
+        self.inner.extend(instructions);
+        self
+    }
+
+    /// The fee of the loan, rounded up as klend does (at least one token).
+    ///
+    /// # Errors
+    /// If the flash loans of the reserve are disabled.
+    #[expect(clippy::result_large_err)]
+    pub fn fee(&self) -> Result<u64> {
+        if self.fee_sf == DISABLED_FEE_SF {
+            return Err(Error::Instruction(format!(
+                "the flash loans of the reserve {} are disabled",
+                self.reserve
+            )));
+        }
+        let fee = (u128::from(self.amount) * u128::from(self.fee_sf)).div_ceil(FRACTION_ONE);
+        u64::try_from(fee)
+            .map(|fee| fee.max(1))
+            .map_err(|_err| Error::Math(format!("flash loan fee of {fee} tokens")))
+    }
+
+    /// The amount taken back from the user's token account by the repay instruction.
+    ///
+    /// # Errors
+    /// If the flash loans of the reserve are disabled.
+    #[expect(clippy::result_large_err)]
+    pub fn repay_amount(&self) -> Result<u64> {
+        self.amount
+            .checked_add(self.fee()?)
+            .ok_or_else(|| Error::Math("flash loan repayment overflow".to_owned()))
+    }
+
+    /// Builds the instructions of the transaction, after checking their layout.
+    ///
+    /// # Parameters
+    /// * `offset` - Number of instructions put before them in the transaction (see
+    ///   [`TransactionOptions::compute_budget_instructions`](crate::transaction::TransactionOptions::compute_budget_instructions)).
+    ///
+    /// # Errors
+    /// If nothing is borrowed, the flash loans of the reserve are disabled, a wrapped instruction
+    /// is a klend flash loan itself, or the flash borrow is past the 256th instruction.
+    #[expect(clippy::result_large_err)]
+    pub fn instructions(&self, offset: usize) -> Result<Vec<Instruction>> {
+        if self.amount == 0 {
+            return Err(Error::Instruction("empty flash loan".to_owned()));
+        }
+        self.fee()?;
+        if let Some(nested) = self
+            .before
+            .iter()
+            .chain(&self.inner)
+            .find(|ix| self.is_flash(ix))
+        {
+            return Err(Error::Instruction(format!(
+                "nested klend flash loan instruction {:?}",
+                nested.data.get(..8)
+            )));
+        }
+        let index = offset + self.before.len();
+        let borrow_instruction_index = u8::try_from(index).map_err(|_err| {
+            Error::Instruction(format!("flash borrow at index {index}, at most 255"))
+        })?;
+        debug!(%borrow_instruction_index, inner = self.inner.len(), "flash loan layout");
+
+        let user_liquidity = create_ata(&self.user, &self.user, &self.liquidity_mint).0;
+        let borrow = Instruction::new_with_bytes(
+            self.program,
+            &::klend::instruction::FlashBorrowReserveLiquidity {
+                _liquidity_amount: self.amount,
+            }
+            .data(),
+            self.accounts(user_liquidity),
+        );
+        let repay = Instruction::new_with_bytes(
+            self.program,
+            &::klend::instruction::FlashRepayReserveLiquidity {
+                _liquidity_amount: self.amount,
+                _borrow_instruction_index: borrow_instruction_index,
+            }
+            .data(),
+            self.accounts(user_liquidity),
+        );
+
+        let mut instructions = self.before.clone();
+        instructions.push(borrow);
+        instructions.extend_from_slice(&self.inner);
+        instructions.push(repay);
+        Ok(instructions)
+    }
+
+    /// Executes the flash loan.
+    ///
+    /// The reserve is refreshed and the user's token account created (if needed) before the
+    /// loan.
+    ///
+    /// # Parameters
+    /// * `ctx` - The cluster and programs to use,
+    /// * `wallet` - The user of the loan (see [`Self::new`]),
+    /// * `signers` - Additional signers required by the wrapped instructions.
+    ///
+    /// # Errors
+    /// If the layout is invalid (see [`Self::instructions`]) or the transaction fails.
+    #[instrument(skip_all, fields(reserve = %self.reserve, amount = self.amount))]
+    pub async fn execute(
+        self,
+        ctx: &Context,
+        wallet: &Keypair,
+        signers: &[&Keypair],
+    ) -> Result<Signature> {
+        let mut setup = refresh::reserves_instructions(ctx, &[self.reserve]).await?;
+        setup.push(create_ata(&self.user, &self.user, &self.liquidity_mint).1);
+        let loan = Self {
+            before: setup.into_iter().chain(self.before).collect(),
+            ..self
+        };
+        info!(fee = loan.fee()?, "flash borrowing");
+
+        let instructions = loan.instructions(ctx.options.compute_budget_instructions())?;
+        let mut all_signers = vec![wallet];
+        all_signers.extend_from_slice(signers);
+        let sig = execute_instructions(ctx, &instructions, &all_signers).await?;
+        info!("Flash loan repaid: {sig}");
+
+        Ok(sig)
+    }
+
+    /// The accounts of the flash borrow and repay instructions.
+    ///
+    /// The published klend crate lacks the liquidity mint the program now takes.
+    fn accounts(&self, user_liquidity: Pubkey) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.user, true),
+            AccountMeta::new_readonly(
+                pda::lending_market_authority(&self.program, &self.lending_market),
+                false,
+            ),
+            AccountMeta::new_readonly(self.lending_market, false),
+            AccountMeta::new(self.reserve, false),
+            AccountMeta::new_readonly(self.liquidity_mint, false),
+            AccountMeta::new(self.supply_vault, false),
+            AccountMeta::new(user_liquidity, false),
+            AccountMeta::new(self.fee_vault, false),
+            // no referrer token state nor referrer account
+            AccountMeta::new_readonly(self.program, false),
+            AccountMeta::new_readonly(self.program, false),
+            AccountMeta::new_readonly(sysvar::instructions::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+        ]
+    }
+
+    /// Whether an instruction is a klend flash borrow or repay.
+    fn is_flash(&self, ix: &Instruction) -> bool {
+        ix.program_id == self.program
+            && [
+                ::klend::instruction::FlashBorrowReserveLiquidity::DISCRIMINATOR,
+                ::klend::instruction::FlashRepayReserveLiquidity::DISCRIMINATOR,
+            ]
+            .iter()
+            .any(|discriminator| ix.data.starts_with(discriminator))
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use std::assert_matches;
+
+    use anchor_client::anchor_lang::AnchorDeserialize;
+    use solana_sdk::system_instruction;
+    use test_log::test;
+
+    use super::*;
+
+    type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
+
+    /// A flash loan of `amount` tokens of a reserve charging 0.3%.
+    fn loan(amount: u64) -> FlashLoan {
+        FlashLoan {
+            program: Pubkey::new_unique(),
+            lending_market: Pubkey::new_unique(),
+            reserve: Pubkey::new_unique(),
+            liquidity_mint: Pubkey::new_unique(),
+            supply_vault: Pubkey::new_unique(),
+            fee_vault: Pubkey::new_unique(),
+            fee_sf: 3_458_764_513_820_540,
+            user: Pubkey::new_unique(),
+            amount,
+            before: vec![],
+            inner: vec![],
+        }
+    }
+
+    #[test]
+    fn flash_loan_fees() -> TestResult {
+        // Given
+        let disabled = FlashLoan {
+            fee_sf: DISABLED_FEE_SF,
+            ..loan(1_000)
+        };
+
+        // When
+        let fee = loan(1_000_000).fee()?;
+        let repay = loan(1_000_000).repay_amount()?;
+        let minimum = loan(1).fee()?;
+
+        // Then
+        assert_eq!(fee, 3_000);
+        assert_eq!(repay, 1_003_000);
+        assert_eq!(minimum, 1, "at least one token");
+        assert_matches!(disabled.fee(), Err(Error::Instruction(_)));
+        assert_matches!(disabled.instructions(0), Err(Error::Instruction(_)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn borrow_instruction_index() -> TestResult {
+        // Given
+        let payer = Pubkey::new_unique();
+        let transfer = system_instruction::transfer(&payer, &Pubkey::new_unique(), 1);
+        let loan = loan(1_000)
+            .before([transfer.clone()])
+            .with([transfer.clone(), transfer]);
+
+        // When
+        let instructions = loan.instructions(2)?;
+
+        // Then
+        let programs: Vec<_> = instructions.iter().map(|ix| ix.program_id).collect();
+        assert_eq!(
+            programs,
+            [
+                solana_sdk::system_program::ID,
+                loan.program,
+                solana_sdk::system_program::ID,
+                solana_sdk::system_program::ID,
+                loan.program,
+            ]
+        );
+        let mut data = &instructions[4].data[8..];
+        let repay = ::klend::instruction::FlashRepayReserveLiquidity::deserialize(&mut data)?;
+        assert_eq!(repay._liquidity_amount, 1_000);
+        assert_eq!(
+            repay._borrow_instruction_index, 3,
+            "after the budget and setup instructions"
+        );
+        assert_eq!(instructions[1].accounts, instructions[4].accounts);
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_layouts() {
+        // Given
+        let flash = loan(1_000);
+        let inner_loan = flash.instructions(0).unwrap_or_default();
+        let transfer =
+            system_instruction::transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 1);
+
+        // When
+        let empty = loan(0).instructions(0);
+        let nested = flash.clone().with(inner_loan).instructions(0);
+        let far = flash.with([transfer]).instructions(255);
+
+        // Then
+        assert_matches!(empty, Err(Error::Instruction(_)));
+        assert_matches!(nested, Err(Error::Instruction(_)));
+        assert_matches!(far, Ok(_), "borrow at index 255");
+        assert_matches!(
+            loan(1_000).instructions(256),
+            Err(Error::Instruction(_)),
+            "borrow past index 255"
+        );
+    }
+}
@@ -0,0 +1,610 @@
+pub mod flash_loan;
+pub mod obligation;
+pub mod pda;
+pub mod refresh;
+pub mod reserve_config;
+pub mod state;
+pub mod update;
+
+use ::klend::state::Reserve;
+use anchor_client::anchor_lang::{AnchorSerialize, Discriminator, InstructionData, ToAccountMetas};
+use anchor_client::solana_sdk::{pubkey::Pubkey, signature::Keypair, system_program, sysvar};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::Signer;
+use solana_sdk::{pubkey, system_instruction};
+use tracing::{info, instrument};
+
+use crate::config::Context;
+use crate::error::Result;
+use crate::lending::create_ata;
+use crate::transaction::{execute_instructions, process_rpc_error};
+use update::{LendingMarketUpdate, ReserveUpdate};
+
+/// Builds a klend instruction from its program, accounts and arguments.
+fn instruction<A: ToAccountMetas, D: InstructionData>(
+    program_id: &Pubkey,
+    accounts: &A,
+    args: &D,
+) -> Instruction {
+    Instruction::new_with_bytes(*program_id, &args.data(), accounts.to_account_metas(None))
+}
+
+/// Initializes a new lending market owned by `owner`.
+///
+/// # Parameters
+/// * `ctx` - The cluster and programs to use,
+/// * `owner` - Owner of the lending market,
+/// * `market` - Keypair of the lending market account to create.
+///
+/// # Errors
+/// If the transaction fails.
+#[instrument(skip_all, fields(market = %market.pubkey()))]
+pub async fn init_lending_market(
+    ctx: &Context,
+    owner: &Keypair,
+    market: &Keypair,
+) -> Result<Signature> {
+    const QUOTE_CURRENCY: &[u8; 32] =
+        b"USD\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0";
+    const SPACE: usize = size_of::<klend::state::LendingMarket>() + 8;
+
+    let market_authority = pda::lending_market_authority(&ctx.klend_program, &market.pubkey());
+    info!("Market authority: {}", market_authority);
+
+    let rent_exempt_balance = ctx
+        .rpc
+        .get_minimum_balance_for_rent_exemption(SPACE)
+        .await
+        .map_err(process_rpc_error)?;
+
+    // Create the account
+    let create_account_ix = system_instruction::create_account(
+        &owner.pubkey(),
+        &market.pubkey(),
+        rent_exempt_balance,
+        SPACE as u64,
+        &ctx.klend_program,
+    );
+
+    let init_ix = instruction(
+        &ctx.klend_program,
+        &klend::accounts::InitLendingMarket {
+            lending_market_owner: owner.pubkey(),
+            lending_market: market.pubkey(),
+            lending_market_authority: market_authority,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        &klend::instruction::InitLendingMarket {
+            _quote_currency: *QUOTE_CURRENCY,
+        },
+    );
+
+    let sig = execute_instructions(ctx, &[create_account_ix, init_ix], &[owner, market]).await?;
+    info!("Lending Market Initialized: {sig}");
+
+    Ok(sig)
+}
+
+/// Updates a field of a lending market.
+///
+/// # Parameters
+/// * `ctx` - The cluster and programs to use,
+/// * `wallet` - Owner of the lending market,
+/// * `lending_market` - Market to update,
+/// * `update` - The field to update and its new value.
+///
+/// # Errors
+/// If the value is invalid or the transaction fails.
+#[instrument(skip(ctx, wallet))]
+pub async fn update_lending_market(
+    ctx: &Context,
+    wallet: &Keypair,
+    lending_market: Pubkey,
+    update: &LendingMarketUpdate,
+) -> Result<Signature> {
+    let ix = instruction(
+        &ctx.klend_program,
+        &klend::accounts::UpdateLendingMarket {
+            lending_market_owner: wallet.pubkey(),
+            lending_market,
+        },
+        &klend::instruction::UpdateLendingMarket {
+            _mode: update.mode(),
+            _value: update.value()?,
+        },
+    );
+    let sig = execute_instructions(ctx, &[ix], &[wallet]).await?;
+    info!("Lending Market Updated: {sig}");
+
+    Ok(sig)
+}
+
+/// Arguments of the `update_reserve_config` instruction.
+///
+/// The published klend crate still encodes the value as a fixed size array, the program now
+/// takes a vector and whether to skip the validation of the resulting configuration.
+#[derive(AnchorSerialize)]
+struct UpdateReserveConfig {
+    mode: u64,
+    value: Vec<u8>,
+    skip_validation: bool,
+}
+
+impl Discriminator for UpdateReserveConfig {
+    const DISCRIMINATOR: [u8; 8] = klend::instruction::UpdateReserveConfig::DISCRIMINATOR;
+}
+
+impl InstructionData for UpdateReserveConfig {}
+
+/// Builds the instruction updating a field of the configuration of a reserve.
+///
+/// # Parameters
+/// * `program_id` - The klend program,
+/// * `owner` - Owner of the lending market,
+/// * `lending_market` - Market of the reserve,
+/// * `reserve` - Reserve to update,
+/// * `update` - The field to update and its new value,
+/// * `skip_validation` - Whether the program skips the validation of the resulting
+///   configuration, only allowed on reserves without liquidity.
+///
+/// # Errors
+/// If the value is invalid.
+#[expect(clippy::result_large_err)]
+pub fn update_reserve_config_instruction(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    lending_market: Pubkey,
+    reserve: Pubkey,
+    update: &ReserveUpdate,
+    skip_validation: bool,
+) -> Result<Instruction> {
+    Ok(instruction(
+        program_id,
+        &klend::accounts::UpdateReserveConfig {
+            lending_market_owner: *owner,
+            lending_market,
+            reserve,
+        },
+        &UpdateReserveConfig {
+            mode: update.mode(),
+            value: update.value()?,
+            skip_validation,
+        },
+    ))
+}
+
+/// Updates a field of the configuration of a reserve.
+///
+/// # Parameters
+/// * `ctx` - The cluster and programs to use,
+/// * `wallet` - Owner of the lending market,
+/// * `lending_market` - Market of the reserve,
+/// * `reserve` - Reserve to update,
+/// * `update` - The field to update and its new value.
+///
+/// # Errors
+/// If the value is invalid or the transaction fails.
+#[instrument(skip(ctx, wallet))]
+pub async fn update_reserve_config(
+    ctx: &Context,
+    wallet: &Keypair,
+    lending_market: Pubkey,
+    reserve: Pubkey,
+    update: &ReserveUpdate,
+) -> Result<Signature> {
+    let ix = update_reserve_config_instruction(
+        &ctx.klend_program,
+        &wallet.pubkey(),
+        lending_market,
+        reserve,
+        update,
+        false,
+    )?;
+    let sig = execute_instructions(ctx, &[ix], &[wallet]).await?;
+    info!("Reserve Config Updated: {sig}");
+
+    Ok(sig)
+}
+
+/// Initializes a new reserve in a lending market.
+///
+/// # Parameters
+/// * `ctx` - The cluster and programs to use,
+/// * `wallet` - Owner of the lending market,
+/// * `lending_market` - Market in which the reserve is created,
+/// * `reserve` - Keypair of the reserve account to create,
+/// * `reserve_mint` - Mint of the tokens held by the reserve.
+///
+/// # Errors
+/// If the transaction fails.
+#[instrument(skip(ctx, wallet, reserve), fields(reserve = %reserve.pubkey()))]
+pub async fn init_reserve(
+    ctx: &Context,
+    wallet: &Keypair,
+    lending_market: Pubkey,
+    reserve: &Keypair,
+    reserve_mint: Pubkey,
+) -> Result<Signature> {
+    const SPACE: usize = size_of::<klend::state::Reserve>() + 8;
+
+    let pdas = pda::ReservePdas::new(&ctx.klend_program, &lending_market, &reserve_mint);
+    let rent_exempt_balance = ctx
+        .rpc
+        .get_minimum_balance_for_rent_exemption(SPACE)
+        .await
+        .map_err(process_rpc_error)?;
+
+    let create_account_ix = system_instruction::create_account(
+        &wallet.pubkey(),
+        &reserve.pubkey(),
+        rent_exempt_balance,
+        SPACE as u64,
+        &ctx.klend_program,
+    );
+    let ix = instruction(
+        &ctx.klend_program,
+        &klend::accounts::InitReserve {
+            lending_market_owner: wallet.pubkey(),
+            lending_market,
+            lending_market_authority: pda::lending_market_authority(
+                &ctx.klend_program,
+                &lending_market,
+            ),
+            reserve: reserve.pubkey(),
+            reserve_liquidity_mint: reserve_mint,
+            reserve_liquidity_supply: pdas.liquidity_supply,
+            fee_receiver: pdas.fee_receiver,
+            reserve_collateral_mint: pdas.collateral_mint,
+            reserve_collateral_supply: pdas.collateral_supply,
+            rent: sysvar::rent::ID,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        &klend::instruction::InitReserve {},
+    );
+    let sig = execute_instructions(ctx, &[create_account_ix, ix], &[wallet, reserve]).await?;
+    info!("Reserve Initialized: {sig}");
+
+    Ok(sig)
+}
+
+/// Deposits liquidity in a reserve in exchange for collateral tokens.
+///
+/// The reserve is refreshed in the same transaction.
+///
+/// # Parameters
+/// * `ctx` - The cluster and programs to use,
+/// * `wallet` - Owner of the deposited tokens,
+/// * `lending_market` - Market of the reserve,
+/// * `reserve` - Reserve in which the liquidity is deposited,
+/// * `liquidity_mint` - Mint of the deposited tokens,
+/// * `amount` - Amount of tokens to deposit.
+///
+/// # Errors
+/// If the transaction fails.
+#[instrument(skip(ctx, wallet))]
+pub async fn lend(
+    ctx: &Context,
+    wallet: &Keypair,
+    lending_market: Pubkey,
+    reserve: Pubkey,
+    liquidity_mint: Pubkey,
+    amount: u64,
+) -> Result<Signature> {
+    let pdas = pda::ReservePdas::new(&ctx.klend_program, &lending_market, &liquidity_mint);
+    let user_source_liquidity = create_ata(&wallet.pubkey(), &wallet.pubkey(), &liquidity_mint).0;
+    let (user_destination_collateral, create_ata_ix) =
+        create_ata(&wallet.pubkey(), &wallet.pubkey(), &pdas.collateral_mint);
+    let ix = instruction(
+        &ctx.klend_program,
+        &klend::accounts::DepositReserveLiquidity {
+            owner: wallet.pubkey(),
+            reserve,
+            lending_market,
+            lending_market_authority: pda::lending_market_authority(
+                &ctx.klend_program,
+                &lending_market,
+            ),
+            reserve_liquidity_supply: pdas.liquidity_supply,
+            reserve_collateral_mint: pdas.collateral_mint,
+            user_source_liquidity,
+            user_destination_collateral,
+            token_program: spl_token::ID,
+        },
+        &klend::instruction::DepositReserveLiquidity {
+            _liquidity_amount: amount,
+        },
+    );
+    let mut instructions = refresh::reserves_instructions(ctx, &[reserve]).await?;
+    instructions.extend([create_ata_ix, ix]);
+    let sig = execute_instructions(ctx, &instructions, &[wallet]).await?;
+    info!("Lent: {sig}");
+
+    Ok(sig)
+}
+
+/// Borrows liquidity from a reserve against the collateral of an obligation.
+///
+/// The obligation and its reserves are refreshed in the same transaction.
+///
+/// # Parameters
+/// * `ctx` - The cluster and programs to use,
+/// * `wallet` - Owner of the obligation,
+/// * `lending_market` - Market of the obligation,
+/// * `obligation` - Obligation to borrow against,
+/// * `borrow_reserve` - Reserve to borrow from,
+/// * `liquidity_mint` - Mint of the borrowed tokens,
+/// * `amount` - Amount of tokens to borrow.
+///
+/// # Errors
+/// If the transaction fails.
+#[instrument(skip(ctx, wallet))]
+pub async fn borrow(
+    ctx: &Context,
+    wallet: &Keypair,
+    lending_market: Pubkey,
+    obligation: Pubkey,
+    borrow_reserve: Pubkey,
+    liquidity_mint: Pubkey,
+    amount: u64,
+) -> Result<Signature> {
+    let pdas = pda::ReservePdas::new(&ctx.klend_program, &lending_market, &liquidity_mint);
+    let (user_destination_liquidity, create_ata_ix) =
+        create_ata(&wallet.pubkey(), &wallet.pubkey(), &liquidity_mint);
+    let ix = instruction(
+        &ctx.klend_program,
+        &klend::accounts::BorrowObligationLiquidity {
+            owner: wallet.pubkey(),
+            obligation,
+            lending_market,
+            lending_market_authority: pda::lending_market_authority(
+                &ctx.klend_program,
+                &lending_market,
+            ),
+            borrow_reserve,
+            reserve_source_liquidity: pdas.liquidity_supply,
+            borrow_reserve_liquidity_fee_receiver: pdas.fee_receiver,
+            user_destination_liquidity,
+            referrer_token_state: ctx.klend_program,
+            token_program: spl_token::ID,
+            instruction_sysvar_account: sysvar::instructions::ID,
+        },
+        &klend::instruction::BorrowObligationLiquidity {
+            _liquidity_amount: amount,
+        },
+    );
+    let mut instructions =
+        refresh::obligation_instructions(ctx, obligation, &[borrow_reserve]).await?;
+    instructions.extend([create_ata_ix, ix]);
+    let sig = execute_instructions(ctx, &instructions, &[wallet]).await?;
+    info!("Borrowed: {sig}");
+
+    Ok(sig)
+}
+
+/// Builds the instruction repaying liquidity borrowed by an obligation.
+///
+/// The obligation and its reserves must be refreshed before, in the same transaction.
+///
+/// # Parameters
+/// * `program_id` - The klend program,
+/// * `owner` - Owner of the obligation, holding the repaid tokens,
+/// * `lending_market` - Market of the obligation,
+/// * `obligation` - Obligation to repay,
+/// * `repay_reserve` - Reserve the liquidity was borrowed from,
+/// * `liquidity_mint` - Mint of the repaid tokens,
+/// * `amount` - Amount of tokens to repay (`u64::MAX` repays everything).
+pub fn repay_instruction(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    lending_market: Pubkey,
+    obligation: Pubkey,
+    repay_reserve: Pubkey,
+    liquidity_mint: Pubkey,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        program_id,
+        &klend::accounts::RepayObligationLiquidity {
+            owner: *owner,
+            obligation,
+            lending_market,
+            repay_reserve,
+            reserve_destination_liquidity: pda::ReservePdas::new(
+                program_id,
+                &lending_market,
+                &liquidity_mint,
+            )
+            .liquidity_supply,
+            user_source_liquidity: create_ata(owner, owner, &liquidity_mint).0,
+            token_program: spl_token::ID,
+            instruction_sysvar_account: sysvar::instructions::ID,
+        },
+        &klend::instruction::RepayObligationLiquidity {
+            _liquidity_amount: amount,
+        },
+    )
+}
+
+/// The Kamino farms program, called by klend to stake the collateral and debt of obligations.
+///
+/// It is compiled in the klend program, on every cluster.
+pub const FARMS_PROGRAM_ID: Pubkey = pubkey!("FarmsPZpWu9i7Kky8tPN37rs2TpmMrAZrC7S7vJa91Hr");
+
+/// Arguments of the `liquidate_obligation_and_redeem_reserve_collateral_v2` instruction.
+///
+/// The published klend crate predates it (and the accounts it added to the first version), so
+/// it is built from `klend_idl.json`.
+#[derive(AnchorSerialize)]
+struct LiquidateObligationAndRedeemReserveCollateralV2 {
+    liquidity_amount: u64,
+    min_acceptable_received_liquidity_amount: u64,
+    max_allowed_ltv_override_percent: u64,
+}
+
+impl Discriminator for LiquidateObligationAndRedeemReserveCollateralV2 {
+    const DISCRIMINATOR: [u8; 8] = [162, 161, 35, 143, 30, 187, 185, 103];
+}
+
+impl InstructionData for LiquidateObligationAndRedeemReserveCollateralV2 {}
+
+/// The reserves of a liquidation, by address.
+#[derive(Clone, Copy)]
+pub struct LiquidationReserves<'state> {
+    /// Reserve the liquidity was borrowed from.
+    pub repay: (Pubkey, &'state Reserve),
+    /// Reserve of the collateral seized.
+    pub withdraw: (Pubkey, &'state Reserve),
+}
+
+/// The farm accounts of an obligation in a reserve, updated by a liquidation.
+///
+/// A reserve without farm takes the klend program in place of both accounts, as Anchor does for
+/// missing optional accounts.
+fn farm_accounts(program_id: &Pubkey, obligation: &Pubkey, farm: &Pubkey) -> [AccountMeta; 2] {
+    if *farm == Pubkey::default() {
+        return [
+            AccountMeta::new_readonly(*program_id, false),
+            AccountMeta::new_readonly(*program_id, false),
+        ];
+    }
+    [
+        AccountMeta::new(
+            pda::obligation_farm_user_state(&FARMS_PROGRAM_ID, farm, obligation),
+            false,
+        ),
+        AccountMeta::new(*farm, false),
+    ]
+}
+
+/// Builds the instructions liquidating an unhealthy obligation: part of its debt is repaid in
+/// exchange for its collateral (with a bonus), redeemed for the liquidity of its reserve. The
+/// token accounts of the liquidator are created if needed.
+///
+/// The liquidation uses `liquidateObligationAndRedeemReserveCollateralV2`, which also updates
+/// the farms of the collateral and of the debt.
+///
+/// The obligation and its reserves must be refreshed before, in the same transaction.
+///
+/// # Parameters
+/// * `program_id` - The klend program,
+/// * `liquidator` - Owner of the repaid tokens, receiving the collateral,
+/// * `lending_market` - Market of the obligation,
+/// * `obligation` - Obligation to liquidate,
+/// * `reserves` - The reserves repaid and withdrawn,
+/// * `amount` - Amount of tokens to repay,
+/// * `min_liquidity_amount` - Minimum amount of liquidity to receive for the collateral.
+pub fn liquidate_instructions(
+    program_id: &Pubkey,
+    liquidator: &Pubkey,
+    lending_market: Pubkey,
+    obligation: Pubkey,
+    reserves: LiquidationReserves<'_>,
+    amount: u64,
+    min_liquidity_amount: u64,
+) -> Vec<Instruction> {
+    let (repay_reserve, repay) = reserves.repay;
+    let (withdraw_reserve, withdraw) = reserves.withdraw;
+    let (user_source_liquidity, create_source_ata) =
+        create_ata(liquidator, liquidator, &repay.liquidity.mint_pubkey);
+    let (user_destination_collateral, create_collateral_ata) =
+        create_ata(liquidator, liquidator, &withdraw.collateral.mint_pubkey);
+    let (user_destination_liquidity, create_liquidity_ata) =
+        create_ata(liquidator, liquidator, &withdraw.liquidity.mint_pubkey);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*liquidator, true),
+        AccountMeta::new(obligation, false),
+        AccountMeta::new_readonly(lending_market, false),
+        AccountMeta::new_readonly(
+            pda::lending_market_authority(program_id, &lending_market),
+            false,
+        ),
+        AccountMeta::new(repay_reserve, false),
+        AccountMeta::new_readonly(repay.liquidity.mint_pubkey, false),
+        AccountMeta::new(repay.liquidity.supply_vault, false),
+        AccountMeta::new(withdraw_reserve, false),
+        AccountMeta::new_readonly(withdraw.liquidity.mint_pubkey, false),
+        AccountMeta::new(withdraw.collateral.mint_pubkey, false),
+        AccountMeta::new(withdraw.collateral.supply_vault, false),
+        AccountMeta::new(withdraw.liquidity.supply_vault, false),
+        AccountMeta::new(withdraw.liquidity.fee_vault, false),
+        AccountMeta::new(user_source_liquidity, false),
+        AccountMeta::new(user_destination_collateral, false),
+        AccountMeta::new(user_destination_liquidity, false),
+        // collateral, repaid liquidity and withdrawn liquidity token programs
+        AccountMeta::new_readonly(spl_token::ID, false),
+        AccountMeta::new_readonly(spl_token::ID, false),
+        AccountMeta::new_readonly(spl_token::ID, false),
+        AccountMeta::new_readonly(sysvar::instructions::ID, false),
+    ];
+    accounts.extend(farm_accounts(
+        program_id,
+        &obligation,
+        &withdraw.farm_collateral,
+    ));
+    accounts.extend(farm_accounts(program_id, &obligation, &repay.farm_debt));
+    accounts.push(AccountMeta::new_readonly(FARMS_PROGRAM_ID, false));
+    let ix = Instruction::new_with_bytes(
+        *program_id,
+        &LiquidateObligationAndRedeemReserveCollateralV2 {
+            liquidity_amount: amount,
+            min_acceptable_received_liquidity_amount: min_liquidity_amount,
+            max_allowed_ltv_override_percent: 0,
+        }
+        .data(),
+        accounts,
+    );
+
+    vec![
+        create_source_ata,
+        create_collateral_ata,
+        create_liquidity_ata,
+        ix,
+    ]
+}
+
+/// Repays liquidity borrowed by an obligation.
+///
+/// The obligation and its reserves are refreshed in the same transaction.
+///
+/// # Parameters
+/// * `ctx` - The cluster and programs to use,
+/// * `wallet` - Owner of the obligation,
+/// * `lending_market` - Market of the obligation,
+/// * `obligation` - Obligation to repay,
+/// * `repay_reserve` - Reserve the liquidity was borrowed from,
+/// * `liquidity_mint` - Mint of the repaid tokens,
+/// * `amount` - Amount of tokens to repay (`u64::MAX` repays everything).
+///
+/// # Errors
+/// If the transaction fails.
+#[instrument(skip(ctx, wallet))]
+pub async fn repay(
+    ctx: &Context,
+    wallet: &Keypair,
+    lending_market: Pubkey,
+    obligation: Pubkey,
+    repay_reserve: Pubkey,
+    liquidity_mint: Pubkey,
+    amount: u64,
+) -> Result<Signature> {
+    let ix = repay_instruction(
+        &ctx.klend_program,
+        &wallet.pubkey(),
+        lending_market,
+        obligation,
+        repay_reserve,
+        liquidity_mint,
+        amount,
+    );
+    let mut instructions =
+        refresh::obligation_instructions(ctx, obligation, &[repay_reserve]).await?;
+    instructions.push(ix);
+    let sig = execute_instructions(ctx, &instructions, &[wallet]).await?;
+    info!("Repaid: {sig}");
+
+    Ok(sig)
+}
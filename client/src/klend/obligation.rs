@@ -0,0 +1,330 @@
+use anchor_client::solana_sdk::{pubkey::Pubkey, signature::Keypair, system_program, sysvar};
+use klend::InitObligationArgs;
+use klend::state::{Obligation, Reserve};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::Signer;
+use tracing::{debug, info, instrument};
+
+use super::{instruction, pda, refresh, repay, state};
+use crate::config::Context;
+use crate::error::Result;
+use crate::lending::{account_exists, create_ata};
+use crate::transaction::execute_instructions;
+
+/// The instruction creating the metadata of a user, if it does not exist yet.
+///
+/// The metadata is required by (and shared between) all the obligations of a user.
+///
+/// # Parameters
+/// * `ctx` - The cluster and programs to use,
+/// * `owner` - The user.
+///
+/// # Errors
+/// If the RPC could not be reached.
+#[instrument(skip_all, fields(owner = %owner))]
+async fn init_user_metadata_instruction(
+    ctx: &Context,
+    owner: &Pubkey,
+) -> Result<Option<Instruction>> {
+    let user_metadata = pda::user_metadata(&ctx.klend_program, owner);
+    if account_exists(ctx, &user_metadata).await? {
+        debug!(%user_metadata, "user metadata already exists");
+        return Ok(None);
+    }
+
+    Ok(Some(instruction(
+        &ctx.klend_program,
+        &klend::accounts::InitUserMetadata {
+            owner: *owner,
+            user_metadata,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        &klend::instruction::InitUserMetadata {
+            _referrer: Pubkey::default(),
+            _user_lookup_table: Pubkey::default(),
+        },
+    )))
+}
+
+/// Creates an obligation (and the user metadata it requires), unless it already exists.
+///
+/// # Parameters
+/// * `ctx` - The cluster and programs to use,
+/// * `owner` - Owner of the obligation, paying for the accounts,
+/// * `lending_market` - Market of the obligation,
+/// * `seeds` - Tag, id and seed accounts of the obligation.
+///
+/// # Returns
+/// The address of the obligation.
+///
+/// # Errors
+/// If the transaction fails.
+#[instrument(skip(ctx, owner), fields(owner = %owner.pubkey()))]
+pub async fn init_obligation(
+    ctx: &Context,
+    owner: &Keypair,
+    lending_market: Pubkey,
+    seeds: &pda::ObligationSeeds,
+) -> Result<Pubkey> {
+    let obligation = pda::obligation(&ctx.klend_program, &owner.pubkey(), &lending_market, seeds);
+    if account_exists(ctx, &obligation).await? {
+        info!(%obligation, "Obligation already exists");
+        return Ok(obligation);
+    }
+
+    let mut instructions: Vec<_> = init_user_metadata_instruction(ctx, &owner.pubkey())
+        .await?
+        .into_iter()
+        .collect();
+    instructions.push(instruction(
+        &ctx.klend_program,
+        &klend::accounts::InitObligation {
+            obligation_owner: owner.pubkey(),
+            obligation,
+            lending_market,
+            seed1_account: seeds.seed1,
+            seed2_account: seeds.seed2,
+            owner_user_metadata: pda::user_metadata(&ctx.klend_program, &owner.pubkey()),
+            rent: sysvar::rent::ID,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        &klend::instruction::InitObligation {
+            _args: InitObligationArgs {
+                tag: seeds.tag,
+                id: seeds.id,
+            },
+        },
+    ));
+    let sig = execute_instructions(ctx, &instructions, &[owner]).await?;
+    info!(%obligation, "Obligation initialized: {sig}");
+
+    Ok(obligation)
+}
+
+/// Deposits collateral tokens of a reserve in an obligation.
+///
+/// The obligation and its reserves are refreshed in the same transaction.
+///
+/// # Parameters
+/// * `ctx` - The cluster and programs to use,
+/// * `owner` - Owner of the obligation and of the collateral tokens,
+/// * `lending_market` - Market of the obligation,
+/// * `obligation` - Obligation receiving the collateral,
+/// * `reserve` - Reserve of the collateral,
+/// * `liquidity_mint` - Mint of the reserve's liquidity,
+/// * `amount` - Amount of collateral tokens to deposit.
+///
+/// # Errors
+/// If the transaction fails.
+#[instrument(skip(ctx, owner))]
+pub async fn deposit_collateral(
+    ctx: &Context,
+    owner: &Keypair,
+    lending_market: Pubkey,
+    obligation: Pubkey,
+    reserve: Pubkey,
+    liquidity_mint: Pubkey,
+    amount: u64,
+) -> Result<Signature> {
+    let pdas = pda::ReservePdas::new(&ctx.klend_program, &lending_market, &liquidity_mint);
+    let ix = instruction(
+        &ctx.klend_program,
+        &klend::accounts::DepositObligationCollateral {
+            owner: owner.pubkey(),
+            obligation,
+            lending_market,
+            deposit_reserve: reserve,
+            reserve_destination_collateral: pdas.collateral_supply,
+            user_source_collateral: create_ata(
+                &owner.pubkey(),
+                &owner.pubkey(),
+                &pdas.collateral_mint,
+            )
+            .0,
+            token_program: spl_token::ID,
+            instruction_sysvar_account: sysvar::instructions::ID,
+        },
+        &klend::instruction::DepositObligationCollateral {
+            _collateral_amount: amount,
+        },
+    );
+    let mut instructions = refresh::obligation_instructions(ctx, obligation, &[reserve]).await?;
+    instructions.push(ix);
+    let sig = execute_instructions(ctx, &instructions, &[owner]).await?;
+    info!("Collateral deposited: {sig}");
+
+    Ok(sig)
+}
+
+/// Withdraws collateral tokens of a reserve from an obligation.
+///
+/// The obligation and its reserves are refreshed in the same transaction.
+///
+/// # Parameters
+/// * `ctx` - The cluster and programs to use,
+/// * `owner` - Owner of the obligation,
+/// * `lending_market` - Market of the obligation,
+/// * `obligation` - Obligation holding the collateral,
+/// * `reserve` - Reserve of the collateral,
+/// * `liquidity_mint` - Mint of the reserve's liquidity,
+/// * `amount` - Amount of collateral tokens to withdraw (`u64::MAX` withdraws everything).
+///
+/// # Errors
+/// If the transaction fails.
+#[instrument(skip(ctx, owner))]
+pub async fn withdraw_collateral(
+    ctx: &Context,
+    owner: &Keypair,
+    lending_market: Pubkey,
+    obligation: Pubkey,
+    reserve: Pubkey,
+    liquidity_mint: Pubkey,
+    amount: u64,
+) -> Result<Signature> {
+    let pdas = pda::ReservePdas::new(&ctx.klend_program, &lending_market, &liquidity_mint);
+    let (user_destination_collateral, create_ata_ix) =
+        create_ata(&owner.pubkey(), &owner.pubkey(), &pdas.collateral_mint);
+    let ix = instruction(
+        &ctx.klend_program,
+        &klend::accounts::WithdrawObligationCollateral {
+            owner: owner.pubkey(),
+            obligation,
+            lending_market,
+            lending_market_authority: pda::lending_market_authority(
+                &ctx.klend_program,
+                &lending_market,
+            ),
+            withdraw_reserve: reserve,
+            reserve_source_collateral: pdas.collateral_supply,
+            user_destination_collateral,
+            token_program: spl_token::ID,
+            instruction_sysvar_account: sysvar::instructions::ID,
+        },
+        &klend::instruction::WithdrawObligationCollateral {
+            _collateral_amount: amount,
+        },
+    );
+    let mut instructions = refresh::obligation_instructions(ctx, obligation, &[reserve]).await?;
+    instructions.extend([create_ata_ix, ix]);
+    let sig = execute_instructions(ctx, &instructions, &[owner]).await?;
+    info!("Collateral withdrawn: {sig}");
+
+    Ok(sig)
+}
+
+/// Builds the instructions withdrawing collateral from an obligation and redeeming it for the
+/// liquidity of its reserve, creating the token accounts if needed.
+///
+/// The obligation and its reserves must be refreshed before, in the same transaction.
+///
+/// # Parameters
+/// * `program_id` - The klend program,
+/// * `owner` - Owner of the obligation, receiving the liquidity,
+/// * `lending_market` - Market of the obligation,
+/// * `obligation` - Obligation holding the collateral,
+/// * `reserve` - Reserve of the collateral,
+/// * `liquidity_mint` - Mint of the reserve's liquidity,
+/// * `amount` - Amount of collateral tokens to withdraw (`u64::MAX` withdraws everything).
+pub fn withdraw_and_redeem_instructions(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    lending_market: Pubkey,
+    obligation: Pubkey,
+    reserve: Pubkey,
+    liquidity_mint: Pubkey,
+    amount: u64,
+) -> Vec<Instruction> {
+    let pdas = pda::ReservePdas::new(program_id, &lending_market, &liquidity_mint);
+    let (user_destination_collateral, create_collateral_ata) =
+        create_ata(owner, owner, &pdas.collateral_mint);
+    let (user_destination_liquidity, create_liquidity_ata) =
+        create_ata(owner, owner, &liquidity_mint);
+    let ix = instruction(
+        program_id,
+        &klend::accounts::WithdrawObligationCollateralAndRedeemReserveCollateral {
+            owner: *owner,
+            obligation,
+            lending_market,
+            lending_market_authority: pda::lending_market_authority(program_id, &lending_market),
+            withdraw_reserve: reserve,
+            reserve_source_collateral: pdas.collateral_supply,
+            reserve_collateral_mint: pdas.collateral_mint,
+            reserve_liquidity_supply: pdas.liquidity_supply,
+            user_destination_liquidity,
+            user_destination_collateral,
+            token_program: spl_token::ID,
+            instruction_sysvar_account: sysvar::instructions::ID,
+        },
+        &klend::instruction::WithdrawObligationCollateralAndRedeemReserveCollateral {
+            _collateral_amount: amount,
+        },
+    );
+
+    vec![create_collateral_ata, create_liquidity_ata, ix]
+}
+
+/// Empties an obligation: repays all its borrows and withdraws all its collateral.
+///
+/// The klend program has no instruction to delete an obligation, so the account
+/// (and its rent) stays on chain and can be reused later.
+///
+/// # Parameters
+/// * `ctx` - The cluster and programs to use,
+/// * `owner` - Owner of the obligation, holding the liquidity to repay,
+/// * `obligation` - The obligation to close.
+///
+/// # Returns
+/// The signatures of the transactions.
+///
+/// # Errors
+/// If a transaction fails.
+#[instrument(skip(ctx, owner))]
+pub async fn close(ctx: &Context, owner: &Keypair, obligation: Pubkey) -> Result<Vec<Signature>> {
+    let state = state::fetch::<Obligation>(ctx, &obligation).await?;
+    let mut signatures = Vec::new();
+
+    for borrow in state
+        .borrows
+        .iter()
+        .filter(|borrow| borrow.borrow_reserve != Pubkey::default())
+    {
+        let reserve = state::fetch::<Reserve>(ctx, &borrow.borrow_reserve).await?;
+        signatures.push(
+            repay(
+                ctx,
+                owner,
+                state.lending_market,
+                obligation,
+                borrow.borrow_reserve,
+                reserve.liquidity.mint_pubkey,
+                u64::MAX,
+            )
+            .await?,
+        );
+    }
+    for deposit in state
+        .deposits
+        .iter()
+        .filter(|deposit| deposit.deposit_reserve != Pubkey::default())
+    {
+        let reserve = state::fetch::<Reserve>(ctx, &deposit.deposit_reserve).await?;
+        signatures.push(
+            withdraw_collateral(
+                ctx,
+                owner,
+                state.lending_market,
+                obligation,
+                deposit.deposit_reserve,
+                reserve.liquidity.mint_pubkey,
+                u64::MAX,
+            )
+            .await?,
+        );
+    }
+    info!(%obligation, "Obligation closed");
+
+    Ok(signatures)
+}
@@ -0,0 +1,223 @@
+use solana_sdk::pubkey::Pubkey;
+
+const LENDING_MARKET_AUTH: &[u8] = b"lma";
+const RESERVE_LIQ_SUPPLY: &[u8] = b"reserve_liq_supply";
+const FEE_RECEIVER: &[u8] = b"fee_receiver";
+const RESERVE_COLL_MINT: &[u8] = b"reserve_coll_mint";
+const RESERVE_COLL_SUPPLY: &[u8] = b"reserve_coll_supply";
+const USER_METADATA: &[u8] = b"user_meta";
+const REFERRER_TOKEN_STATE: &[u8] = b"referrer_acc";
+const REFERRER_STATE: &[u8] = b"ref_state";
+const SHORT_URL: &[u8] = b"short_url";
+const FARM_USER_STATE: &[u8] = b"user";
+
+/// The addresses owned by the program for a given reserve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReservePdas {
+    /// Vault holding the liquidity deposited in the reserve.
+    pub liquidity_supply: Pubkey,
+    /// Vault receiving the fees of the reserve.
+    pub fee_receiver: Pubkey,
+    /// Mint of the collateral tokens given in exchange for deposits.
+    pub collateral_mint: Pubkey,
+    /// Vault holding the collateral deposited in obligations.
+    pub collateral_supply: Pubkey,
+}
+
+impl ReservePdas {
+    /// Derives the program addresses of a reserve.
+    ///
+    /// # Parameters
+    /// * `program_id` - The klend program,
+    /// * `lending_market` - Market of the reserve,
+    /// * `liquidity_mint` - Mint of the tokens held by the reserve.
+    pub fn new(program_id: &Pubkey, lending_market: &Pubkey, liquidity_mint: &Pubkey) -> Self {
+        let derive = |seed: &[u8]| {
+            find(
+                program_id,
+                &[seed, lending_market.as_ref(), liquidity_mint.as_ref()],
+            )
+        };
+        Self {
+            liquidity_supply: derive(RESERVE_LIQ_SUPPLY),
+            fee_receiver: derive(FEE_RECEIVER),
+            collateral_mint: derive(RESERVE_COLL_MINT),
+            collateral_supply: derive(RESERVE_COLL_SUPPLY),
+        }
+    }
+}
+
+/// The seeds identifying an obligation of an owner in a market.
+///
+/// Vanilla obligations use the tag 0 and the default seed accounts, other
+/// tags are used by the multiply / leverage / lending positions of Kamino.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ObligationSeeds {
+    pub tag: u8,
+    pub id: u8,
+    pub seed1: Pubkey,
+    pub seed2: Pubkey,
+}
+
+fn find(program_id: &Pubkey, seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, program_id).0
+}
+
+/// The authority of a lending market, owner of all its vaults.
+pub fn lending_market_authority(program_id: &Pubkey, lending_market: &Pubkey) -> Pubkey {
+    find(program_id, &[LENDING_MARKET_AUTH, lending_market.as_ref()])
+}
+
+/// The obligation of an owner in a market.
+pub fn obligation(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    lending_market: &Pubkey,
+    seeds: &ObligationSeeds,
+) -> Pubkey {
+    find(
+        program_id,
+        &[
+            &[seeds.tag],
+            &[seeds.id],
+            owner.as_ref(),
+            lending_market.as_ref(),
+            seeds.seed1.as_ref(),
+            seeds.seed2.as_ref(),
+        ],
+    )
+}
+
+/// The metadata of a user, shared by all its obligations.
+pub fn user_metadata(program_id: &Pubkey, owner: &Pubkey) -> Pubkey {
+    find(program_id, &[USER_METADATA, owner.as_ref()])
+}
+
+/// The account accumulating the fees of a referrer for a reserve.
+pub fn referrer_token_state(program_id: &Pubkey, referrer: &Pubkey, reserve: &Pubkey) -> Pubkey {
+    find(
+        program_id,
+        &[REFERRER_TOKEN_STATE, referrer.as_ref(), reserve.as_ref()],
+    )
+}
+
+/// The state of a referrer, pointing to its short URL.
+pub fn referrer_state(program_id: &Pubkey, referrer: &Pubkey) -> Pubkey {
+    find(program_id, &[REFERRER_STATE, referrer.as_ref()])
+}
+
+/// The account holding a referrer short URL.
+pub fn short_url(program_id: &Pubkey, short_url: &str) -> Pubkey {
+    find(program_id, &[SHORT_URL, short_url.as_bytes()])
+}
+
+/// The stake of an obligation in a farm, owned by the Kamino farms program.
+///
+/// # Parameters
+/// * `farms_program` - The Kamino farms program,
+/// * `farm` - The farm of the collateral or of the debt of a reserve,
+/// * `obligation` - The staking obligation.
+pub fn obligation_farm_user_state(
+    farms_program: &Pubkey,
+    farm: &Pubkey,
+    obligation: &Pubkey,
+) -> Pubkey {
+    find(
+        farms_program,
+        &[FARM_USER_STATE, farm.as_ref(), obligation.as_ref()],
+    )
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+
+    use solana_sdk::pubkey;
+    use test_log::test;
+
+    use crate::config::WSOL_MINT;
+
+    use super::*;
+
+    // Kamino's main market, deployed with the original program ID.
+    const MAIN_MARKET: Pubkey = pubkey!("7u3HeHxYDLhnCoErrtycNokbQYbWGzLs6JSDqGAv5PfF");
+    const USDC_MINT: Pubkey = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+
+    #[test]
+    fn market_authority() {
+        // Given
+        const AUTHORITY: Pubkey = pubkey!("9DrvZvyWh1HuAoZxvYWMvkf2XCzryCpGgHqrMjyDWpmo");
+
+        // When
+        let authority = lending_market_authority(&klend::ID, &MAIN_MARKET);
+
+        // Then
+        assert_eq!(authority, AUTHORITY);
+    }
+
+    #[test]
+    fn sol_reserve_pdas() {
+        // Given
+        let expected = ReservePdas {
+            liquidity_supply: pubkey!("GafNuUXj9rxGLn4y79dPu6MHSuPWeJR6UtTWuexpGh3U"),
+            fee_receiver: pubkey!("3JNof8s453bwG5UqiXBLJc77NRQXezYYEBbk3fqnoKph"),
+            collateral_mint: pubkey!("2UywZrUdyqs5vDchy7fKQJKau2RVyuzBev2XKGPDSiX1"),
+            collateral_supply: pubkey!("8NXMyRD91p3nof61BTkJvrfpGTASHygz1cUvc3HvwyGS"),
+        };
+
+        // When
+        let pdas = ReservePdas::new(&klend::ID, &MAIN_MARKET, &WSOL_MINT);
+
+        // Then
+        assert_eq!(pdas, expected);
+    }
+
+    #[test]
+    fn usdc_reserve_pdas() {
+        // Given
+        let expected = ReservePdas {
+            liquidity_supply: pubkey!("Bgq7trRgVMeq33yt235zM2onQ4bRDBsY5EWiTetF4qw6"),
+            fee_receiver: pubkey!("BbDUrk1bVtSixgQsPLBJFZEF7mwGstnD5joA1WzYvYFX"),
+            collateral_mint: pubkey!("B8V6WVjPxW1UGwVDfxH2d2r8SyT4cqn7dQRK6XneVa7D"),
+            collateral_supply: pubkey!("3DzjXRfxRm6iejfyyMynR4tScddaanrePJ1NJU2XnPPL"),
+        };
+
+        // When
+        let pdas = ReservePdas::new(&klend::ID, &MAIN_MARKET, &USDC_MINT);
+
+        // Then
+        assert_eq!(pdas, expected);
+    }
+
+    #[test]
+    fn obligations_depend_on_seeds() {
+        // Given
+        let owner = pubkey!("CfqFi1pccHicyH3SKD42UbxK9spK3kpYTc3VGjsfXq6v");
+        let vanilla = ObligationSeeds::default();
+        let multiply = ObligationSeeds {
+            tag: 1,
+            seed1: WSOL_MINT,
+            seed2: USDC_MINT,
+            ..ObligationSeeds::default()
+        };
+
+        // When
+        let first = obligation(&klend::ID, &owner, &MAIN_MARKET, &vanilla);
+        let second = obligation(
+            &klend::ID,
+            &owner,
+            &MAIN_MARKET,
+            &ObligationSeeds { id: 1, ..vanilla },
+        );
+        let leveraged = obligation(&klend::ID, &owner, &MAIN_MARKET, &multiply);
+
+        // Then
+        assert_ne!(first, second, "the id is part of the seeds");
+        assert_ne!(first, leveraged, "the tag is part of the seeds");
+        assert_ne!(
+            user_metadata(&klend::ID, &owner),
+            referrer_state(&klend::ID, &owner),
+            "seeds are prefixed"
+        );
+    }
+}
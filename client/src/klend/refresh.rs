@@ -0,0 +1,280 @@
+use ::klend::state::{Obligation, Reserve};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use tracing::{debug, instrument};
+
+use super::{instruction, pda, state};
+use crate::config::Context;
+use crate::error::Result;
+
+/// An optional account of an instruction, replaced by the program ID when unset.
+fn optional(program_id: &Pubkey, account: Pubkey) -> Pubkey {
+    if account == Pubkey::default() {
+        *program_id
+    } else {
+        account
+    }
+}
+
+/// The reserves an obligation depends on: its deposit reserves, then its borrow reserves.
+///
+/// This is the order expected by `refreshObligation` for its remaining accounts.
+pub fn obligation_reserves(obligation: &Obligation) -> Vec<Pubkey> {
+    obligation
+        .deposits
+        .iter()
+        .map(|deposit| deposit.deposit_reserve)
+        .chain(
+            obligation
+                .borrows
+                .iter()
+                .map(|borrow| borrow.borrow_reserve),
+        )
+        .filter(|reserve| *reserve != Pubkey::default())
+        .collect()
+}
+
+/// The instruction refreshing the price and interests of a reserve, with its oracles.
+///
+/// # Parameters
+/// * `program_id` - The klend program,
+/// * `address` - The reserve to refresh,
+/// * `reserve` - Its current state, giving its market and oracles.
+pub fn refresh_reserve(program_id: &Pubkey, address: Pubkey, reserve: &Reserve) -> Instruction {
+    let token_info = &reserve.config.token_info;
+    instruction(
+        program_id,
+        &::klend::accounts::RefreshReserve {
+            reserve: address,
+            lending_market: reserve.lending_market,
+            pyth_oracle: optional(program_id, token_info.pyth_configuration.price),
+            switchboard_price_oracle: optional(
+                program_id,
+                token_info.switchboard_configuration.price_aggregator,
+            ),
+            switchboard_twap_oracle: optional(
+                program_id,
+                token_info.switchboard_configuration.twap_aggregator,
+            ),
+            scope_prices: optional(program_id, token_info.scope_configuration.price_feed),
+        },
+        &::klend::instruction::RefreshReserve {},
+    )
+}
+
+/// The accounts read or written by the instructions on a reserve: the reserve, its market, the
+/// market authority, its vaults, its mints and its oracles.
+///
+/// These are the addresses worth putting in a lookup table.
+///
+/// # Parameters
+/// * `program_id` - The klend program,
+/// * `address` - The reserve,
+/// * `reserve` - Its current state.
+pub fn reserve_accounts(program_id: &Pubkey, address: Pubkey, reserve: &Reserve) -> Vec<Pubkey> {
+    let token_info = &reserve.config.token_info;
+    [
+        address,
+        reserve.lending_market,
+        pda::lending_market_authority(program_id, &reserve.lending_market),
+        reserve.liquidity.mint_pubkey,
+        reserve.liquidity.supply_vault,
+        reserve.liquidity.fee_vault,
+        reserve.collateral.mint_pubkey,
+        reserve.collateral.supply_vault,
+        token_info.pyth_configuration.price,
+        token_info.switchboard_configuration.price_aggregator,
+        token_info.switchboard_configuration.twap_aggregator,
+        token_info.scope_configuration.price_feed,
+    ]
+    .into_iter()
+    .filter(|account| *account != Pubkey::default())
+    .collect()
+}
+
+/// The instruction refreshing the values of an obligation.
+///
+/// The reserves of the obligation must have been refreshed before, in the same slot.
+///
+/// # Parameters
+/// * `program_id` - The klend program,
+/// * `address` - The obligation to refresh,
+/// * `obligation` - Its current state, giving its market and reserves.
+pub fn refresh_obligation(
+    program_id: &Pubkey,
+    address: Pubkey,
+    obligation: &Obligation,
+) -> Instruction {
+    let mut ix = instruction(
+        program_id,
+        &::klend::accounts::RefreshObligation {
+            lending_market: obligation.lending_market,
+            obligation: address,
+        },
+        &::klend::instruction::RefreshObligation {},
+    );
+    ix.accounts.extend(
+        obligation_reserves(obligation)
+            .into_iter()
+            .map(|reserve| AccountMeta::new_readonly(reserve, false)),
+    );
+    ix
+}
+
+/// The instructions refreshing reserves, to prepend to an instruction using them.
+///
+/// # Parameters
+/// * `ctx` - The cluster and programs to use,
+/// * `reserves` - The reserves to refresh (duplicates are refreshed once).
+///
+/// # Errors
+/// If a reserve could not be fetched.
+#[instrument(skip(ctx))]
+pub async fn reserves_instructions(ctx: &Context, reserves: &[Pubkey]) -> Result<Vec<Instruction>> {
+    let mut instructions: Vec<Instruction> = Vec::with_capacity(reserves.len());
+    for reserve in reserves {
+        if instructions.iter().any(|ix| {
+            ix.accounts
+                .first()
+                .is_some_and(|meta| meta.pubkey == *reserve)
+        }) {
+            continue;
+        }
+        let state = state::fetch::<Reserve>(ctx, reserve).await?;
+        instructions.push(refresh_reserve(&ctx.klend_program, *reserve, &state));
+    }
+
+    Ok(instructions)
+}
+
+/// The instructions refreshing an obligation and all its reserves, to prepend to an
+/// instruction acting on the obligation.
+///
+/// # Parameters
+/// * `ctx` - The cluster and programs to use,
+/// * `obligation` - The obligation to refresh,
+/// * `reserves` - Reserves used by the instruction which the obligation might not
+///   depend on yet (e.g. a reserve borrowed from for the first time).
+///
+/// # Errors
+/// If the obligation or one of the reserves could not be fetched.
+#[instrument(skip(ctx))]
+pub async fn obligation_instructions(
+    ctx: &Context,
+    obligation: Pubkey,
+    reserves: &[Pubkey],
+) -> Result<Vec<Instruction>> {
+    let state = state::fetch::<Obligation>(ctx, &obligation).await?;
+    let mut all_reserves = obligation_reserves(&state);
+    all_reserves.extend_from_slice(reserves);
+
+    let mut instructions = reserves_instructions(ctx, &all_reserves).await?;
+    instructions.push(refresh_obligation(&ctx.klend_program, obligation, &state));
+    debug!(
+        count = instructions.len(),
+        "refresh instructions for {obligation}"
+    );
+
+    Ok(instructions)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+
+    use test_log::test;
+
+    use super::*;
+    use crate::{config::profile::DEVNET_KLEND_PROGRAM, klend::state::zeroed};
+    type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
+
+    #[test]
+    fn reserve_oracles() -> TestResult {
+        // Given
+        let address = Pubkey::new_unique();
+        let pyth = Pubkey::new_unique();
+        let scope = Pubkey::new_unique();
+        let mut reserve: Reserve = zeroed()?;
+        reserve.lending_market = Pubkey::new_unique();
+        reserve.config.token_info.pyth_configuration.price = pyth;
+        reserve.config.token_info.scope_configuration.price_feed = scope;
+
+        // When
+        let ix = refresh_reserve(&DEVNET_KLEND_PROGRAM, address, &reserve);
+
+        // Then
+        let accounts: Vec<_> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+        assert_eq!(
+            accounts,
+            [
+                address,
+                reserve.lending_market,
+                pyth,
+                DEVNET_KLEND_PROGRAM,
+                DEVNET_KLEND_PROGRAM,
+                scope
+            ],
+            "unset oracles are replaced by the program ID"
+        );
+        assert!(ix.accounts[0].is_writable, "the reserve is updated");
+        Ok(())
+    }
+
+    #[test]
+    fn reserve_lookup_accounts() -> TestResult {
+        // Given
+        let address = Pubkey::new_unique();
+        let pyth = Pubkey::new_unique();
+        let mut reserve: Reserve = zeroed()?;
+        reserve.lending_market = Pubkey::new_unique();
+        reserve.liquidity.supply_vault = Pubkey::new_unique();
+        reserve.config.token_info.pyth_configuration.price = pyth;
+
+        // When
+        let accounts = reserve_accounts(&DEVNET_KLEND_PROGRAM, address, &reserve);
+
+        // Then
+        assert_eq!(
+            accounts,
+            [
+                address,
+                reserve.lending_market,
+                pda::lending_market_authority(&DEVNET_KLEND_PROGRAM, &reserve.lending_market),
+                reserve.liquidity.supply_vault,
+                pyth
+            ],
+            "unset accounts are skipped"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn obligation_remaining_accounts() -> TestResult {
+        // Given
+        let address = Pubkey::new_unique();
+        let sol = Pubkey::new_unique();
+        let bsol = Pubkey::new_unique();
+        let usdc = Pubkey::new_unique();
+        let mut obligation: Obligation = zeroed()?;
+        obligation.lending_market = Pubkey::new_unique();
+        obligation.borrows[0].borrow_reserve = bsol;
+        obligation.deposits[0].deposit_reserve = sol;
+        obligation.deposits[2].deposit_reserve = usdc;
+
+        // When
+        let ix = refresh_obligation(&DEVNET_KLEND_PROGRAM, address, &obligation);
+
+        // Then
+        let accounts: Vec<_> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+        assert_eq!(
+            accounts,
+            [obligation.lending_market, address, sol, usdc, bsol],
+            "deposit reserves come before borrow reserves"
+        );
+        assert!(
+            ix.accounts[2..].iter().all(|meta| !meta.is_writable),
+            "the reserves are only read"
+        );
+        Ok(())
+    }
+}
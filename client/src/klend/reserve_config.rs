@@ -0,0 +1,524 @@
+//! Declarative configuration of a reserve, applied by sending only the fields that differ.
+
+use std::{fs, path::Path};
+
+use klend::{ReserveConfig, state::Reserve};
+use serde::Deserialize;
+use solana_sdk::{pubkey::Pubkey, signature::Signature, signer::Signer};
+use tracing::{info, instrument};
+
+use crate::{
+    config::{Context, address},
+    error::{Error, Result},
+    klend::{
+        state::fetch,
+        update::{CurvePoint, ReserveUpdate, WithdrawalCap, borrow_rate_curve, fee_sf},
+        update_reserve_config_instruction,
+    },
+    transaction::execute_instructions,
+};
+
+/// Number of updates sent in a single transaction.
+const UPDATES_PER_TRANSACTION: usize = 5;
+
+/// The fields of a reserve configuration to set, the ones omitted being left untouched.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReserveConfigFile {
+    /// 0 for active, 1 for obsolete, 2 for hidden.
+    pub status: Option<u8>,
+    /// 0 for regular, 1 for isolated collateral, 2 for isolated debt.
+    pub asset_tier: Option<u8>,
+    pub loan_to_value_pct: Option<u8>,
+    pub liquidation_threshold_pct: Option<u8>,
+    pub min_liquidation_bonus_bps: Option<u16>,
+    pub max_liquidation_bonus_bps: Option<u16>,
+    pub bad_debt_liquidation_bonus_bps: Option<u16>,
+    pub protocol_take_rate_pct: Option<u8>,
+    pub protocol_liquidation_fee_pct: Option<u8>,
+    pub deleveraging_margin_call_period_secs: Option<u64>,
+    pub borrow_fee_bps: Option<u16>,
+    pub flash_loan_fee_bps: Option<u16>,
+    /// The (utilization, borrow rate) points in basis points, completed to 11 points.
+    pub borrow_rate_curve: Option<Vec<(u32, u32)>>,
+    pub borrow_factor_pct: Option<u64>,
+    pub deposit_limit: Option<u64>,
+    pub borrow_limit: Option<u64>,
+    pub deposit_withdrawal_cap: Option<WithdrawalCap>,
+    pub debt_withdrawal_cap: Option<WithdrawalCap>,
+    /// The elevation groups of the reserve, at most 20.
+    pub elevation_groups: Option<Vec<u8>>,
+    /// The token and the validation of its price.
+    #[serde(default)]
+    pub token: TokenConfig,
+    /// The oracles of the token.
+    #[serde(default)]
+    pub oracle: OracleConfig,
+}
+
+/// The token of a reserve and the validation of its price.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenConfig {
+    pub name: Option<String>,
+    pub price_max_age_secs: Option<u64>,
+    pub twap_max_age_secs: Option<u64>,
+    pub max_twap_divergence_bps: Option<u64>,
+    /// Lowest price accepted, scaled by `10^heuristic_exp`.
+    pub heuristic_lower: Option<u64>,
+    /// Highest price accepted, scaled by `10^heuristic_exp`.
+    pub heuristic_upper: Option<u64>,
+    pub heuristic_exp: Option<u64>,
+}
+
+/// The oracles of the token of a reserve.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OracleConfig {
+    #[serde(default, deserialize_with = "address::optional::deserialize")]
+    pub pyth_price: Option<Pubkey>,
+    #[serde(default, deserialize_with = "address::optional::deserialize")]
+    pub switchboard_price: Option<Pubkey>,
+    #[serde(default, deserialize_with = "address::optional::deserialize")]
+    pub switchboard_twap: Option<Pubkey>,
+    #[serde(default, deserialize_with = "address::optional::deserialize")]
+    pub scope_price_feed: Option<Pubkey>,
+    pub scope_price_chain: Option<[u16; 4]>,
+    pub scope_twap_chain: Option<[u16; 4]>,
+}
+
+impl ReserveConfigFile {
+    /// Loads a reserve configuration from a TOML file.
+    ///
+    /// # Parameters
+    /// * `path` - Path of the file.
+    ///
+    /// # Errors
+    /// If the file could not be read or parsed.
+    #[expect(clippy::result_large_err)]
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| Error::Config(format!("could not read {}: {err}", path.display())))?;
+        toml::from_str(&content)
+            .map_err(|err| Error::Config(format!("could not parse {}: {err}", path.display())))
+    }
+
+    /// The updates turning a configuration into this one.
+    ///
+    /// The loan to value ratio and the liquidation threshold (and the minimum and maximum
+    /// liquidation bonuses) are ordered so that the first never exceeds the second in between.
+    ///
+    /// # Parameters
+    /// * `current` - The configuration of the reserve.
+    ///
+    /// # Errors
+    /// If the borrow rate curve or the elevation groups are invalid.
+    #[expect(clippy::result_large_err)]
+    pub fn updates(&self, current: &ReserveConfig) -> Result<Vec<ReserveUpdate>> {
+        let mut updates = self.risk_updates(current);
+
+        if let Some(points) = &self.borrow_rate_curve {
+            let current_curve = current.borrow_rate_curve.points.map(|point| CurvePoint {
+                utilization_rate_bps: point.utilization_rate_bps,
+                borrow_rate_bps: point.borrow_rate_bps,
+            });
+            updates.extend(
+                changed(Some(borrow_rate_curve(points)?), &current_curve)
+                    .map(ReserveUpdate::BorrowRateCurve),
+            );
+        }
+        updates.extend(
+            changed(self.borrow_factor_pct, &current.borrow_factor_pct)
+                .map(ReserveUpdate::BorrowFactorPct),
+        );
+        updates.extend(
+            changed(self.deposit_limit, &current.deposit_limit).map(ReserveUpdate::DepositLimit),
+        );
+        updates.extend(
+            changed(self.borrow_limit, &current.borrow_limit).map(ReserveUpdate::BorrowLimit),
+        );
+        let caps = [
+            (
+                self.deposit_withdrawal_cap,
+                &current.deposit_withdrawal_cap,
+                ReserveUpdate::DepositWithdrawalCap as fn(WithdrawalCap) -> ReserveUpdate,
+            ),
+            (
+                self.debt_withdrawal_cap,
+                &current.debt_withdrawal_cap,
+                ReserveUpdate::DebtWithdrawalCap,
+            ),
+        ];
+        for (wanted, cap, update) in caps {
+            let current_cap = WithdrawalCap {
+                capacity: cap.config_capacity,
+                interval_secs: cap.config_interval_length_seconds,
+            };
+            updates.extend(changed(wanted, &current_cap).map(update));
+        }
+        if let Some(groups) = &self.elevation_groups {
+            let mut padded = [0; 20];
+            padded
+                .get_mut(..groups.len())
+                .ok_or_else(|| {
+                    Error::Instruction("a reserve has at most 20 elevation groups".to_owned())
+                })?
+                .copy_from_slice(groups);
+            updates.extend(
+                changed(Some(padded), &current.elevation_groups)
+                    .map(ReserveUpdate::ElevationGroups),
+            );
+        }
+
+        updates.extend(self.token.updates(current));
+        updates.extend(self.oracle.updates(current));
+
+        Ok(updates)
+    }
+
+    /// The updates of the status, risk parameters and fees of a configuration.
+    fn risk_updates(&self, current: &ReserveConfig) -> Vec<ReserveUpdate> {
+        let mut updates = Vec::new();
+
+        updates.extend(changed(self.status, &current.status).map(ReserveUpdate::Status));
+        updates.extend(changed(self.asset_tier, &current.asset_tier).map(ReserveUpdate::AssetTier));
+        let ltv = changed(self.loan_to_value_pct, &current.loan_to_value_pct)
+            .map(ReserveUpdate::LoanToValuePct);
+        let threshold = changed(
+            self.liquidation_threshold_pct,
+            &current.liquidation_threshold_pct,
+        )
+        .map(ReserveUpdate::LiquidationThresholdPct);
+        updates.extend(ordered(
+            ltv,
+            threshold,
+            self.liquidation_threshold_pct > Some(current.liquidation_threshold_pct),
+        ));
+        let min_bonus = changed(
+            self.min_liquidation_bonus_bps,
+            &current.min_liquidation_bonus_bps,
+        )
+        .map(ReserveUpdate::MinLiquidationBonusBps);
+        let max_bonus = changed(
+            self.max_liquidation_bonus_bps,
+            &current.max_liquidation_bonus_bps,
+        )
+        .map(ReserveUpdate::MaxLiquidationBonusBps);
+        updates.extend(ordered(
+            min_bonus,
+            max_bonus,
+            self.max_liquidation_bonus_bps > Some(current.max_liquidation_bonus_bps),
+        ));
+        updates.extend(
+            changed(
+                self.bad_debt_liquidation_bonus_bps,
+                &current.bad_debt_liquidation_bonus_bps,
+            )
+            .map(ReserveUpdate::BadDebtLiquidationBonusBps),
+        );
+        updates.extend(
+            changed(self.protocol_take_rate_pct, &current.protocol_take_rate_pct)
+                .map(ReserveUpdate::ProtocolTakeRatePct),
+        );
+        updates.extend(
+            changed(
+                self.protocol_liquidation_fee_pct,
+                &current.protocol_liquidation_fee_pct,
+            )
+            .map(ReserveUpdate::ProtocolLiquidationFeePct),
+        );
+        updates.extend(
+            changed(
+                self.deleveraging_margin_call_period_secs,
+                &current.deleveraging_margin_call_period_secs,
+            )
+            .map(ReserveUpdate::DeleveragingMarginCallPeriodSecs),
+        );
+        updates.extend(
+            changed(self.borrow_fee_bps.map(fee_sf), &current.fees.borrow_fee_sf)
+                .map(ReserveUpdate::BorrowFeeSf),
+        );
+        updates.extend(
+            changed(
+                self.flash_loan_fee_bps.map(fee_sf),
+                &current.fees.flash_loan_fee_sf,
+            )
+            .map(ReserveUpdate::FlashLoanFeeSf),
+        );
+        updates
+    }
+}
+
+impl TokenConfig {
+    /// The updates of the token info of a configuration.
+    fn updates(&self, current: &ReserveConfig) -> Vec<ReserveUpdate> {
+        let info = &current.token_info;
+        let current_name = String::from_utf8_lossy(&info.name)
+            .trim_end_matches('\0')
+            .to_owned();
+
+        let mut updates = Vec::new();
+        updates.extend(
+            self.name
+                .clone()
+                .filter(|name| *name != current_name)
+                .map(ReserveUpdate::Name),
+        );
+        let values = [
+            (
+                self.price_max_age_secs,
+                info.max_age_price_seconds,
+                ReserveUpdate::PriceMaxAgeSecs as fn(u64) -> ReserveUpdate,
+            ),
+            (
+                self.twap_max_age_secs,
+                info.max_age_twap_seconds,
+                ReserveUpdate::TwapMaxAgeSecs,
+            ),
+            (
+                self.max_twap_divergence_bps,
+                info.max_twap_divergence_bps,
+                ReserveUpdate::TwapDivergenceBps,
+            ),
+            (
+                self.heuristic_exp,
+                info.heuristic.exp,
+                ReserveUpdate::ExpHeuristic,
+            ),
+            (
+                self.heuristic_lower,
+                info.heuristic.lower,
+                ReserveUpdate::LowerHeuristic,
+            ),
+            (
+                self.heuristic_upper,
+                info.heuristic.upper,
+                ReserveUpdate::UpperHeuristic,
+            ),
+        ];
+        for (wanted, value, update) in values {
+            updates.extend(changed(wanted, &value).map(update));
+        }
+        updates
+    }
+}
+
+impl OracleConfig {
+    /// The updates of the oracles of a configuration.
+    fn updates(&self, current: &ReserveConfig) -> Vec<ReserveUpdate> {
+        let info = &current.token_info;
+        let feeds = [
+            (
+                self.pyth_price,
+                info.pyth_configuration.price,
+                ReserveUpdate::PythPrice as fn(Pubkey) -> ReserveUpdate,
+            ),
+            (
+                self.switchboard_price,
+                info.switchboard_configuration.price_aggregator,
+                ReserveUpdate::SwitchboardFeed,
+            ),
+            (
+                self.switchboard_twap,
+                info.switchboard_configuration.twap_aggregator,
+                ReserveUpdate::SwitchboardTwapFeed,
+            ),
+            (
+                self.scope_price_feed,
+                info.scope_configuration.price_feed,
+                ReserveUpdate::ScopePriceFeed,
+            ),
+        ];
+
+        let mut updates = Vec::new();
+        for (wanted, feed, update) in feeds {
+            updates.extend(changed(wanted, &feed).map(update));
+        }
+        updates.extend(
+            changed(
+                self.scope_price_chain,
+                &info.scope_configuration.price_chain,
+            )
+            .map(ReserveUpdate::ScopePriceChain),
+        );
+        updates.extend(
+            changed(self.scope_twap_chain, &info.scope_configuration.twap_chain)
+                .map(ReserveUpdate::ScopeTwapChain),
+        );
+        updates
+    }
+}
+
+/// Applies a configuration to a reserve, sending only the fields that differ.
+///
+/// The configuration resulting from each update is validated by the program, except on a
+/// reserve without liquidity yet, where only the last update is (a new reserve is invalid until
+/// it is fully configured).
+///
+/// # Parameters
+/// * `ctx` - The cluster to use, its payer owning the lending market,
+/// * `reserve` - The reserve to configure,
+/// * `config` - The configuration to apply.
+///
+/// # Returns
+/// The signatures of the transactions sent, none if the reserve was already configured.
+///
+/// # Errors
+/// If the reserve could not be fetched, a value is invalid or a transaction fails.
+#[instrument(skip(ctx, config))]
+pub async fn apply(
+    ctx: &Context,
+    reserve: Pubkey,
+    config: &ReserveConfigFile,
+) -> Result<Vec<Signature>> {
+    let state = fetch::<Reserve>(ctx, &reserve).await?;
+    let updates = config.updates(&state.config)?;
+    if updates.is_empty() {
+        info!("the reserve is already configured");
+        return Ok(Vec::new());
+    }
+
+    let unused = state.liquidity.available_amount == 0 && state.collateral.mint_total_supply == 0;
+    let last = updates.len() - 1;
+    let mut instructions = Vec::with_capacity(updates.len());
+    for (index, update) in updates.iter().enumerate() {
+        info!(?update, "updating the reserve");
+        instructions.push(update_reserve_config_instruction(
+            &ctx.klend_program,
+            &ctx.payer.pubkey(),
+            state.lending_market,
+            reserve,
+            update,
+            unused && index != last,
+        )?);
+    }
+
+    let mut signatures = Vec::new();
+    for chunk in instructions.chunks(UPDATES_PER_TRANSACTION) {
+        signatures.push(execute_instructions(ctx, chunk, &[]).await?);
+    }
+    info!("Reserve configured with {} updates", updates.len());
+
+    Ok(signatures)
+}
+
+/// The wanted value, if it differs from the current one.
+fn changed<T: PartialEq>(wanted: Option<T>, current: &T) -> Option<T> {
+    wanted.filter(|value| value != current)
+}
+
+/// Two updates, the second first if `second_first`.
+fn ordered(
+    first: Option<ReserveUpdate>,
+    second: Option<ReserveUpdate>,
+    second_first: bool,
+) -> impl Iterator<Item = ReserveUpdate> {
+    let (first, second) = if second_first {
+        (second, first)
+    } else {
+        (first, second)
+    };
+    first.into_iter().chain(second)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use std::assert_matches;
+
+    use test_log::test;
+
+    use super::*;
+    use crate::klend::state::zeroed;
+
+    type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
+
+    const CONFIG: &str = r#"
+        loan_to_value_pct = 80
+        liquidation_threshold_pct = 85
+        borrow_fee_bps = 10
+        borrow_rate_curve = [[0, 0], [8000, 500], [10000, 3000]]
+        deposit_limit = 1000000000000
+        deposit_withdrawal_cap = { capacity = 1000, interval_secs = 3600 }
+
+        [token]
+        name = "SOL"
+        price_max_age_secs = 180
+
+        [oracle]
+        pyth_price = "BdgHsXrH1mXqhdosXavYxZgX6bGqTdj5mh2sxDhF8bJy"
+    "#;
+
+    fn reserve_config() -> core::result::Result<ReserveConfig, Box<dyn core::error::Error>> {
+        let mut reserve: Reserve = zeroed()?;
+        reserve.config.loan_to_value_pct = 70;
+        reserve.config.liquidation_threshold_pct = 75;
+        reserve.config.deposit_limit = 1_000_000_000_000;
+        Ok(reserve.config)
+    }
+
+    #[test]
+    fn diff_against_current_config() -> TestResult {
+        // Given
+        let file: ReserveConfigFile = toml::from_str(CONFIG)?;
+        let example: ReserveConfigFile = toml::from_str(include_str!("../../../reserve_sol.toml"))?;
+        let mut current = reserve_config()?;
+
+        // When
+        let updates = file.updates(&current)?;
+        let example_updates = example.updates(&current)?;
+        current.loan_to_value_pct = 90;
+        current.liquidation_threshold_pct = 95;
+        let lowering = file.updates(&current)?;
+
+        // Then
+        assert_matches!(
+            updates.as_slice(),
+            [
+                ReserveUpdate::LiquidationThresholdPct(85),
+                ReserveUpdate::LoanToValuePct(80),
+                ReserveUpdate::BorrowFeeSf(_),
+                ReserveUpdate::BorrowRateCurve(_),
+                ReserveUpdate::DepositWithdrawalCap(_),
+                ReserveUpdate::Name(_),
+                ReserveUpdate::PriceMaxAgeSecs(180),
+                ReserveUpdate::PythPrice(_),
+            ]
+        );
+        assert!(
+            !example_updates.is_empty(),
+            "the example configures the reserve"
+        );
+        assert_matches!(
+            lowering.as_slice(),
+            [
+                ReserveUpdate::LoanToValuePct(80),
+                ReserveUpdate::LiquidationThresholdPct(85),
+                ..
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_config_files() -> TestResult {
+        // Given
+        let current = reserve_config()?;
+        let unknown = toml::from_str::<ReserveConfigFile>("loan_to_value = 80");
+        let curve: ReserveConfigFile = toml::from_str("borrow_rate_curve = [[100, 0]]")?;
+        let groups: ReserveConfigFile =
+            toml::from_str(&format!("elevation_groups = {:?}", [1; 21]))?;
+
+        // When
+        let curve_updates = curve.updates(&current);
+        let groups_updates = groups.updates(&current);
+
+        // Then
+        assert_matches!(unknown, Err(_));
+        assert_matches!(curve_updates, Err(Error::Instruction(_)));
+        assert_matches!(groups_updates, Err(Error::Instruction(_)));
+
+        Ok(())
+    }
+}
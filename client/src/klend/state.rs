@@ -0,0 +1,580 @@
+use core::fmt;
+
+use ::klend::state::{
+    LendingMarket, Obligation, ReferrerState, ReferrerTokenState, Reserve, ShortUrl, UserMetadata,
+};
+#[cfg(test)]
+use anchor_client::anchor_lang::AccountSerialize;
+use anchor_client::anchor_lang::{
+    AccountDeserialize, AnchorDeserialize, Discriminator, error::ErrorCode,
+};
+use solana_sdk::pubkey::Pubkey;
+use tracing::{debug, instrument};
+
+use crate::{
+    config::Context,
+    error::{Error, Result},
+    transaction::process_rpc_error,
+};
+
+/// Number of fractional bits of the scaled fractions (`_sf` fields) stored by klend.
+const FRACTION_BITS: u32 = 60;
+
+/// The farm stake of a user, owned by the Kamino farms program.
+///
+/// Not part of the published klend crate but listed in the program’s IDL,
+/// since obligations delegate their farm positions to it.
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[expect(dead_code)]
+pub struct UserState {
+    pub user_id: u64,
+    pub farm_state: Pubkey,
+    pub owner: Pubkey,
+    pub is_farm_delegated: u8,
+    pub padding0: [u8; 7],
+    pub rewards_tally_scaled: [u128; 10],
+    pub rewards_issued_unclaimed: [u64; 10],
+    pub last_claim_ts: [u64; 10],
+    pub active_stake_scaled: u128,
+    pub pending_deposit_stake_scaled: u128,
+    pub pending_deposit_stake_ts: u64,
+    pub pending_withdrawal_unstake_scaled: u128,
+    pub pending_withdrawal_unstake_ts: u64,
+    pub bump: u64,
+    pub delegatee: Pubkey,
+    pub last_stake_ts: u64,
+    pub padding1: [u64; 50],
+}
+
+impl Discriminator for UserState {
+    const DISCRIMINATOR: [u8; 8] = [72, 177, 85, 249, 76, 167, 186, 126];
+}
+
+impl AccountDeserialize for UserState {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_client::anchor_lang::Result<Self> {
+        if !buf.starts_with(&Self::DISCRIMINATOR) {
+            return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_client::anchor_lang::Result<Self> {
+        let mut data = buf
+            .get(8..)
+            .ok_or(ErrorCode::AccountDiscriminatorNotFound)?;
+        Self::deserialize(&mut data).map_err(|_err| ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+/// Converts a klend scaled fraction to a float.
+#[expect(clippy::cast_precision_loss)]
+pub fn fraction(value: u128) -> f64 {
+    value as f64 / (1_u128 << FRACTION_BITS) as f64
+}
+
+/// Converts an amount of tokens from base units to UI units.
+fn ui_amount(amount: f64, decimals: u64) -> f64 {
+    amount / 10_f64.powi(i32::try_from(decimals).unwrap_or(i32::MAX))
+}
+
+/// Decodes the data of a klend (or any Anchor) account, checking its discriminator.
+///
+/// # Parameters
+/// * `address` - Address of the account (for error reporting),
+/// * `data` - The raw data of the account.
+///
+/// # Errors
+/// If the discriminator does not match `T` or the data could not be decoded.
+#[expect(clippy::result_large_err)]
+pub fn decode<T: AccountDeserialize + Discriminator>(address: &Pubkey, data: &[u8]) -> Result<T> {
+    if !data.starts_with(&T::DISCRIMINATOR) {
+        return Err(Error::AccountDecode {
+            address: *address,
+            reason: "discriminator mismatch".to_owned(),
+        });
+    }
+    let mut buf = data;
+    T::try_deserialize(&mut buf).map_err(|err| Error::AccountDecode {
+        address: *address,
+        reason: err.to_string(),
+    })
+}
+
+/// The data of a freshly allocated (all zero) account of type `T`.
+#[cfg(test)]
+pub fn zeroed_data<T: Discriminator>() -> Vec<u8> {
+    let mut data = T::DISCRIMINATOR.to_vec();
+    data.resize(8 + size_of::<T>(), 0);
+    data
+}
+
+/// A decoded, all zero, account of type `T`.
+///
+/// # Errors
+/// If the zeroed data is not a valid `T`.
+#[cfg(test)]
+#[expect(clippy::result_large_err)]
+pub fn zeroed<T: AccountDeserialize + Discriminator>() -> Result<T> {
+    decode(&Pubkey::new_unique(), &zeroed_data::<T>())
+}
+
+/// The data of an account holding `state`, as stored on chain.
+///
+/// # Errors
+/// If the state could not be serialized.
+#[cfg(test)]
+#[expect(clippy::result_large_err)]
+pub fn account_data<T: AccountSerialize>(address: &Pubkey, state: &T) -> Result<Vec<u8>> {
+    let mut data = vec![];
+    state
+        .try_serialize(&mut data)
+        .map_err(|err| Error::AccountDecode {
+            address: *address,
+            reason: err.to_string(),
+        })?;
+    Ok(data)
+}
+
+/// Fetches and decodes a klend (or any Anchor) account.
+///
+/// # Parameters
+/// * `ctx` - The cluster to read from,
+/// * `address` - Address of the account.
+///
+/// # Errors
+/// If the account does not exist or is not of type `T`.
+#[instrument(skip(ctx))]
+pub async fn fetch<T: AccountDeserialize + Discriminator>(
+    ctx: &Context,
+    address: &Pubkey,
+) -> Result<T> {
+    debug!("fetching account");
+    let account = ctx
+        .rpc
+        .get_account(address)
+        .await
+        .map_err(process_rpc_error)?;
+    decode(address, &account.data)
+}
+
+/// Any of the accounts owned by the klend program.
+#[derive(Clone)]
+pub enum Account {
+    LendingMarket(Box<LendingMarket>),
+    Reserve(Box<Reserve>),
+    Obligation(Box<Obligation>),
+    UserMetadata(Box<UserMetadata>),
+    UserState(Box<UserState>),
+    ReferrerState(ReferrerState),
+    ReferrerTokenState(Box<ReferrerTokenState>),
+    ShortUrl(ShortUrl),
+}
+
+impl Account {
+    /// Decodes a klend account of any type, based on its discriminator.
+    ///
+    /// # Parameters
+    /// * `address` - Address of the account (for error reporting),
+    /// * `data` - The raw data of the account.
+    ///
+    /// # Errors
+    /// If the discriminator is unknown or the data could not be decoded.
+    #[expect(clippy::result_large_err)]
+    pub fn decode(address: &Pubkey, data: &[u8]) -> Result<Self> {
+        let discriminator = data.get(..8).unwrap_or_default();
+        Ok(match discriminator {
+            d if d == LendingMarket::DISCRIMINATOR => {
+                Self::LendingMarket(Box::new(decode(address, data)?))
+            }
+            d if d == Reserve::DISCRIMINATOR => Self::Reserve(Box::new(decode(address, data)?)),
+            d if d == Obligation::DISCRIMINATOR => {
+                Self::Obligation(Box::new(decode(address, data)?))
+            }
+            d if d == UserMetadata::DISCRIMINATOR => {
+                Self::UserMetadata(Box::new(decode(address, data)?))
+            }
+            d if d == UserState::DISCRIMINATOR => Self::UserState(Box::new(decode(address, data)?)),
+            d if d == ReferrerState::DISCRIMINATOR => Self::ReferrerState(decode(address, data)?),
+            d if d == ReferrerTokenState::DISCRIMINATOR => {
+                Self::ReferrerTokenState(Box::new(decode(address, data)?))
+            }
+            d if d == ShortUrl::DISCRIMINATOR => Self::ShortUrl(decode(address, data)?),
+            _ => {
+                return Err(Error::AccountDecode {
+                    address: *address,
+                    reason: "unknown klend account discriminator".to_owned(),
+                });
+            }
+        })
+    }
+
+    /// Fetches and decodes a klend account of any type.
+    ///
+    /// # Parameters
+    /// * `ctx` - The cluster to read from,
+    /// * `address` - Address of the account.
+    ///
+    /// # Errors
+    /// If the account does not exist or is not a klend account.
+    #[instrument(skip(ctx))]
+    pub async fn fetch(ctx: &Context, address: &Pubkey) -> Result<Self> {
+        debug!("fetching account");
+        let account = ctx
+            .rpc
+            .get_account(address)
+            .await
+            .map_err(process_rpc_error)?;
+        Self::decode(address, &account.data)
+    }
+}
+
+impl fmt::Debug for Account {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Display for Account {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LendingMarket(market) => write!(
+                f,
+                "LendingMarket {{ owner: {}, emergency mode: {}, liquidation close factor: {}%, \
+                 referral fee: {} bps }}",
+                market.lending_market_owner,
+                market.emergency_mode,
+                market.liquidation_max_debt_close_factor_pct,
+                market.referral_fee_bps,
+            ),
+            Self::Reserve(reserve) => ReserveSummary::from(reserve.as_ref()).fmt(f),
+            Self::Obligation(obligation) => ObligationSummary::from(obligation.as_ref()).fmt(f),
+            Self::UserMetadata(metadata) => write!(
+                f,
+                "UserMetadata {{ referrer: {}, lookup table: {} }}",
+                metadata.referrer, metadata.user_lookup_table
+            ),
+            Self::UserState(state) => write!(
+                f,
+                "UserState {{ owner: {}, farm: {}, active stake: {} }}",
+                state.owner,
+                state.farm_state,
+                fraction(state.active_stake_scaled)
+            ),
+            Self::ReferrerState(state) => {
+                write!(f, "ReferrerState {{ short url: {} }}", state.short_url)
+            }
+            Self::ReferrerTokenState(state) => write!(
+                f,
+                "ReferrerTokenState {{ referrer: {}, mint: {}, unclaimed: {}, cumulative: {} }}",
+                state.referrer,
+                state.mint,
+                fraction(state.amount_unclaimed_sf),
+                fraction(state.amount_cumulative_sf)
+            ),
+            Self::ShortUrl(url) => write!(
+                f,
+                "ShortUrl {{ referrer: {}, url: {} }}",
+                url.referrer, url.short_url
+            ),
+        }
+    }
+}
+
+/// The main figures of a reserve, in UI units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReserveSummary {
+    /// Mint of the liquidity of the reserve.
+    pub mint: Pubkey,
+    /// Market price of the liquidity in the market's quote currency.
+    pub price: f64,
+    /// Liquidity available to borrow or withdraw.
+    pub available: f64,
+    /// Liquidity currently borrowed (including accrued interests).
+    pub borrowed: f64,
+    /// Total liquidity supplied to the reserve, net of fees.
+    pub total_supply: f64,
+    /// Ratio of the supply currently borrowed.
+    pub utilization: f64,
+    /// Maximum loan to value of the collateral, in percents.
+    pub loan_to_value_pct: u8,
+    /// Loan to value at which the collateral is liquidated, in percents.
+    pub liquidation_threshold_pct: u8,
+    /// Whether the reserve must be refreshed before being used.
+    pub stale: bool,
+}
+
+impl From<&Reserve> for ReserveSummary {
+    fn from(reserve: &Reserve) -> Self {
+        let liquidity = &reserve.liquidity;
+        let decimals = liquidity.mint_decimals;
+        let borrowed = fraction(liquidity.borrowed_amount_sf);
+        let fees = fraction(
+            liquidity
+                .accumulated_protocol_fees_sf
+                .saturating_add(liquidity.accumulated_referrer_fees_sf)
+                .saturating_add(liquidity.pending_referrer_fees_sf),
+        );
+        #[expect(clippy::cast_precision_loss)]
+        let available = liquidity.available_amount as f64;
+        let total_supply = (available + borrowed - fees).max(0.0);
+
+        Self {
+            mint: liquidity.mint_pubkey,
+            price: fraction(liquidity.market_price_sf),
+            available: ui_amount(available, decimals),
+            borrowed: ui_amount(borrowed, decimals),
+            total_supply: ui_amount(total_supply, decimals),
+            utilization: if total_supply > 0.0 {
+                (borrowed / total_supply).min(1.0)
+            } else {
+                0.0
+            },
+            loan_to_value_pct: reserve.config.loan_to_value_pct,
+            liquidation_threshold_pct: reserve.config.liquidation_threshold_pct,
+            stale: reserve.last_update.stale != 0,
+        }
+    }
+}
+
+impl fmt::Display for ReserveSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Reserve {{ mint: {}, price: {:.4}, supply: {:.4}, borrowed: {:.4}, available: {:.4}, \
+             utilization: {:.2}%, LTV: {}%, liquidation threshold: {}%{} }}",
+            self.mint,
+            self.price,
+            self.total_supply,
+            self.borrowed,
+            self.available,
+            self.utilization * 100.0_f64,
+            self.loan_to_value_pct,
+            self.liquidation_threshold_pct,
+            if self.stale { ", stale" } else { "" },
+        )
+    }
+}
+
+/// A collateral deposited in an obligation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ObligationDeposit {
+    /// Reserve of the collateral.
+    pub reserve: Pubkey,
+    /// Amount of collateral tokens deposited.
+    pub amount: u64,
+    /// Market value of the collateral.
+    pub value: f64,
+}
+
+/// A liquidity borrowed by an obligation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ObligationBorrow {
+    /// Reserve the liquidity was borrowed from.
+    pub reserve: Pubkey,
+    /// Amount of liquidity borrowed (base units, including accrued interests).
+    pub amount: f64,
+    /// Market value of the borrowed liquidity.
+    pub value: f64,
+}
+
+/// The positions and values of an obligation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObligationSummary {
+    /// Owner of the obligation.
+    pub owner: Pubkey,
+    /// Collaterals deposited in the obligation.
+    pub deposits: Vec<ObligationDeposit>,
+    /// Liquidities borrowed by the obligation.
+    pub borrows: Vec<ObligationBorrow>,
+    /// Market value of the deposits.
+    pub deposited_value: f64,
+    /// Market value of the borrows, adjusted by the reserves' borrow factors.
+    pub borrowed_value: f64,
+    /// Maximum borrowed value allowed by the deposits.
+    pub allowed_borrow_value: f64,
+    /// Borrowed value above which the obligation can be liquidated.
+    pub unhealthy_borrow_value: f64,
+    /// Whether the obligation must be refreshed before being used.
+    pub stale: bool,
+}
+
+impl ObligationSummary {
+    /// The loan to value of the obligation (0 if nothing is deposited).
+    pub fn loan_to_value(&self) -> f64 {
+        if self.deposited_value > 0.0 {
+            self.borrowed_value / self.deposited_value
+        } else {
+            0.0
+        }
+    }
+}
+
+impl From<&Obligation> for ObligationSummary {
+    fn from(obligation: &Obligation) -> Self {
+        Self {
+            owner: obligation.owner,
+            deposits: obligation
+                .deposits
+                .iter()
+                .filter(|deposit| deposit.deposit_reserve != Pubkey::default())
+                .map(|deposit| ObligationDeposit {
+                    reserve: deposit.deposit_reserve,
+                    amount: deposit.deposited_amount,
+                    value: fraction(deposit.market_value_sf),
+                })
+                .collect(),
+            borrows: obligation
+                .borrows
+                .iter()
+                .filter(|borrow| borrow.borrow_reserve != Pubkey::default())
+                .map(|borrow| ObligationBorrow {
+                    reserve: borrow.borrow_reserve,
+                    amount: fraction(borrow.borrowed_amount_sf),
+                    value: fraction(borrow.market_value_sf),
+                })
+                .collect(),
+            deposited_value: fraction(obligation.deposited_value_sf),
+            borrowed_value: fraction(obligation.borrow_factor_adjusted_debt_value_sf),
+            allowed_borrow_value: fraction(obligation.allowed_borrow_value_sf),
+            unhealthy_borrow_value: fraction(obligation.unhealthy_borrow_value_sf),
+            stale: obligation.last_update.stale != 0,
+        }
+    }
+}
+
+impl fmt::Display for ObligationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Obligation {{ owner: {}, deposited: {:.4}, borrowed: {:.4}, allowed: {:.4}, \
+             unhealthy: {:.4}, LTV: {:.2}%{} }}",
+            self.owner,
+            self.deposited_value,
+            self.borrowed_value,
+            self.allowed_borrow_value,
+            self.unhealthy_borrow_value,
+            self.loan_to_value() * 100.0_f64,
+            if self.stale { ", stale" } else { "" },
+        )?;
+        for deposit in &self.deposits {
+            write!(
+                f,
+                "\n  deposit {}: {} (value {:.4})",
+                deposit.reserve, deposit.amount, deposit.value
+            )?;
+        }
+        for borrow in &self.borrows {
+            write!(
+                f,
+                "\n  borrow {}: {:.0} (value {:.4})",
+                borrow.reserve, borrow.amount, borrow.value
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+
+    use std::assert_matches;
+
+    use anchor_client::anchor_lang::AccountSerialize;
+    use test_log::test;
+
+    use super::*;
+    type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
+
+    const ONE: u128 = 1 << FRACTION_BITS;
+
+    #[expect(clippy::result_large_err)]
+    fn serialize<T: AccountSerialize>(account: &T) -> core::result::Result<Vec<u8>, Error> {
+        let mut data = Vec::new();
+        account
+            .try_serialize(&mut data)
+            .map_err(|err| Error::Instruction(err.to_string()))?;
+        Ok(data)
+    }
+
+    #[test]
+    fn reserve_summary() -> TestResult {
+        // Given
+        let address = Pubkey::new_unique();
+        let mut reserve: Reserve = decode(&address, &zeroed_data::<Reserve>())?;
+        reserve.liquidity.mint_decimals = 9;
+        reserve.liquidity.available_amount = 3_000_000_000;
+        reserve.liquidity.borrowed_amount_sf = 1_000_000_000 * ONE;
+        reserve.liquidity.market_price_sf = 150 * ONE;
+        reserve.config.loan_to_value_pct = 75;
+        let data = serialize(&reserve)?;
+
+        // When
+        let summary = ReserveSummary::from(&decode::<Reserve>(&address, &data)?);
+
+        // Then
+        assert!((summary.total_supply - 4.0).abs() < 1e-9, "{summary}");
+        assert!((summary.borrowed - 1.0).abs() < 1e-9, "{summary}");
+        assert!((summary.utilization - 0.25).abs() < 1e-9, "{summary}");
+        assert!((summary.price - 150.0).abs() < 1e-9, "{summary}");
+        assert_eq!(summary.loan_to_value_pct, 75);
+
+        Ok(())
+    }
+
+    #[test]
+    fn obligation_summary() -> TestResult {
+        // Given
+        let address = Pubkey::new_unique();
+        let reserve = Pubkey::new_unique();
+        let mut obligation: Obligation = decode(&address, &zeroed_data::<Obligation>())?;
+        obligation.deposits[0].deposit_reserve = reserve;
+        obligation.deposits[0].deposited_amount = 10;
+        obligation.deposits[0].market_value_sf = 200 * ONE;
+        obligation.borrows[0].borrow_reserve = reserve;
+        obligation.borrows[0].borrowed_amount_sf = 5 * ONE;
+        obligation.deposited_value_sf = 200 * ONE;
+        obligation.borrow_factor_adjusted_debt_value_sf = 50 * ONE;
+
+        // When
+        let summary = ObligationSummary::from(&obligation);
+
+        // Then
+        assert_eq!(summary.deposits.len(), 1);
+        assert_eq!(summary.borrows.len(), 1);
+        assert!((summary.loan_to_value() - 0.25).abs() < 1e-9, "{summary}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn decode_any_account() {
+        // Given
+        let address = Pubkey::new_unique();
+
+        // When
+        let market = Account::decode(&address, &zeroed_data::<LendingMarket>());
+        let state = Account::decode(&address, &zeroed_data::<UserState>());
+        let metadata = Account::decode(&address, &zeroed_data::<UserMetadata>());
+
+        // Then
+        assert_matches!(market, Ok(Account::LendingMarket(_)));
+        assert_matches!(state, Ok(Account::UserState(_)));
+        assert_matches!(metadata, Ok(Account::UserMetadata(_)));
+    }
+
+    #[test]
+    fn wrong_discriminator() {
+        // Given
+        let address = Pubkey::new_unique();
+        let data = zeroed_data::<Reserve>();
+
+        // When
+        let obligation = decode::<Obligation>(&address, &data);
+        let unknown = Account::decode(&address, &[0; 64]);
+
+        // Then
+        assert_matches!(obligation.err(), Some(Error::AccountDecode { address: key, .. }) if key == address);
+        assert_matches!(unknown, Err(Error::AccountDecode { .. }));
+    }
+}
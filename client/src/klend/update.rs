@@ -0,0 +1,645 @@
+//! Typed values of the `update_reserve_config` and `update_lending_market` instructions.
+//!
+//! Both instructions take a mode (the field to update) and its value as raw bytes, encoded the
+//! way the program reads them back. The deprecated modes are not supported.
+
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::{Error, Result};
+
+/// Number of points of a borrow rate curve.
+pub const CURVE_POINTS: usize = 11;
+/// Maximum length of the name of a token or of a market, in bytes.
+const NAME_LENGTH: usize = 32;
+/// Size of the value of a lending market update.
+const MARKET_VALUE_SIZE: usize = 72;
+/// 100% in basis points.
+const FULL_BPS: u16 = 10_000;
+
+/// A point of a borrow rate curve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurvePoint {
+    /// Utilization of the reserve, in basis points.
+    pub utilization_rate_bps: u32,
+    /// Borrow rate at this utilization, in basis points.
+    pub borrow_rate_bps: u32,
+}
+
+/// Limit of the amount withdrawn (or borrowed) from a reserve over an interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WithdrawalCap {
+    /// Amount that can be withdrawn during an interval, in base units.
+    pub capacity: i64,
+    /// Length of the interval, in seconds.
+    pub interval_secs: u64,
+}
+
+/// An elevation group of a lending market (e-mode).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElevationGroup {
+    /// Identifier of the group, between 1 and 32.
+    pub id: u8,
+    /// Maximum bonus of the liquidators, in basis points.
+    pub max_liquidation_bonus_bps: u16,
+    /// Loan to value ratio of the group, as a percentage.
+    pub ltv_pct: u8,
+    /// Liquidation threshold of the group, as a percentage.
+    pub liquidation_threshold_pct: u8,
+    /// Whether new loans can be taken in the group.
+    pub allow_new_loans: bool,
+    /// Maximum number of reserves deposited as collateral by an obligation of the group.
+    pub max_reserves_as_collateral: u8,
+    /// The only reserve that can be borrowed in the group.
+    pub debt_reserve: Pubkey,
+}
+
+/// A field of a reserve configuration (`UpdateConfigMode`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[expect(dead_code)]
+pub enum ReserveUpdate {
+    /// Target ratio of the value of borrows to deposits, as a percentage.
+    LoanToValuePct(u8),
+    /// Maximum bonus of the liquidators, in basis points.
+    MaxLiquidationBonusBps(u16),
+    /// Loan to value ratio at which an obligation can be liquidated, as a percentage.
+    LiquidationThresholdPct(u8),
+    /// Cut of the liquidation bonus received by the protocol, as a percentage.
+    ProtocolLiquidationFeePct(u8),
+    /// Part of the interests received by the protocol, as a percentage.
+    ProtocolTakeRatePct(u8),
+    /// Fee of the borrows, as a scaled fraction (see [`fee_sf`]).
+    BorrowFeeSf(u64),
+    /// Fee of the flash loans, as a scaled fraction (see [`fee_sf`]).
+    FlashLoanFeeSf(u64),
+    /// Cut of the fees received by the referrers, in basis points.
+    ReferralFeeBps(u16),
+    /// Maximum amount of liquidity deposited, in base units.
+    DepositLimit(u64),
+    /// Maximum amount of liquidity borrowed, in base units.
+    BorrowLimit(u64),
+    /// Lowest price accepted from the oracle, as a scaled value (see `ExpHeuristic`).
+    LowerHeuristic(u64),
+    /// Highest price accepted from the oracle, as a scaled value (see `ExpHeuristic`).
+    UpperHeuristic(u64),
+    /// Number of decimals of the heuristic prices.
+    ExpHeuristic(u64),
+    /// Maximum divergence between the price and its TWAP, in basis points.
+    TwapDivergenceBps(u64),
+    /// Scope chain of the TWAP of the token.
+    ScopeTwapChain([u16; 4]),
+    /// Scope chain of the price of the token.
+    ScopePriceChain([u16; 4]),
+    /// Name of the token (at most 32 bytes).
+    Name(String),
+    /// Maximum age of the price, in seconds.
+    PriceMaxAgeSecs(u64),
+    /// Maximum age of the TWAP, in seconds.
+    TwapMaxAgeSecs(u64),
+    /// The Scope price feed.
+    ScopePriceFeed(Pubkey),
+    /// The Pyth price account.
+    PythPrice(Pubkey),
+    /// The Switchboard price aggregator.
+    SwitchboardFeed(Pubkey),
+    /// The Switchboard TWAP aggregator.
+    SwitchboardTwapFeed(Pubkey),
+    /// The borrow rate curve (see [`borrow_rate_curve`]).
+    BorrowRateCurve([CurvePoint; CURVE_POINTS]),
+    /// The whole configuration, already encoded.
+    EntireReserveConfig(Vec<u8>),
+    /// Limit of the amount borrowed over an interval.
+    DebtWithdrawalCap(WithdrawalCap),
+    /// Limit of the amount withdrawn over an interval.
+    DepositWithdrawalCap(WithdrawalCap),
+    /// Amount borrowed during the current interval.
+    DebtWithdrawalCapCurrentTotal(i64),
+    /// Amount withdrawn during the current interval.
+    DepositWithdrawalCapCurrentTotal(i64),
+    /// Bonus of the liquidators of obligations with bad debt, in basis points.
+    BadDebtLiquidationBonusBps(u16),
+    /// Minimum bonus of the liquidators, in basis points.
+    MinLiquidationBonusBps(u16),
+    /// Delay before deleveraging once the deposit limit is crossed, in seconds.
+    DeleveragingMarginCallPeriodSecs(u64),
+    /// Risk adjustment of the borrowed value, as a percentage (at least 100).
+    BorrowFactorPct(u64),
+    /// Asset tier: 0 for regular, 1 for isolated collateral, 2 for isolated debt.
+    AssetTier(u8),
+    /// Elevation groups of the reserve (0 for none).
+    ElevationGroups([u8; 20]),
+    /// Decrease of the liquidation threshold during deleveraging, in basis points per day.
+    DeleveragingThresholdDecreaseBpsPerDay(u64),
+    /// Status: 0 for active, 1 for obsolete, 2 for hidden.
+    Status(u8),
+    /// The farm of the collateral.
+    FarmCollateral(Pubkey),
+    /// The farm of the debt.
+    FarmDebt(Pubkey),
+    /// Whether the reserve can only be used as collateral in an elevation group.
+    DisableUsageAsCollateralOutsideEmode(bool),
+    /// Utilization above which borrowing is blocked, as a percentage (0 to disable).
+    BlockBorrowingAboveUtilizationPct(u8),
+    /// Whether the price of the reserve is ignored.
+    BlockPriceUsage(bool),
+    /// Maximum amount borrowed outside of elevation groups, in base units.
+    BorrowLimitOutsideElevationGroup(u64),
+    /// Maximum amount borrowed in each elevation group against this reserve, in base units.
+    BorrowLimitsInElevationGroupAgainstThisReserve(Box<[u64; 32]>),
+    /// Fixed interest rate paid to the host, in basis points.
+    HostFixedInterestRateBps(u16),
+    /// Whether the obligations can be deleveraged automatically.
+    AutodeleverageEnabled(bool),
+    /// Increase of the deleveraging bonus, in basis points per day.
+    DeleveragingBonusIncreaseBpsPerDay(u64),
+}
+
+impl ReserveUpdate {
+    /// The `UpdateConfigMode` of the update.
+    pub const fn mode(&self) -> u64 {
+        match self {
+            Self::LoanToValuePct(_) => 0,
+            Self::MaxLiquidationBonusBps(_) => 1,
+            Self::LiquidationThresholdPct(_) => 2,
+            Self::ProtocolLiquidationFeePct(_) => 3,
+            Self::ProtocolTakeRatePct(_) => 4,
+            Self::BorrowFeeSf(_) => 5,
+            Self::FlashLoanFeeSf(_) => 6,
+            Self::ReferralFeeBps(_) => 7,
+            Self::DepositLimit(_) => 8,
+            Self::BorrowLimit(_) => 9,
+            Self::LowerHeuristic(_) => 10,
+            Self::UpperHeuristic(_) => 11,
+            Self::ExpHeuristic(_) => 12,
+            Self::TwapDivergenceBps(_) => 13,
+            Self::ScopeTwapChain(_) => 14,
+            Self::ScopePriceChain(_) => 15,
+            Self::Name(_) => 16,
+            Self::PriceMaxAgeSecs(_) => 17,
+            Self::TwapMaxAgeSecs(_) => 18,
+            Self::ScopePriceFeed(_) => 19,
+            Self::PythPrice(_) => 20,
+            Self::SwitchboardFeed(_) => 21,
+            Self::SwitchboardTwapFeed(_) => 22,
+            Self::BorrowRateCurve(_) => 23,
+            Self::EntireReserveConfig(_) => 24,
+            Self::DebtWithdrawalCap(_) => 25,
+            Self::DepositWithdrawalCap(_) => 26,
+            Self::DebtWithdrawalCapCurrentTotal(_) => 27,
+            Self::DepositWithdrawalCapCurrentTotal(_) => 28,
+            Self::BadDebtLiquidationBonusBps(_) => 29,
+            Self::MinLiquidationBonusBps(_) => 30,
+            Self::DeleveragingMarginCallPeriodSecs(_) => 31,
+            Self::BorrowFactorPct(_) => 32,
+            Self::AssetTier(_) => 33,
+            Self::ElevationGroups(_) => 34,
+            Self::DeleveragingThresholdDecreaseBpsPerDay(_) => 35,
+            Self::Status(_) => 38,
+            Self::FarmCollateral(_) => 39,
+            Self::FarmDebt(_) => 40,
+            Self::DisableUsageAsCollateralOutsideEmode(_) => 41,
+            Self::BlockBorrowingAboveUtilizationPct(_) => 42,
+            Self::BlockPriceUsage(_) => 43,
+            Self::BorrowLimitOutsideElevationGroup(_) => 44,
+            Self::BorrowLimitsInElevationGroupAgainstThisReserve(_) => 45,
+            Self::HostFixedInterestRateBps(_) => 46,
+            Self::AutodeleverageEnabled(_) => 47,
+            Self::DeleveragingBonusIncreaseBpsPerDay(_) => 48,
+        }
+    }
+
+    /// Validates and encodes the value of the update.
+    ///
+    /// # Errors
+    /// If the value is out of the range accepted by the program.
+    #[expect(clippy::result_large_err)]
+    #[expect(clippy::little_endian_bytes)]
+    pub fn value(&self) -> Result<Vec<u8>> {
+        Ok(match self {
+            Self::LoanToValuePct(pct)
+            | Self::LiquidationThresholdPct(pct)
+            | Self::ProtocolLiquidationFeePct(pct)
+            | Self::ProtocolTakeRatePct(pct)
+            | Self::BlockBorrowingAboveUtilizationPct(pct) => vec![percentage(*pct)?],
+            Self::MaxLiquidationBonusBps(bps)
+            | Self::ReferralFeeBps(bps)
+            | Self::BadDebtLiquidationBonusBps(bps)
+            | Self::MinLiquidationBonusBps(bps)
+            | Self::HostFixedInterestRateBps(bps) => basis_points(*bps)?.to_le_bytes().to_vec(),
+            Self::BorrowFeeSf(fee) | Self::FlashLoanFeeSf(fee) => {
+                check(*fee <= fee_sf(FULL_BPS), "a fee cannot exceed 100%")?;
+                fee.to_le_bytes().to_vec()
+            }
+            Self::DepositLimit(value)
+            | Self::BorrowLimit(value)
+            | Self::LowerHeuristic(value)
+            | Self::UpperHeuristic(value)
+            | Self::ExpHeuristic(value)
+            | Self::TwapDivergenceBps(value)
+            | Self::PriceMaxAgeSecs(value)
+            | Self::TwapMaxAgeSecs(value)
+            | Self::DeleveragingMarginCallPeriodSecs(value)
+            | Self::DeleveragingThresholdDecreaseBpsPerDay(value)
+            | Self::BorrowLimitOutsideElevationGroup(value)
+            | Self::DeleveragingBonusIncreaseBpsPerDay(value) => value.to_le_bytes().to_vec(),
+            Self::BorrowFactorPct(pct) => {
+                check(*pct >= 100, "the borrow factor must be at least 100%")?;
+                pct.to_le_bytes().to_vec()
+            }
+            Self::ScopeTwapChain(chain) | Self::ScopePriceChain(chain) => {
+                chain.iter().flat_map(|id| id.to_le_bytes()).collect()
+            }
+            Self::Name(name) => name_bytes(name)?.to_vec(),
+            Self::ScopePriceFeed(address)
+            | Self::PythPrice(address)
+            | Self::SwitchboardFeed(address)
+            | Self::SwitchboardTwapFeed(address)
+            | Self::FarmCollateral(address)
+            | Self::FarmDebt(address) => address.to_bytes().to_vec(),
+            Self::BorrowRateCurve(points) => {
+                check_curve(points)?;
+                points
+                    .iter()
+                    .flat_map(|point| {
+                        [point.utilization_rate_bps, point.borrow_rate_bps]
+                            .into_iter()
+                            .flat_map(u32::to_le_bytes)
+                    })
+                    .collect()
+            }
+            Self::EntireReserveConfig(config) => config.clone(),
+            Self::DebtWithdrawalCap(cap) | Self::DepositWithdrawalCap(cap) => {
+                check(
+                    cap.capacity >= 0,
+                    "a withdrawal capacity cannot be negative",
+                )?;
+                [cap.capacity.to_le_bytes(), cap.interval_secs.to_le_bytes()].concat()
+            }
+            Self::DebtWithdrawalCapCurrentTotal(total)
+            | Self::DepositWithdrawalCapCurrentTotal(total) => total.to_le_bytes().to_vec(),
+            Self::AssetTier(tier) => {
+                check(*tier <= 2, "the asset tier must be 0, 1 or 2")?;
+                vec![*tier]
+            }
+            Self::ElevationGroups(groups) => {
+                check(
+                    groups.iter().all(|&group| group <= 32),
+                    "an elevation group must be between 0 and 32",
+                )?;
+                groups.to_vec()
+            }
+            Self::Status(status) => {
+                check(*status <= 2, "the reserve status must be 0, 1 or 2")?;
+                vec![*status]
+            }
+            Self::DisableUsageAsCollateralOutsideEmode(flag)
+            | Self::BlockPriceUsage(flag)
+            | Self::AutodeleverageEnabled(flag) => vec![u8::from(*flag)],
+            Self::BorrowLimitsInElevationGroupAgainstThisReserve(limits) => limits
+                .iter()
+                .flat_map(|limit| limit.to_le_bytes())
+                .collect(),
+        })
+    }
+}
+
+/// A field of a lending market (`UpdateLendingMarketMode`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[expect(dead_code)]
+pub enum LendingMarketUpdate {
+    /// The owner of the market.
+    Owner(Pubkey),
+    /// Whether the market is in emergency mode, every operation being paused.
+    EmergencyMode(bool),
+    /// Part of a borrow repaid by a liquidation, as a percentage.
+    LiquidationCloseFactorPct(u8),
+    /// Maximum value repaid by a liquidation, in dollars.
+    LiquidationMaxValue(u64),
+    /// Maximum value borrowed from the market, in dollars.
+    GlobalAllowedBorrowValue(u64),
+    /// The risk council of the market.
+    RiskCouncil(Pubkey),
+    /// Value under which an obligation is fully liquidated, in dollars.
+    MinFullLiquidationValueThreshold(u64),
+    /// Loan to value ratio above which an obligation is insolvent, as a percentage.
+    InsolvencyRiskLtvPct(u8),
+    /// An elevation group of the market, created or replaced.
+    ElevationGroup(ElevationGroup),
+    /// Cut of the fees received by the referrers, in basis points.
+    ReferralFeeBps(u16),
+    /// Age of the prices triggering their refresh, as a percentage of their maximum age.
+    PriceRefreshTriggerToMaxAgePct(u8),
+    /// Whether the obligations can be deleveraged automatically.
+    AutodeleverageEnabled(bool),
+    /// Whether borrowing is disabled in the whole market.
+    BorrowingDisabled(bool),
+    /// Minimum net value of an obligation after an action, as a scaled fraction.
+    MinNetValueObligationPostActionSf(u128),
+    /// Value under which the LTV is not checked during priority liquidations, in dollars.
+    MinValueLtvSkipPriorityLiqCheck(u64),
+    /// Value under which the borrow factor is not checked during priority liquidations, in dollars.
+    MinValueBfSkipPriorityLiqCheck(u64),
+    /// Resets the padding fields of the market.
+    PaddingFields,
+    /// Name of the market (at most 32 bytes).
+    Name(String),
+    /// Delay before deleveraging an obligation marked individually, in seconds.
+    IndividualAutodeleverageMarginCallPeriodSecs(u64),
+    /// Amount deposited when a reserve is created, in base units.
+    InitialDepositAmount(u64),
+}
+
+// only used by `update_lending_market` so far
+#[cfg_attr(not(test), expect(dead_code))]
+impl LendingMarketUpdate {
+    /// The `UpdateLendingMarketMode` of the update.
+    pub const fn mode(&self) -> u64 {
+        match self {
+            Self::Owner(_) => 0,
+            Self::EmergencyMode(_) => 1,
+            Self::LiquidationCloseFactorPct(_) => 2,
+            Self::LiquidationMaxValue(_) => 3,
+            Self::GlobalAllowedBorrowValue(_) => 5,
+            Self::RiskCouncil(_) => 6,
+            Self::MinFullLiquidationValueThreshold(_) => 7,
+            Self::InsolvencyRiskLtvPct(_) => 8,
+            Self::ElevationGroup(_) => 9,
+            Self::ReferralFeeBps(_) => 10,
+            Self::PriceRefreshTriggerToMaxAgePct(_) => 12,
+            Self::AutodeleverageEnabled(_) => 13,
+            Self::BorrowingDisabled(_) => 14,
+            Self::MinNetValueObligationPostActionSf(_) => 15,
+            Self::MinValueLtvSkipPriorityLiqCheck(_) => 16,
+            Self::MinValueBfSkipPriorityLiqCheck(_) => 17,
+            Self::PaddingFields => 18,
+            Self::Name(_) => 19,
+            Self::IndividualAutodeleverageMarginCallPeriodSecs(_) => 20,
+            Self::InitialDepositAmount(_) => 21,
+        }
+    }
+
+    /// Validates and encodes the value of the update, padded with zeros.
+    ///
+    /// # Errors
+    /// If the value is out of the range accepted by the program.
+    #[expect(clippy::result_large_err)]
+    #[expect(clippy::little_endian_bytes)]
+    pub fn value(&self) -> Result<[u8; MARKET_VALUE_SIZE]> {
+        let bytes = match self {
+            Self::Owner(address) | Self::RiskCouncil(address) => address.to_bytes().to_vec(),
+            Self::EmergencyMode(flag)
+            | Self::AutodeleverageEnabled(flag)
+            | Self::BorrowingDisabled(flag) => vec![u8::from(*flag)],
+            Self::LiquidationCloseFactorPct(pct)
+            | Self::InsolvencyRiskLtvPct(pct)
+            | Self::PriceRefreshTriggerToMaxAgePct(pct) => vec![percentage(*pct)?],
+            Self::LiquidationMaxValue(value)
+            | Self::GlobalAllowedBorrowValue(value)
+            | Self::MinFullLiquidationValueThreshold(value)
+            | Self::MinValueLtvSkipPriorityLiqCheck(value)
+            | Self::MinValueBfSkipPriorityLiqCheck(value)
+            | Self::IndividualAutodeleverageMarginCallPeriodSecs(value)
+            | Self::InitialDepositAmount(value) => value.to_le_bytes().to_vec(),
+            Self::ElevationGroup(group) => {
+                check(
+                    (1..=32).contains(&group.id),
+                    "an elevation group must be between 1 and 32",
+                )?;
+                check(
+                    group.ltv_pct < group.liquidation_threshold_pct,
+                    "the LTV of an elevation group must be below its liquidation threshold",
+                )?;
+                [
+                    basis_points(group.max_liquidation_bonus_bps)?
+                        .to_le_bytes()
+                        .as_slice(),
+                    &[
+                        group.id,
+                        group.ltv_pct,
+                        percentage(group.liquidation_threshold_pct)?,
+                        u8::from(group.allow_new_loans),
+                        group.max_reserves_as_collateral,
+                        0,
+                    ],
+                    group.debt_reserve.as_ref(),
+                ]
+                .concat()
+            }
+            Self::ReferralFeeBps(bps) => basis_points(*bps)?.to_le_bytes().to_vec(),
+            Self::MinNetValueObligationPostActionSf(value) => value.to_le_bytes().to_vec(),
+            Self::PaddingFields => Vec::new(),
+            Self::Name(name) => name_bytes(name)?.to_vec(),
+        };
+
+        let mut value = [0; MARKET_VALUE_SIZE];
+        value
+            .get_mut(..bytes.len())
+            .ok_or_else(|| Error::Instruction("the market update value is too long".to_owned()))?
+            .copy_from_slice(&bytes);
+        Ok(value)
+    }
+}
+
+/// A fee as the scaled fraction stored by klend.
+///
+/// # Parameters
+/// * `bps` - The fee, in basis points.
+pub fn fee_sf(bps: u16) -> u64 {
+    u64::try_from((u128::from(bps) << 60).div_ceil(u128::from(FULL_BPS))).unwrap_or(u64::MAX)
+}
+
+/// A borrow rate curve, completed to its 11 points by repeating the last one.
+///
+/// # Parameters
+/// * `points` - The (utilization, borrow rate) points in basis points, from 0% to 100% utilization.
+///
+/// # Errors
+/// If there are more than 11 points, or if they do not go from 0% to 100% utilization.
+#[expect(clippy::result_large_err)]
+pub fn borrow_rate_curve(points: &[(u32, u32)]) -> Result<[CurvePoint; CURVE_POINTS]> {
+    let &(last_utilization, last_rate) = points
+        .last()
+        .ok_or_else(|| Error::Instruction("a borrow rate curve cannot be empty".to_owned()))?;
+    check(
+        points.len() <= CURVE_POINTS,
+        "a borrow rate curve has at most 11 points",
+    )?;
+
+    let mut curve = [CurvePoint {
+        utilization_rate_bps: last_utilization,
+        borrow_rate_bps: last_rate,
+    }; CURVE_POINTS];
+    for (point, &(utilization_rate_bps, borrow_rate_bps)) in curve.iter_mut().zip(points) {
+        *point = CurvePoint {
+            utilization_rate_bps,
+            borrow_rate_bps,
+        };
+    }
+    check_curve(&curve)?;
+
+    Ok(curve)
+}
+
+/// Checks that a curve goes from 0% to 100% utilization, with increasing utilizations and rates.
+#[expect(clippy::result_large_err)]
+fn check_curve(curve: &[CurvePoint; CURVE_POINTS]) -> Result<()> {
+    check(
+        curve[0].utilization_rate_bps == 0,
+        "a borrow rate curve starts at 0% utilization",
+    )?;
+    check(
+        curve[CURVE_POINTS - 1].utilization_rate_bps == u32::from(FULL_BPS),
+        "a borrow rate curve ends at 100% utilization",
+    )?;
+    for pair in curve.windows(2) {
+        let [previous, next] = pair else {
+            continue;
+        };
+        let complete = previous.utilization_rate_bps == u32::from(FULL_BPS);
+        check(
+            if complete {
+                next == previous
+            } else {
+                next.utilization_rate_bps > previous.utilization_rate_bps
+                    && next.borrow_rate_bps >= previous.borrow_rate_bps
+            },
+            "the utilizations and rates of a borrow rate curve must increase",
+        )?;
+    }
+    Ok(())
+}
+
+/// A name as the null-padded bytes stored by klend.
+#[expect(clippy::result_large_err)]
+fn name_bytes(name: &str) -> Result<[u8; NAME_LENGTH]> {
+    check(name.len() <= NAME_LENGTH, "a name is at most 32 bytes long")?;
+    let mut bytes = [0; NAME_LENGTH];
+    bytes[..name.len()].copy_from_slice(name.as_bytes());
+    Ok(bytes)
+}
+
+#[expect(clippy::result_large_err)]
+fn percentage(pct: u8) -> Result<u8> {
+    check(pct <= 100, "a percentage cannot exceed 100")?;
+    Ok(pct)
+}
+
+#[expect(clippy::result_large_err)]
+fn basis_points(bps: u16) -> Result<u16> {
+    check(bps <= FULL_BPS, "basis points cannot exceed 10000")?;
+    Ok(bps)
+}
+
+#[expect(clippy::result_large_err)]
+fn check(condition: bool, reason: &str) -> Result<()> {
+    if condition {
+        Ok(())
+    } else {
+        Err(Error::Instruction(reason.to_owned()))
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use std::assert_matches;
+
+    use test_log::test;
+
+    use super::*;
+
+    type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
+
+    #[test]
+    fn reserve_update_values() -> TestResult {
+        // Given
+        let pyth = Pubkey::new_unique();
+        let curve = borrow_rate_curve(&[(0, 0), (8_000, 500), (10_000, 3_000)])?;
+
+        // When
+        let ltv = ReserveUpdate::LoanToValuePct(75).value()?;
+        let bonus = ReserveUpdate::MaxLiquidationBonusBps(500).value()?;
+        let oracle = ReserveUpdate::PythPrice(pyth).value()?;
+        let name = ReserveUpdate::Name("SOL".to_owned()).value()?;
+        let encoded_curve = ReserveUpdate::BorrowRateCurve(curve).value()?;
+        let cap = ReserveUpdate::DepositWithdrawalCap(WithdrawalCap {
+            capacity: 1_000,
+            interval_secs: 3_600,
+        })
+        .value()?;
+
+        // Then
+        assert_eq!(ltv, vec![75]);
+        assert_eq!(bonus, vec![0xf4, 0x01]);
+        assert_eq!(oracle, pyth.to_bytes().to_vec());
+        assert_eq!(name.len(), 32);
+        assert_eq!(&name[..4], b"SOL\0");
+        assert_eq!(encoded_curve.len(), CURVE_POINTS * 8);
+        assert_eq!(curve[2], curve[CURVE_POINTS - 1]);
+        assert_eq!(cap.len(), 16);
+        assert_eq!(ReserveUpdate::Status(0).mode(), 38);
+        assert_eq!(fee_sf(100), 11_529_215_046_068_470);
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_reserve_updates() {
+        // Given
+        let updates = [
+            ReserveUpdate::LoanToValuePct(101),
+            ReserveUpdate::MinLiquidationBonusBps(10_001),
+            ReserveUpdate::BorrowFactorPct(99),
+            ReserveUpdate::AssetTier(3),
+            ReserveUpdate::Name("a name that is much too long for klend".to_owned()),
+        ];
+
+        // When
+        let curves = [
+            borrow_rate_curve(&[]),
+            borrow_rate_curve(&[(100, 0), (10_000, 100)]),
+            borrow_rate_curve(&[(0, 0), (5_000, 100)]),
+            borrow_rate_curve(&[(0, 100), (5_000, 50), (10_000, 200)]),
+        ];
+
+        // Then
+        for update in updates {
+            assert_matches!(update.value(), Err(Error::Instruction(_)), "{update:?}");
+        }
+        for curve in curves {
+            assert_matches!(curve, Err(Error::Instruction(_)));
+        }
+    }
+
+    #[test]
+    fn lending_market_update_values() -> TestResult {
+        // Given
+        let debt_reserve = Pubkey::new_unique();
+        let group = ElevationGroup {
+            id: 1,
+            max_liquidation_bonus_bps: 100,
+            ltv_pct: 90,
+            liquidation_threshold_pct: 92,
+            allow_new_loans: true,
+            max_reserves_as_collateral: 2,
+            debt_reserve,
+        };
+
+        // When
+        let value = LendingMarketUpdate::ElevationGroup(group).value()?;
+        let invalid =
+            LendingMarketUpdate::ElevationGroup(ElevationGroup { id: 0, ..group }).value();
+        let close_factor = LendingMarketUpdate::LiquidationCloseFactorPct(20).value()?;
+
+        // Then
+        assert_eq!(&value[..8], &[100, 0, 1, 90, 92, 1, 2, 0]);
+        assert_eq!(&value[8..40], debt_reserve.as_ref());
+        assert_matches!(invalid, Err(Error::Instruction(_)));
+        assert_eq!(LendingMarketUpdate::ElevationGroup(group).mode(), 9);
+        assert_eq!(close_factor[0], 20);
+        assert!(close_factor[1..].iter().all(|&byte| byte == 0));
+
+        Ok(())
+    }
+}
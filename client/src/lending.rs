@@ -1,23 +1,29 @@
-use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, system_instruction};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
 use spl_token_2022::{extension::StateWithExtensions, state};
 use tracing::{debug, instrument};
 
-use crate::transaction::get_rpc;
+use crate::{
+    config::{Context, WSOL_MINT},
+    error::{Error, Result},
+    transaction::process_rpc_error,
+};
 
 /// Get the mint of an ATA
 ///
 /// # Parameters
+/// * `ctx` - The cluster to read from,
 /// * `account` Account (address) to read.
 ///
 /// # Returns
 /// If the account does not exist, or is not of a valid type,
 /// `None` will be returned.
-#[instrument]
-pub async fn get_mint_address(account: &Pubkey) -> Option<Pubkey> {
+#[instrument(skip(ctx))]
+pub async fn get_mint_address(ctx: &Context, account: &Pubkey) -> Option<Pubkey> {
     debug!("getting mint address associated to account");
-    let rpc = get_rpc();
-
-    let account = rpc.get_account(account).await.ok()?;
+    let account = ctx.rpc.get_account(account).await.ok()?;
     Some(
         StateWithExtensions::<state::Account>::unpack(&account.data)
             .ok()?
@@ -26,17 +32,123 @@ pub async fn get_mint_address(account: &Pubkey) -> Option<Pubkey> {
     )
 }
 
+/// Get the amount of tokens held by a token account.
+///
+/// # Parameters
+/// * `ctx` - The cluster to read from,
+/// * `account` - Token account (address) to read.
+///
+/// # Returns
+/// The raw amount of tokens (not adjusted for decimals), 0 if the account does not exist.
+///
+/// # Errors
+/// If the RPC could not be reached.
+#[instrument(skip(ctx))]
+pub async fn get_token_balance(ctx: &Context, account: &Pubkey) -> Result<u64> {
+    debug!("getting token balance of account");
+    let rpc = &ctx.rpc;
+
+    let Some(account) = rpc
+        .get_account_with_commitment(account, rpc.commitment())
+        .await
+        .map_err(process_rpc_error)?
+        .value
+    else {
+        return Ok(0);
+    };
+    Ok(StateWithExtensions::<state::Account>::unpack(&account.data)
+        .map(|state| state.base.amount)
+        .unwrap_or_default())
+}
+
+/// Check whether an account exists on chain.
+///
+/// # Parameters
+/// * `ctx` - The cluster to read from,
+/// * `account` - Account (address) to look for.
+///
+/// # Errors
+/// If the RPC could not be reached.
+#[instrument(skip(ctx))]
+pub async fn account_exists(ctx: &Context, account: &Pubkey) -> Result<bool> {
+    let rpc = &ctx.rpc;
+    Ok(rpc
+        .get_account_with_commitment(account, rpc.commitment())
+        .await
+        .map_err(process_rpc_error)?
+        .value
+        .is_some())
+}
+
+/// Get the amount of lamports held by an account.
+///
+/// # Parameters
+/// * `ctx` - The cluster to read from,
+/// * `account` - Account (address) to read.
+///
+/// # Errors
+/// If the RPC could not be reached.
+#[instrument(skip(ctx))]
+pub async fn get_lamports(ctx: &Context, account: &Pubkey) -> Result<u64> {
+    debug!("getting lamports of account");
+    ctx.rpc
+        .get_balance(account)
+        .await
+        .map_err(process_rpc_error)
+}
+
+/// Instructions to create the associated token account of an owner if it does not exist yet.
+///
+/// # Parameters
+/// * `payer` - Account paying for the creation of the ATA,
+/// * `owner` - Owner of the ATA,
+/// * `mint` - Mint of the ATA.
+///
+/// # Returns
+/// The ATA address and the (idempotent) instruction to create it.
+pub fn create_ata(payer: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> (Pubkey, Instruction) {
+    (
+        get_associated_token_address(owner, mint),
+        create_associated_token_account_idempotent(payer, owner, mint, &spl_token::ID),
+    )
+}
+
+/// Instructions to wrap SOL into the WSOL associated token account of an owner.
+///
+/// The ATA is created if needed, then credited and synchronized.
+///
+/// # Parameters
+/// * `payer` - Account paying for the creation of the ATA,
+/// * `owner` - Owner of the SOL to wrap,
+/// * `lamports` - Amount of lamports to wrap.
+///
+/// # Errors
+/// If the `sync_native` instruction could not be built.
+#[expect(clippy::result_large_err)]
+pub fn wrap_sol(payer: &Pubkey, owner: &Pubkey, lamports: u64) -> Result<Vec<Instruction>> {
+    let (ata, create) = create_ata(payer, owner, &WSOL_MINT);
+    Ok(vec![
+        create,
+        system_instruction::transfer(owner, &ata, lamports),
+        spl_token::instruction::sync_native(&spl_token::ID, &ata)
+            .map_err(|err| Error::Instruction(err.to_string()))?,
+    ])
+}
+
 #[cfg(test)]
 #[cfg_attr(coverage_nightly, coverage(off))]
+#[expect(clippy::unwrap_in_result)]
 mod tests {
 
-    use std::assert_matches::assert_matches;
+    use std::assert_matches;
 
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
     use solana_sdk::pubkey;
     use test_log::test;
     use tracing::info;
 
-    use crate::config::set_config;
+    use crate::config::{mock::MockRpc, test_context};
 
     use super::*;
     type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
@@ -47,14 +159,44 @@ mod tests {
         const BSOL_MINT: Pubkey = pubkey!("bSo13r4TkiE4KumL71LsHTPpL2euBYLFx6h9HP3piy1");
         const BSOL_ATA: Pubkey = pubkey!("FtyYfaF1w7qZVHjLwB9mb4mhSjiFh1Fc1dWbQyrhN6dT");
 
-        set_config()?;
+        let (ctx, requests) = test_context(MockRpc::fixture("bsol_ata")?)?;
 
         // When
-        let mint = get_mint_address(&BSOL_ATA).await;
+        let mint = get_mint_address(&ctx, &BSOL_ATA).await;
+        let balance = get_token_balance(&ctx, &BSOL_ATA).await;
 
         // Then
         info!("mint: {mint:?}");
         assert_matches!(mint, Some(key) if key == BSOL_MINT);
+        assert_matches!(balance, Ok(2_500_000_000));
+        let params = requests.params(RpcRequest::GetAccountInfo);
+        assert_eq!(params.len(), 2, "{params:?}");
+        assert!(
+            params.iter().all(|param| param[0] == BSOL_ATA.to_string()),
+            "{params:?}"
+        );
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn missing_account() -> TestResult {
+        // Given
+        let account = Pubkey::new_unique();
+        let rpc = MockRpc::default().respond(
+            RpcRequest::GetAccountInfo,
+            json!({ "context": { "slot": 1_u64 }, "value": null }),
+        );
+        let (ctx, requests) = test_context(rpc)?;
+
+        // When
+        let mint = get_mint_address(&ctx, &account).await;
+        let balance = get_token_balance(&ctx, &account).await;
+
+        // Then
+        assert_matches!(mint, None);
+        assert_matches!(balance, Ok(0));
+        assert_eq!(requests.params(RpcRequest::GetAccountInfo).len(), 2);
 
         Ok(())
     }
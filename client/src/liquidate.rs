@@ -0,0 +1,659 @@
+//! Liquidation of the unhealthy obligations of a lending market.
+//!
+//! All the obligations of the market are fetched at once, and evaluated at the current oracle
+//! prices. For each unhealthy one, the borrow repaid and the collateral seized are chosen to
+//! maximize the liquidation bonus under the close factor of the market. The repaid tokens are
+//! either the liquidator's, or flash borrowed from the repaid reserve and paid back with the seized
+//! collateral (swapped through a Raydium CPMM pool when it is not the borrowed token).
+
+use core::fmt;
+use std::collections::HashMap;
+
+use ::klend::state::{LendingMarket, Obligation, Reserve};
+use anchor_client::anchor_lang::Discriminator;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+};
+use tracing::{debug, info, instrument, warn};
+
+use crate::{
+    config::Context,
+    error::{Error, Result},
+    klend::{
+        LiquidationReserves,
+        flash_loan::FlashLoan,
+        liquidate_instructions,
+        refresh::{obligation_reserves, refresh_obligation, refresh_reserve},
+        state::{self as klend_state, ReserveSummary},
+    },
+    monitor::{Health, positions},
+    oracle::Price,
+    raydium::{
+        cpmm::{PoolKeys, swap_base_input_instructions},
+        quote::{ConstantProduct, less_slippage},
+    },
+    transaction::{execute_instructions, process_rpc_error},
+};
+
+/// Size of the data of an obligation account, with its discriminator.
+///
+/// The Rust struct is padded, so it is not derived from its size.
+const OBLIGATION_SIZE: u64 = 3344;
+/// Offset of the lending market in the data of an obligation: after its discriminator, tag and
+/// last update.
+const LENDING_MARKET_OFFSET: usize = 8 + 8 + 16;
+/// Denominator of the liquidation bonuses, in basis points.
+const BPS_DENOMINATOR: f64 = 10_000.0;
+
+/// How the obligations are liquidated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LiquidationOptions {
+    /// Minimum value of the bonus of a liquidation.
+    pub min_profit_value: f64,
+    /// Maximum number of obligations liquidated by a sweep.
+    pub max_liquidations: usize,
+    /// Tolerance on the amounts of tokens received, in basis points.
+    pub slippage_bps: u16,
+    /// Whether the repaid tokens are flash borrowed rather than the liquidator's.
+    pub flash_loan: bool,
+}
+
+/// The liquidation of an obligation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Liquidation {
+    pub obligation: Pubkey,
+    /// Health of the obligation at the current prices.
+    pub health: Health,
+    /// Reserve the repaid liquidity was borrowed from.
+    pub repay_reserve: Pubkey,
+    /// Reserve of the collateral seized.
+    pub withdraw_reserve: Pubkey,
+    /// Amount of tokens repaid.
+    pub repay_amount: u64,
+    /// Bonus on the seized collateral, in basis points.
+    pub bonus_bps: u64,
+    /// Value of the bonus.
+    pub profit: f64,
+    /// Collateral tokens expected to be seized.
+    pub collateral_amount: u64,
+    /// Liquidity they are expected to be redeemed for.
+    pub liquidity_amount: u64,
+}
+
+impl fmt::Display for Liquidation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "liquidation of {} ({}): repay {} tokens of {} for {} collateral tokens of {} (bonus \
+             {} bps, profit {:.4})",
+            self.obligation,
+            self.health,
+            self.repay_amount,
+            self.repay_reserve,
+            self.collateral_amount,
+            self.withdraw_reserve,
+            self.bonus_bps,
+            self.profit,
+        )
+    }
+}
+
+/// Fetches all the obligations of a lending market.
+///
+/// # Errors
+/// If the RPC could not be reached or an obligation could not be decoded.
+#[instrument(skip(ctx))]
+pub async fn scan(ctx: &Context, market: &Pubkey) -> Result<Vec<(Pubkey, Obligation)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize(OBLIGATION_SIZE),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &Obligation::DISCRIMINATOR)),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                LENDING_MARKET_OFFSET,
+                market.as_ref(),
+            )),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    let accounts = ctx
+        .rpc
+        .get_program_accounts_with_config(&ctx.klend_program, config)
+        .await
+        .map_err(process_rpc_error)?;
+    debug!(count = accounts.len(), "obligations fetched");
+
+    let mut obligations = Vec::with_capacity(accounts.len());
+    for (address, account) in accounts {
+        obligations.push((address, klend_state::decode(&address, &account.data)?));
+    }
+    Ok(obligations)
+}
+
+/// Chooses the most profitable liquidation of an obligation.
+///
+/// Every pair of borrow and deposit is considered: at most the close factor of the market of the
+/// borrow is repaid (all of it under the market's full liquidation threshold), and no more than
+/// the collateral can cover with its bonus. The bonus grows with the excess of the loan to value
+/// over the liquidation threshold, within the bounds of the collateral's reserve.
+///
+/// # Parameters
+/// * `address` - The obligation,
+/// * `obligation` - Its state,
+/// * `reserves` - Its reserves, by address,
+/// * `prices` - The prices of their oracles, by address (see [`Health::compute`]),
+/// * `market` - The lending market of the obligation.
+///
+/// # Returns
+/// Nothing if the obligation is healthy.
+///
+/// # Errors
+/// If a reserve of the obligation is missing.
+#[expect(
+    clippy::result_large_err,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+pub fn best_liquidation(
+    address: Pubkey,
+    obligation: &Obligation,
+    reserves: &HashMap<Pubkey, Reserve>,
+    prices: &HashMap<Pubkey, Price>,
+    market: &LendingMarket,
+) -> Result<Option<Liquidation>> {
+    let health = Health::compute(obligation, reserves, prices)?;
+    if health.factor() >= 1.0_f64 {
+        return Ok(None);
+    }
+    // how far the loan to value is over the liquidation threshold
+    let excess_bps =
+        (health.loan_to_value() - health.liquidation_threshold / health.deposits) * BPS_DENOMINATOR;
+    let close_factor = f64::from(market.liquidation_max_debt_close_factor_pct) / 100.0_f64;
+    let at_once = match market.max_liquidatable_debt_market_value_at_once {
+        0 => f64::INFINITY,
+        value => value as f64,
+    };
+
+    let (borrows, deposits) = positions(obligation, reserves, prices)?;
+    let mut best: Option<Liquidation> = None;
+    for debt in &borrows {
+        let repayable = if debt.value < market.min_full_liquidation_amount_threshold as f64 {
+            debt.value
+        } else {
+            debt.value * close_factor
+        };
+        for collateral in &deposits {
+            let config = &collateral.reserve.config;
+            let bonus_bps = excess_bps.clamp(
+                f64::from(config.min_liquidation_bonus_bps),
+                f64::from(
+                    config
+                        .max_liquidation_bonus_bps
+                        .max(config.min_liquidation_bonus_bps),
+                ),
+            ) as u64;
+            let bonus = bonus_bps as f64 / BPS_DENOMINATOR;
+            let repay_value = repayable
+                .min(at_once)
+                .min(collateral.value / (1.0_f64 + bonus));
+            let repay_amount = (repay_value / debt.price * debt.scale) as u64;
+            let profit = repay_value * bonus;
+            if repay_amount == 0 || best.is_some_and(|best| best.profit >= profit) {
+                continue;
+            }
+
+            let seized = repay_value * (1.0_f64 + bonus) / collateral.price;
+            let total_supply = ReserveSummary::from(collateral.reserve).total_supply;
+            best = Some(Liquidation {
+                obligation: address,
+                health,
+                repay_reserve: debt.address,
+                withdraw_reserve: collateral.address,
+                repay_amount,
+                bonus_bps,
+                profit,
+                collateral_amount: (seized / total_supply
+                    * collateral.reserve.collateral.mint_total_supply as f64)
+                    as u64,
+                liquidity_amount: (seized * collateral.scale) as u64,
+            });
+        }
+    }
+
+    Ok(best)
+}
+
+/// The accounts used to evaluate the obligations of a market.
+struct MarketState {
+    market: LendingMarket,
+    reserves: HashMap<Pubkey, Reserve>,
+    prices: HashMap<Pubkey, Price>,
+}
+
+impl MarketState {
+    /// Fetches the market, the reserves used by the obligations and their oracles.
+    ///
+    /// # Errors
+    /// If the market or a reserve could not be fetched. The oracles that could not be read are
+    /// skipped, the prices of their reserves being used instead.
+    async fn fetch(
+        ctx: &Context,
+        market: &Pubkey,
+        obligations: &[(Pubkey, Obligation)],
+    ) -> Result<Self> {
+        let mut reserves = HashMap::new();
+        let mut prices = HashMap::new();
+        for (_, obligation) in obligations {
+            for address in obligation_reserves(obligation) {
+                if reserves.contains_key(&address) {
+                    continue;
+                }
+                let reserve: Reserve = klend_state::fetch(ctx, &address).await?;
+                let oracle = reserve.config.token_info.pyth_configuration.price;
+                if oracle != Pubkey::default() && !prices.contains_key(&oracle) {
+                    match Price::fetch(ctx, &oracle).await {
+                        Ok(price) => {
+                            prices.insert(oracle, price);
+                        }
+                        Err(err) => warn!(%oracle, %err, "could not read the oracle"),
+                    }
+                }
+                reserves.insert(address, reserve);
+            }
+        }
+
+        Ok(Self {
+            market: klend_state::fetch(ctx, market).await?,
+            reserves,
+            prices,
+        })
+    }
+
+    /// The reserve at an address.
+    #[expect(clippy::result_large_err)]
+    fn reserve(&self, address: &Pubkey) -> Result<&Reserve> {
+        self.reserves
+            .get(address)
+            .ok_or_else(|| Error::Liquidation(format!("unknown reserve {address}")))
+    }
+}
+
+/// Liquidates the most profitable unhealthy obligations of a market.
+///
+/// # Parameters
+/// * `ctx` - The cluster and programs to use,
+/// * `liquidator` - Owner of the repaid tokens, receiving the collateral,
+/// * `market` - The lending market,
+/// * `pool` - A Raydium CPMM pool swapping the seized collateral for the repaid tokens, to pay
+///   back the flash loans,
+/// * `options` - How the obligations are liquidated.
+///
+/// # Returns
+/// The signatures of the liquidations. Those which failed are logged and skipped.
+///
+/// # Errors
+/// If the obligations or their accounts could not be fetched.
+#[instrument(skip(ctx, liquidator, options))]
+pub async fn sweep(
+    ctx: &Context,
+    liquidator: &Keypair,
+    market: Pubkey,
+    pool: Option<Pubkey>,
+    options: &LiquidationOptions,
+) -> Result<Vec<Signature>> {
+    let obligations = scan(ctx, &market).await?;
+    let state = MarketState::fetch(ctx, &market, &obligations).await?;
+    let mut liquidations = vec![];
+    for (address, obligation) in &obligations {
+        match best_liquidation(
+            *address,
+            obligation,
+            &state.reserves,
+            &state.prices,
+            &state.market,
+        ) {
+            Ok(Some(liquidation)) => liquidations.push((liquidation, obligation)),
+            Ok(None) => (),
+            Err(err) => warn!(%address, %err, "could not evaluate the obligation"),
+        }
+    }
+    liquidations.retain(|(liquidation, _)| liquidation.profit >= options.min_profit_value);
+    liquidations.sort_by(|(a, _), (b, _)| b.profit.total_cmp(&a.profit));
+    info!(
+        obligations = obligations.len(),
+        liquidatable = liquidations.len(),
+        "market scanned"
+    );
+
+    let swap = match pool {
+        Some(pool) => {
+            let keys = PoolKeys::fetch(ctx, pool).await?;
+            Some((keys.fetch_constant_product(ctx).await?, keys))
+        }
+        None => None,
+    };
+    let mut signatures = vec![];
+    for (liquidation, obligation) in liquidations.iter().take(options.max_liquidations) {
+        info!("{liquidation}");
+        match liquidate(
+            ctx,
+            liquidator,
+            liquidation,
+            obligation,
+            &state,
+            swap.as_ref(),
+            options,
+        )
+        .await
+        {
+            Ok(sig) => signatures.push(sig),
+            Err(err) => warn!(obligation = %liquidation.obligation, %err, "liquidation failed"),
+        }
+    }
+
+    Ok(signatures)
+}
+
+/// Liquidates an obligation.
+///
+/// # Errors
+/// If a flash loan cannot be paid back by the seized collateral, or the transaction fails.
+async fn liquidate(
+    ctx: &Context,
+    liquidator: &Keypair,
+    liquidation: &Liquidation,
+    obligation: &Obligation,
+    state: &MarketState,
+    swap: Option<&(ConstantProduct, PoolKeys)>,
+    options: &LiquidationOptions,
+) -> Result<Signature> {
+    let program = ctx.klend_program;
+    let user = liquidator.pubkey();
+    let reserves = LiquidationReserves {
+        repay: (
+            liquidation.repay_reserve,
+            state.reserve(&liquidation.repay_reserve)?,
+        ),
+        withdraw: (
+            liquidation.withdraw_reserve,
+            state.reserve(&liquidation.withdraw_reserve)?,
+        ),
+    };
+    let mut instructions = vec![];
+    for address in obligation_reserves(obligation) {
+        instructions.push(refresh_reserve(&program, address, state.reserve(&address)?));
+    }
+    instructions.push(refresh_obligation(
+        &program,
+        liquidation.obligation,
+        obligation,
+    ));
+    instructions.extend(liquidate_instructions(
+        &program,
+        &user,
+        obligation.lending_market,
+        liquidation.obligation,
+        reserves,
+        liquidation.repay_amount,
+        less_slippage(liquidation.liquidity_amount, options.slippage_bps),
+    ));
+
+    if !options.flash_loan {
+        let sig = execute_instructions(ctx, &instructions, &[liquidator]).await?;
+        info!("Liquidated: {sig}");
+        return Ok(sig);
+    }
+
+    // the seized liquidity, swapped for the repaid tokens if needed, pays back the loan
+    let loan = FlashLoan::new(
+        program,
+        liquidation.repay_reserve,
+        reserves.repay.1,
+        user,
+        liquidation.repay_amount,
+    );
+    let seized = less_slippage(liquidation.liquidity_amount, options.slippage_bps);
+    let (repay_mint, withdraw_mint) = (
+        reserves.repay.1.liquidity.mint_pubkey,
+        reserves.withdraw.1.liquidity.mint_pubkey,
+    );
+    let available = if repay_mint == withdraw_mint {
+        seized
+    } else {
+        let Some((pool, keys)) = swap.filter(|(_, keys)| {
+            [keys.mint_0, keys.mint_1].contains(&repay_mint)
+                && [keys.mint_0, keys.mint_1].contains(&withdraw_mint)
+        }) else {
+            return Err(Error::Liquidation(format!(
+                "a pool is needed to swap {withdraw_mint} for {repay_mint}"
+            )));
+        };
+        let min_out = pool
+            .swap_base_input(keys.zero_for_one(&withdraw_mint)?, seized)?
+            .minimum_amount_out(options.slippage_bps);
+        instructions.extend(swap_base_input_instructions(
+            &user,
+            keys,
+            withdraw_mint,
+            seized,
+            min_out,
+        )?);
+        min_out
+    };
+    let owed = loan.repay_amount()?;
+    if available < owed {
+        return Err(Error::Liquidation(format!(
+            "the seized collateral ({available} tokens) cannot pay back the flash loan ({owed} \
+             tokens)"
+        )));
+    }
+
+    loan.with(instructions).execute(ctx, liquidator, &[]).await
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+#[expect(clippy::unwrap_in_result)]
+mod tests {
+    use std::assert_matches;
+
+    use serde_json::Value;
+    use solana_account_decoder::UiAccount;
+    use solana_client::{rpc_request::RpcRequest, rpc_response::RpcKeyedAccount};
+    use solana_sdk::account::Account;
+    use test_log::test;
+
+    use super::*;
+    use crate::{
+        config::{mock::MockRpc, profile::DEVNET_KLEND_PROGRAM, test_context},
+        klend::{self, FARMS_PROGRAM_ID, state::zeroed},
+    };
+
+    type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
+
+    const ONE: u128 = 1 << 60;
+    /// Base units of a token.
+    const TOKEN: u64 = 1_000_000_000;
+
+    /// A reserve of a 9 decimals token worth $100, holding 1000 tokens for as many collateral
+    /// tokens, liquidated with a 2% to 10% bonus.
+    #[expect(clippy::result_large_err)]
+    fn reserve() -> Result<Reserve> {
+        let mut reserve: Reserve = zeroed()?;
+        reserve.liquidity.mint_decimals = 9;
+        reserve.liquidity.available_amount = 1000 * TOKEN;
+        reserve.liquidity.market_price_sf = 100 * ONE;
+        reserve.liquidity.cumulative_borrow_rate_bsf.value[0] = 1;
+        reserve.collateral.mint_total_supply = 1000 * TOKEN;
+        reserve.config.liquidation_threshold_pct = 80;
+        reserve.config.borrow_factor_pct = 100;
+        reserve.config.min_liquidation_bonus_bps = 200;
+        reserve.config.max_liquidation_bonus_bps = 1000;
+        Ok(reserve)
+    }
+
+    #[test]
+    fn choose_liquidation() -> TestResult {
+        // Given
+        let (sol, bsol, usdc) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut reserves = HashMap::new();
+        reserves.insert(sol, reserve()?);
+        reserves.insert(usdc, reserve()?);
+        let mut generous = reserve()?;
+        generous.config.min_liquidation_bonus_bps = 500;
+        reserves.insert(bsol, generous);
+        let mut market: LendingMarket = zeroed()?;
+        market.liquidation_max_debt_close_factor_pct = 20;
+
+        // $1000 of SOL and $100 of bSOL deposited, $900 of USDC borrowed
+        let mut obligation: Obligation = zeroed()?;
+        obligation.deposits[0].deposit_reserve = sol;
+        obligation.deposits[0].deposited_amount = 10 * TOKEN;
+        obligation.deposits[1].deposit_reserve = bsol;
+        obligation.deposits[1].deposited_amount = TOKEN;
+        obligation.borrows[0].borrow_reserve = usdc;
+        obligation.borrows[0].borrowed_amount_sf = u128::from(9 * TOKEN) * ONE;
+        obligation.borrows[0].cumulative_borrow_rate_bsf.value[0] = 1;
+        let address = Pubkey::new_unique();
+        let mut healthy = obligation;
+        healthy.borrows[0].borrowed_amount_sf = u128::from(8 * TOKEN) * ONE;
+
+        // When
+        let none = best_liquidation(address, &healthy, &reserves, &HashMap::new(), &market)?;
+        let best = best_liquidation(address, &obligation, &reserves, &HashMap::new(), &market)?
+            .ok_or("no liquidation")?;
+
+        // Then
+        assert_eq!(none, None, "the obligation is healthy");
+        assert!(best.health.factor() < 1.0, "{best}");
+        assert_eq!(best.repay_reserve, usdc);
+        // the close factor allows $180, the bSOL cover $95 with a 5% bonus ($4.76) and the SOL
+        // all of it with a 2% one ($3.60)
+        assert_eq!(best.withdraw_reserve, bsol, "{best}");
+        assert_eq!(best.bonus_bps, 500);
+        assert!(
+            (100.0_f64 / 1.05).mul_add(-0.05, best.profit).abs() < 1e-6,
+            "{best}"
+        );
+        assert!(best.collateral_amount <= TOKEN, "{best}");
+        assert!(best.liquidity_amount >= TOKEN - 1, "{best}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn liquidation_instruction() -> TestResult {
+        // Given
+        let (liquidator, market, obligation) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let (repay_reserve, withdraw_reserve) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let repay = reserve()?;
+        let mut withdraw = reserve()?;
+        withdraw.farm_collateral = Pubkey::new_unique();
+        let reserves = LiquidationReserves {
+            repay: (repay_reserve, &repay),
+            withdraw: (withdraw_reserve, &withdraw),
+        };
+
+        // When
+        let instructions = liquidate_instructions(
+            &DEVNET_KLEND_PROGRAM,
+            &liquidator,
+            market,
+            obligation,
+            reserves,
+            TOKEN,
+            TOKEN,
+        );
+
+        // Then
+        let ix = instructions.last().ok_or("no instruction")?;
+        let accounts: Vec<_> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+        assert_eq!(ix.data[..8], [162, 161, 35, 143, 30, 187, 185, 103], "v2");
+        assert_eq!(accounts.len(), 25);
+        assert_eq!(
+            accounts[20..],
+            [
+                klend::pda::obligation_farm_user_state(
+                    &FARMS_PROGRAM_ID,
+                    &withdraw.farm_collateral,
+                    &obligation
+                ),
+                withdraw.farm_collateral,
+                // no debt farm
+                DEVNET_KLEND_PROGRAM,
+                DEVNET_KLEND_PROGRAM,
+                FARMS_PROGRAM_ID,
+            ],
+        );
+
+        Ok(())
+    }
+
+    /// The response of `getProgramAccounts` for a klend account.
+    fn program_accounts(address: Pubkey, data: Vec<u8>) -> serde_json::Result<Value> {
+        let account = Account {
+            data,
+            owner: DEVNET_KLEND_PROGRAM,
+            ..Account::default()
+        };
+        serde_json::to_value([RpcKeyedAccount {
+            pubkey: address.to_string(),
+            account: UiAccount::encode(&address, &account, UiAccountEncoding::Base64, None, None),
+        }])
+    }
+
+    #[test(tokio::test)]
+    async fn scan_market() -> TestResult {
+        // Given
+        let (market, address) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = Obligation::DISCRIMINATOR.to_vec();
+        data.resize(usize::try_from(OBLIGATION_SIZE)?, 0);
+        data[LENDING_MARKET_OFFSET..LENDING_MARKET_OFFSET + 32].copy_from_slice(market.as_ref());
+        let rpc = MockRpc::default().respond(
+            RpcRequest::GetProgramAccounts,
+            program_accounts(address, data)?,
+        );
+        let (ctx, requests) = test_context(rpc)?;
+        let bad_rpc = MockRpc::default().respond(
+            RpcRequest::GetProgramAccounts,
+            program_accounts(address, vec![0; 8])?,
+        );
+        let (bad_ctx, _) = test_context(bad_rpc)?;
+
+        // When
+        let obligations = scan(&ctx, &market).await?;
+        let bad_data = scan(&bad_ctx, &market).await;
+
+        // Then
+        assert_eq!(obligations.len(), 1);
+        assert_eq!(obligations[0].0, address);
+        assert_eq!(obligations[0].1.lending_market, market);
+        let params = requests.params(RpcRequest::GetProgramAccounts);
+        let filters = &params[0][1]["filters"];
+        assert_eq!(filters[0]["dataSize"], OBLIGATION_SIZE);
+        assert_eq!(filters[2]["memcmp"]["offset"], LENDING_MARKET_OFFSET);
+        assert_eq!(filters[2]["memcmp"]["bytes"], market.to_string());
+        assert_matches!(bad_data.err(), Some(Error::AccountDecode { address: decoded, .. }) if decoded == address);
+
+        Ok(())
+    }
+}
@@ -1,41 +1,110 @@
 #![feature(assert_matches)]
 
 mod config;
+mod deployment;
 mod error;
 mod klend;
 mod lending;
+mod liquidate;
+mod monitor;
+mod oracle;
+mod protect;
+mod raydium;
+mod sender;
+mod simulation;
 mod transaction;
 
-use std::rc::Rc;
+use core::time::Duration;
+use std::path::{Path, PathBuf};
 
-use anchor_client::{Client, Cluster};
-use clap::{Parser, Subcommand};
-use config::{BSOL_MINT, RPC_HTTP, RPC_WS, TRX_PAYER, WSOL_MINT};
-use klend::{get_program, init_lending_market};
-use solana_sdk::pubkey;
-use solana_sdk::signature::read_keypair_file;
+use ::klend::state::{Obligation, Reserve};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use config::{Context, WSOL_MINT, profile::Profile, read_keypair};
+use deployment::{DeployedPool, Deployment};
+use klend::flash_loan::FlashLoan;
+use klend::obligation::{self, deposit_collateral, init_obligation, withdraw_collateral};
+use klend::reserve_config::{self, ReserveConfigFile};
+use klend::state::{self as klend_state, ObligationSummary, ReserveSummary};
+use klend::{borrow, lend, pda, repay};
+use lending::{create_ata, get_lamports, get_token_balance, wrap_sol};
+use liquidate::LiquidationOptions;
+use monitor::{HealthThresholds, Monitor};
+use protect::ProtectConfig;
+use raydium::amm_v4::{self, AmmKeys, BaseSide, state::AmmInfo};
+use raydium::clmm::{self, Position, state::PoolState as ClmmPoolState};
+use raydium::cpmm::state::{AmmConfig, PoolState};
+use raydium::cpmm::{self, PoolKeys};
+use raydium::quote::less_slippage;
+use solana_sdk::signature::Signature;
+use solana_sdk::{commitment_config::CommitmentLevel, system_program, sysvar};
 use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
-use tracing::{debug, error, info, level_filters::LevelFilter};
+use tracing::{debug, error, info, level_filters::LevelFilter, warn};
 use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt as _, util::SubscriberInitExt as _};
+use transaction::{
+    TransactionOptions, close_lookup_table, create_lookup_table, deactivate_lookup_table,
+    execute_instructions, extend_lookup_table,
+};
 
 type Error = Box<dyn core::error::Error>;
 type Result<T> = core::result::Result<T, Error>;
 
-const WSOL_SOURCE: Pubkey = pubkey!("CzHgrJsCNMayNCfxLZiyghyasDw3TkDGhJKDHZDQr8qd");
-const BSOL_SOURCE: Pubkey = pubkey!("FtyYfaF1w7qZVHjLwB9mb4mhSjiFh1Fc1dWbQyrhN6dT");
+/// Default tolerance on the amounts quoted for the Raydium operations (0.5%).
+const DEFAULT_SLIPPAGE_BPS: u16 = 50;
 
 #[derive(Parser)]
 struct Cli {
+    /// Configuration file holding the profiles (TOML, or JSON with a `.json` extension); the
+    /// devnet values are used if not given.
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Profile of the configuration file to use.
+    #[arg(long, default_value = "devnet", requires = "config")]
+    profile: String,
+
+    /// State file recording the accounts created by `init`, read by the other commands.
+    #[arg(long, default_value = "deployment.json")]
+    state: PathBuf,
+
+    /// Keypair of the admin, overrides the profile's.
     #[arg(short, long)]
-    admin: String,
+    admin: Option<String>,
+    /// Keypair of the user, overrides the profile's.
     #[arg(short, long)]
-    user: String,
+    user: Option<String>,
 
-    #[arg(short, long, default_value_t = String::from("https://api.devnet.solana.com"))]
-    rpc: String,
+    /// Address of the RPC via HTTP, overrides the profile's.
+    #[arg(short, long)]
+    rpc: Option<String>,
+    /// Address of the RPC via `WS`, overrides the profile's.
+    #[arg(short, long)]
+    ws: Option<String>,
+    /// Commitment level of the RPC requests, overrides the profile's.
+    #[arg(long)]
+    commitment: Option<CommitmentLevel>,
 
-    #[arg(short, long, default_value_t = String::from("wss://api.devnet.solana.com/"))]
-    ws: String,
+    /// Address lookup tables used to build v0 transactions (legacy transactions if none).
+    #[arg(long = "lookup-table")]
+    lookup_tables: Vec<Pubkey>,
+
+    /// Compute unit limit of the transactions (the default one if not given).
+    #[arg(long)]
+    cu_limit: Option<u32>,
+    /// Compute unit price of the transactions, in micro-lamports (no priority fee if not given).
+    #[arg(long)]
+    cu_price: Option<u64>,
+    /// Derives the compute unit limit and price not given from a simulation and the recent
+    /// priority fees.
+    #[arg(long)]
+    auto_compute_budget: bool,
+    /// Percentile of the recent priority fees paid with `--auto-compute-budget`.
+    #[arg(long, default_value_t = 75)]
+    fee_percentile: u8,
+    /// Number of times an expired transaction is signed again with a fresh blockhash.
+    #[arg(long, default_value_t = 2)]
+    max_retries: u8,
+    /// Simulates the transactions and displays their outcome instead of sending them.
+    #[arg(long)]
+    dry_run: bool,
 
     #[command(subcommand)]
     command: Option<Commands>,
@@ -43,25 +112,644 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
+    /// Creates the lending market and its reserves, and records them in the state file.
+    Init(InitArgs),
+    Test(TestArgs),
+    /// Derives the klend program addresses of a market and of the user.
+    Pdas(PdasArgs),
+    /// Decodes and displays a klend account.
+    Inspect {
+        /// The account to inspect.
+        address: Pubkey,
+    },
+    /// Manages the user's obligation in a lending market.
+    Obligation {
+        #[command(flatten)]
+        obligation: ObligationArgs,
+        #[command(subcommand)]
+        command: ObligationCommand,
+    },
+    /// Operates Raydium CPMM pools.
+    #[command(subcommand)]
+    Pool(PoolCommand),
+    /// Manages the user's concentrated liquidity positions in Raydium CLMM pools.
+    #[command(subcommand)]
+    Position(PositionCommand),
+    /// Manages the address lookup tables of the admin.
+    #[command(subcommand)]
+    LookupTable(LookupTableCommand),
+    /// Sets the configuration of a reserve from a file, sending only the fields that differ.
+    ApplyReserveConfig {
+        /// The reserve to configure.
+        #[arg(long)]
+        reserve: Pubkey,
+        /// The configuration file (TOML).
+        file: PathBuf,
+    },
+    /// Flash borrows liquidity from a reserve and repays it in the same transaction.
+    FlashLoan(FlashLoanArgs),
+    /// Follows the health of obligations, reporting when it crosses the thresholds.
+    Monitor(MonitorArgs),
+    /// Repays part of the user's debt when the loan to value of their obligation crosses a
+    /// trigger.
+    Protect(ProtectArgs),
+    /// Liquidates the unhealthy obligations of the lending market.
+    Liquidate(LiquidateArgs),
+}
+
+/// Arguments of the `liquidate` command.
+#[derive(Args)]
+struct LiquidateArgs {
+    /// The lending market, overrides the profile's.
+    #[arg(long)]
+    market: Option<Pubkey>,
+    /// Minimum value of the bonus of a liquidation.
+    #[arg(long, default_value_t = 0.0)]
+    min_profit_value: f64,
+    /// Maximum number of obligations liquidated by a sweep of the market.
+    #[arg(long, default_value_t = 10)]
+    max_liquidations: usize,
+    /// Flash borrows the repaid tokens, paying them back with the seized collateral.
+    #[arg(long)]
+    flash_loan: bool,
+    /// A Raydium CPMM pool swapping the seized collateral for the repaid tokens.
+    #[arg(long)]
+    pool: Option<Pubkey>,
+    /// Tolerance on the amounts of tokens received, in basis points.
+    #[arg(long, default_value_t = DEFAULT_SLIPPAGE_BPS)]
+    slippage_bps: u16,
+    /// Sweeps the market again after this many seconds, until interrupted.
+    #[arg(long)]
+    interval_secs: Option<u64>,
+}
+
+/// Arguments of the `protect` command.
+#[derive(Args)]
+struct ProtectArgs {
+    #[command(flatten)]
+    obligation: ObligationArgs,
+    /// Loan to value (e.g. 0.75) above which the obligation is deleveraged.
+    #[arg(long)]
+    trigger_ltv: f64,
+    /// Loan to value the obligation is brought back to.
+    #[arg(long)]
+    target_ltv: f64,
+    /// Maximum value of the debt repaid by a run.
+    #[arg(long)]
+    max_repay_value: f64,
+    /// A Raydium CPMM pool of the collateral and borrowed tokens, to swap the collateral and
+    /// whose LP tokens are withdrawn first.
+    #[arg(long)]
+    pool: Option<Pubkey>,
+    /// Tolerance on the amounts of tokens received from the pool, in basis points.
+    #[arg(long, default_value_t = DEFAULT_SLIPPAGE_BPS)]
+    slippage_bps: u16,
+}
+
+/// Arguments of the `monitor` command.
+#[derive(Args)]
+struct MonitorArgs {
+    /// The user's obligation, followed when no other obligation is given.
+    #[command(flatten)]
+    obligation: ObligationArgs,
+    /// The obligations to follow.
+    #[arg(long = "obligation")]
+    obligations: Vec<Pubkey>,
+    /// Health factor below which an obligation is reported as getting close to its liquidation.
+    #[arg(long, default_value_t = HealthThresholds::default().warning)]
+    warning_health: f64,
+    /// Health factor below which an obligation is reported as about to be liquidated.
+    #[arg(long, default_value_t = HealthThresholds::default().critical)]
+    critical_health: f64,
+    /// Seconds without any update after which the accounts are polled, also the delay between
+    /// the polls when the websocket is unavailable.
+    #[arg(long, default_value_t = 30)]
+    poll_interval_secs: u64,
+}
+
+/// Arguments of the `flash-loan` command.
+#[derive(Args)]
+struct FlashLoanArgs {
+    /// The reserve lending the liquidity.
+    #[arg(long)]
+    reserve: Pubkey,
+    /// Amount of tokens borrowed.
+    #[arg(long)]
+    amount: u64,
+    /// A Raydium CPMM pool of the borrowed tokens, in which they are deposited then withdrawn
+    /// during the loan.
+    #[arg(long)]
+    pool: Option<Pubkey>,
+    /// Tolerance on the amounts of tokens deposited and withdrawn, in basis points.
+    #[arg(long, default_value_t = DEFAULT_SLIPPAGE_BPS)]
+    slippage_bps: u16,
+}
+
+/// Operations on address lookup tables.
+#[derive(Subcommand)]
+enum LookupTableCommand {
+    /// Creates an empty lookup table.
+    Create,
+    /// Adds the accounts of a market, its reserves and a Raydium pool to a lookup table.
+    Extend {
+        /// The lookup table.
+        #[arg(long)]
+        table: Pubkey,
+        /// A lending market, added with its authority.
+        #[arg(long)]
+        market: Option<Pubkey>,
+        /// Reserves, added with their vaults, mints and oracles.
+        #[arg(long = "reserve")]
+        reserves: Vec<Pubkey>,
+        /// A Raydium pool, added with its vaults and mints.
+        #[arg(long)]
+        pool: Option<Pubkey>,
+        /// The type of the Raydium pool.
+        #[arg(long, value_enum, default_value_t = PoolType::Cpmm)]
+        pool_type: PoolType,
+    },
+    /// Deactivates a lookup table, before closing it.
+    Deactivate {
+        /// The lookup table.
+        #[arg(long)]
+        table: Pubkey,
+    },
+    /// Closes a deactivated lookup table.
+    Close {
+        /// The lookup table.
+        #[arg(long)]
+        table: Pubkey,
+    },
+}
+
+/// Operations on Raydium CPMM pools.
+#[derive(Subcommand)]
+enum PoolCommand {
+    /// Creates the pool of two mints and deposits its initial liquidity.
+    Create {
+        /// Index of the fee configuration of the pool.
+        #[arg(long, default_value_t = 0)]
+        config_index: u16,
+        /// Mint of the first token.
+        #[arg(long)]
+        mint_a: Pubkey,
+        /// Initial amount of the first token.
+        #[arg(long)]
+        amount_a: u64,
+        /// Mint of the second token.
+        #[arg(long)]
+        mint_b: Pubkey,
+        /// Initial amount of the second token.
+        #[arg(long)]
+        amount_b: u64,
+    },
+    /// Decodes and displays a pool and its fee configuration.
+    Info {
+        /// The pool state account.
+        #[arg(long)]
+        pool: Pubkey,
+        /// The type of the pool.
+        #[arg(long, value_enum, default_value_t = PoolType::Cpmm)]
+        pool_type: PoolType,
+    },
+    /// Swaps an exact amount of tokens.
+    SwapIn {
+        /// The pool state account.
+        #[arg(long)]
+        pool: Pubkey,
+        /// The type of the pool.
+        #[arg(long, value_enum, default_value_t = PoolType::Cpmm)]
+        pool_type: PoolType,
+        /// Mint of the tokens sold.
+        #[arg(long)]
+        input_mint: Pubkey,
+        /// Amount of tokens sold.
+        #[arg(long)]
+        amount_in: u64,
+        /// Tolerance on the amount of tokens received, in basis points.
+        #[arg(long, default_value_t = DEFAULT_SLIPPAGE_BPS)]
+        slippage_bps: u16,
+    },
+    /// Swaps tokens for an exact amount of the other token (CPMM pools only).
+    SwapOut {
+        /// The pool state account.
+        #[arg(long)]
+        pool: Pubkey,
+        /// Mint of the tokens sold.
+        #[arg(long)]
+        input_mint: Pubkey,
+        /// Amount of tokens received.
+        #[arg(long)]
+        amount_out: u64,
+        /// Tolerance on the amount of tokens sold, in basis points.
+        #[arg(long, default_value_t = DEFAULT_SLIPPAGE_BPS)]
+        slippage_bps: u16,
+    },
+    /// Quotes a swap of an exact amount of tokens without sending it.
+    Quote {
+        /// The pool state account.
+        #[arg(long)]
+        pool: Pubkey,
+        /// The type of the pool.
+        #[arg(long, value_enum, default_value_t = PoolType::Cpmm)]
+        pool_type: PoolType,
+        /// Mint of the tokens sold.
+        #[arg(long)]
+        input_mint: Pubkey,
+        /// Amount of tokens sold.
+        #[arg(long)]
+        amount_in: u64,
+    },
+}
+
+/// Operations on Raydium CLMM positions.
+#[derive(Subcommand)]
+enum PositionCommand {
+    /// Opens a position in a price range, with as much liquidity as the amounts provide.
+    Open {
+        /// The CLMM pool state account.
+        #[arg(long)]
+        pool: Pubkey,
+        /// Lower price of the range, in tokens 1 per token 0.
+        #[arg(long)]
+        price_lower: f64,
+        /// Upper price of the range, in tokens 1 per token 0.
+        #[arg(long)]
+        price_upper: f64,
+        /// Maximum amount of the first token of the pool to deposit.
+        #[arg(long)]
+        amount_0: u64,
+        /// Maximum amount of the second token of the pool to deposit.
+        #[arg(long)]
+        amount_1: u64,
+    },
+    /// Adds liquidity to a position.
+    Increase {
+        /// The mint of the NFT of the position.
+        #[arg(long)]
+        nft_mint: Pubkey,
+        /// Maximum amount of the first token of the pool to deposit.
+        #[arg(long)]
+        amount_0: u64,
+        /// Maximum amount of the second token of the pool to deposit.
+        #[arg(long)]
+        amount_1: u64,
+    },
+    /// Removes liquidity from a position, collecting its fees.
+    Decrease {
+        /// The mint of the NFT of the position.
+        #[arg(long)]
+        nft_mint: Pubkey,
+        /// Liquidity to remove, all of it by default.
+        #[arg(long)]
+        liquidity: Option<u128>,
+        /// Tolerance on the amounts of tokens received, in basis points.
+        #[arg(long, default_value_t = DEFAULT_SLIPPAGE_BPS)]
+        slippage_bps: u16,
+    },
+    /// Collects the fees and rewards of a position.
+    Collect {
+        /// The mint of the NFT of the position.
+        #[arg(long)]
+        nft_mint: Pubkey,
+    },
+    /// Withdraws everything from a position and closes it.
+    Close {
+        /// The mint of the NFT of the position.
+        #[arg(long)]
+        nft_mint: Pubkey,
+        /// Tolerance on the amounts of tokens withdrawn, in basis points.
+        #[arg(long, default_value_t = DEFAULT_SLIPPAGE_BPS)]
+        slippage_bps: u16,
+    },
+    /// Decodes and displays a position and its pool.
+    Info {
+        /// The mint of the NFT of the position.
+        #[arg(long)]
+        nft_mint: Pubkey,
+    },
+}
+
+/// Operations on an obligation.
+#[derive(Subcommand)]
+enum ObligationCommand {
+    /// Creates the obligation (and the user metadata), unless it already exists.
     Init,
-    Test,
+    /// Deposits collateral tokens of a reserve in the obligation.
+    Deposit {
+        /// The reserve of the collateral.
+        #[arg(long)]
+        reserve: Pubkey,
+        /// Amount of collateral tokens to deposit.
+        #[arg(long)]
+        amount: u64,
+    },
+    /// Withdraws collateral tokens of a reserve from the obligation.
+    Withdraw {
+        /// The reserve of the collateral.
+        #[arg(long)]
+        reserve: Pubkey,
+        /// Amount of collateral tokens to withdraw (everything if not given).
+        #[arg(long, default_value_t = u64::MAX)]
+        amount: u64,
+    },
+    /// Repays all the borrows and withdraws all the collateral of the obligation.
+    Close,
+}
+
+/// The user's obligation in a lending market.
+#[derive(Args)]
+struct ObligationArgs {
+    /// The lending market holding the reserves, overrides the profile's.
+    #[arg(long)]
+    market: Option<Pubkey>,
+    /// Tag of the user's obligation (0 for a vanilla obligation).
+    #[arg(long, default_value_t = 0)]
+    tag: u8,
+    /// Id of the user's obligation, to hold several obligations with the same tag.
+    #[arg(long, default_value_t = 0)]
+    id: u8,
+}
+
+impl ObligationArgs {
+    fn seeds(&self) -> pda::ObligationSeeds {
+        pda::ObligationSeeds {
+            tag: self.tag,
+            id: self.id,
+            ..pda::ObligationSeeds::default()
+        }
+    }
+
+    fn address(&self, program_id: &Pubkey, market: &Pubkey, owner: &Pubkey) -> Pubkey {
+        pda::obligation(program_id, owner, market, &self.seeds())
+    }
+}
+
+/// Accounts and amounts used by the test scenario.
+#[derive(Args)]
+struct TestArgs {
+    #[command(flatten)]
+    obligation: ObligationArgs,
+    /// The SOL reserve of the lending market, overrides the profile's.
+    #[arg(long)]
+    sol_reserve: Option<Pubkey>,
+    /// The bSOL reserve of the lending market, overrides the profile's.
+    #[arg(long)]
+    bsol_reserve: Option<Pubkey>,
+    /// The Raydium SOL/bSOL pool, the one of the state file if not given.
+    #[arg(long)]
+    pool: Option<Pubkey>,
+    /// The type of the Raydium pool.
+    #[arg(long, value_enum, default_value_t = PoolType::Cpmm)]
+    pool_type: PoolType,
+    /// Lamports deposited in the SOL reserve.
+    #[arg(long, default_value_t = 100_000_000)]
+    deposit: u64,
+    /// bSOL (base units) borrowed from the bSOL reserve.
+    #[arg(long, default_value_t = 10_000_000)]
+    borrow: u64,
+    /// Maximum lamports added to the Raydium pool.
+    #[arg(long, default_value_t = 10_000_000)]
+    max_sol: u64,
+    /// Tolerance on the amounts quoted for the Raydium pool, in basis points.
+    #[arg(long, default_value_t = DEFAULT_SLIPPAGE_BPS)]
+    slippage_bps: u16,
+}
+
+impl TestArgs {
+    /// The pool given on the command line, or the one recorded in the state file.
+    fn pool(&self, ctx: &Context, deployment: &Deployment) -> Result<(PoolType, Pubkey)> {
+        Ok(match (self.pool, deployment.pool()) {
+            (Some(pool), _) => (self.pool_type, pool),
+            (None, Some(pool)) => (
+                PoolType::from_program(ctx, &pool.program).ok_or_else(|| {
+                    format!("unknown program {} of the recorded pool", pool.program)
+                })?,
+                pool.address,
+            ),
+            (None, None) => return Err("no Raydium pool given, nor recorded by init".into()),
+        })
+    }
+}
+
+/// Accounts recorded by `init` on top of the market and its reserves.
+#[derive(Args)]
+struct InitArgs {
+    /// A Raydium SOL/bSOL pool to record for the test.
+    #[arg(long)]
+    pool: Option<Pubkey>,
+    /// The type of the Raydium pool.
+    #[arg(long, value_enum, default_value_t = PoolType::Cpmm)]
+    pool_type: PoolType,
+}
+
+/// The Raydium pool programs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum PoolType {
+    /// Constant product pools.
+    Cpmm,
+    /// Legacy pools backed by an `OpenBook` market.
+    AmmV4,
+}
+
+impl PoolType {
+    /// The program of the context owning the pools of this type.
+    const fn program(self, ctx: &Context) -> Pubkey {
+        match self {
+            Self::Cpmm => ctx.cpmm_program,
+            Self::AmmV4 => ctx.amm_v4_program,
+        }
+    }
+
+    /// The type of the pools owned by a program of the context, if it is a known one.
+    fn from_program(ctx: &Context, program: &Pubkey) -> Option<Self> {
+        [Self::Cpmm, Self::AmmV4]
+            .into_iter()
+            .find(|pool_type| pool_type.program(ctx) == *program)
+    }
+}
+
+/// A Raydium pool used by the test.
+enum TestPool {
+    Cpmm(PoolKeys),
+    AmmV4(AmmKeys),
 }
 
-fn main() -> Result<()> {
+impl TestPool {
+    async fn fetch(ctx: &Context, pool_type: PoolType, pool: Pubkey) -> error::Result<Self> {
+        Ok(match pool_type {
+            PoolType::Cpmm => Self::Cpmm(PoolKeys::fetch(ctx, pool).await?),
+            PoolType::AmmV4 => Self::AmmV4(AmmKeys::fetch(ctx, pool).await?),
+        })
+    }
+
+    /// The pool as recorded in the state file.
+    const fn deployed(&self) -> DeployedPool {
+        match self {
+            Self::Cpmm(keys) => DeployedPool {
+                program: keys.program,
+                address: keys.pool,
+                lp_mint: keys.lp_mint,
+            },
+            Self::AmmV4(keys) => DeployedPool {
+                program: keys.program,
+                address: keys.amm,
+                lp_mint: keys.lp_mint,
+            },
+        }
+    }
+
+    /// The two mints of the pool.
+    const fn mints(&self) -> [Pubkey; 2] {
+        match self {
+            Self::Cpmm(keys) => [keys.mint_0, keys.mint_1],
+            Self::AmmV4(keys) => [keys.coin_mint, keys.pc_mint],
+        }
+    }
+
+    /// Adds as much of the borrowed bSOL and of `max_sol` lamports as possible to the pool.
+    async fn deposit(
+        &self,
+        ctx: &Context,
+        user: &Keypair,
+        args: &TestArgs,
+    ) -> error::Result<Signature> {
+        match self {
+            Self::Cpmm(keys) => {
+                let (max_0, max_1) = if keys.mint_0 == WSOL_MINT {
+                    (args.max_sol, args.borrow)
+                } else {
+                    (args.borrow, args.max_sol)
+                };
+                // The amounts quoted must stay within the budget, slippage included
+                let lp_amount = keys
+                    .fetch_constant_product(ctx)
+                    .await?
+                    .deposit_amounts(
+                        less_slippage(max_0, args.slippage_bps),
+                        less_slippage(max_1, args.slippage_bps),
+                    )?
+                    .lp_amount;
+                cpmm::deposit(ctx, user, keys, lp_amount, args.slippage_bps).await
+            }
+            Self::AmmV4(keys) => {
+                let base_side = if keys.coin_mint == WSOL_MINT {
+                    BaseSide::Pc
+                } else {
+                    BaseSide::Coin
+                };
+                amm_v4::add_liquidity(ctx, user, keys, args.borrow, base_side, args.slippage_bps)
+                    .await
+            }
+        }
+    }
+
+    /// Burns all the LP tokens of the user, minted by [`Self::deposit`].
+    async fn withdraw(
+        &self,
+        ctx: &Context,
+        user: &Keypair,
+        args: &TestArgs,
+    ) -> error::Result<Signature> {
+        let owner = user.pubkey();
+        let lp_mint = match self {
+            Self::Cpmm(keys) => keys.lp_mint,
+            Self::AmmV4(keys) => keys.lp_mint,
+        };
+        let lp_amount = get_token_balance(ctx, &create_ata(&owner, &owner, &lp_mint).0).await?;
+        match self {
+            Self::Cpmm(keys) => cpmm::withdraw(ctx, user, keys, lp_amount, args.slippage_bps).await,
+            Self::AmmV4(keys) => amm_v4::remove_liquidity(ctx, user, keys, lp_amount).await,
+        }
+    }
+}
+
+/// Accounts whose klend program addresses are derived.
+#[derive(Args)]
+struct PdasArgs {
+    #[command(flatten)]
+    obligation: ObligationArgs,
+    /// Liquidity mints of the reserves whose vaults are derived.
+    #[arg(long)]
+    mint: Vec<Pubkey>,
+    /// A referrer whose accounts are derived.
+    #[arg(long)]
+    referrer: Option<Pubkey>,
+    /// Reserves in which the referrer collects fees.
+    #[arg(long, requires = "referrer")]
+    reserve: Vec<Pubkey>,
+    /// A short URL of a referrer.
+    #[arg(long)]
+    short_url: Option<String>,
+}
+
+/// Balances of a user’s accounts involved in the test.
+#[derive(Debug, Clone, Copy)]
+struct Balances {
+    sol: u64,
+    wsol: u64,
+    bsol: u64,
+}
+
+impl Balances {
+    async fn fetch(ctx: &Context, bsol_mint: &Pubkey, owner: &Pubkey) -> Result<Self> {
+        Ok(Self {
+            sol: get_lamports(ctx, owner).await?,
+            wsol: get_token_balance(ctx, &create_ata(owner, owner, &WSOL_MINT).0).await?,
+            bsol: get_token_balance(ctx, &create_ata(owner, owner, bsol_mint).0).await?,
+        })
+    }
+
+    fn report(&self, step: &str, sig: &Signature, after: &Self) {
+        let delta = |from: u64, to: u64| i128::from(to) - i128::from(from);
+        info!(
+            %sig,
+            sol = delta(self.sol, after.sol),
+            wsol = delta(self.wsol, after.wsol),
+            bsol = delta(self.bsol, after.bsol),
+            "{step} done"
+        );
+    }
+}
+
+#[expect(clippy::unwrap_in_result)]
+#[tokio::main]
+async fn main() -> Result<()> {
     setup_tracing()?;
     info!("Hello World");
     let cli = Cli::parse();
 
-    let admin = Rc::new(read_keypair_file(&cli.admin)?);
-    let client = Client::new(Cluster::Devnet, Rc::clone(&admin));
+    let (profile, mut deployment) = load_profile(&cli)?;
+    debug!("{profile}");
+    let admin = read_keypair(&profile.admin)?;
 
-    setup(&cli, &admin);
+    let ctx = setup(&cli, &profile, admin);
 
     let res = match &cli.command {
-        Some(Commands::Test) => run_test(&cli, &client, &admin),
-        Some(Commands::Init) => run_init(&client, &admin),
+        Some(Commands::Test(args)) => run_test(&ctx, &profile, &deployment, args).await,
+        Some(Commands::Init(args)) => {
+            run_init(&ctx, &profile, &mut deployment, &cli.state, args).await
+        }
+        Some(Commands::Pdas(args)) => run_pdas(&ctx, &profile, args),
+        Some(Commands::Inspect { address }) => run_inspect(&ctx, address).await,
+        Some(Commands::Obligation {
+            obligation,
+            command,
+        }) => run_obligation(&ctx, &profile, obligation, command).await,
+        Some(Commands::Pool(command)) => run_pool(&ctx, &profile, command).await,
+        Some(Commands::Position(command)) => run_position(&ctx, &profile, command).await,
+        Some(Commands::LookupTable(command)) => run_lookup_table(&ctx, command).await,
+        Some(Commands::ApplyReserveConfig { reserve, file }) => {
+            run_apply_reserve_config(&ctx, *reserve, file).await
+        }
+        Some(Commands::FlashLoan(args)) => run_flash_loan(&ctx, &profile, args).await,
+        Some(Commands::Monitor(args)) => run_monitor(&ctx, &profile, args).await,
+        Some(Commands::Protect(args)) => run_protect(&ctx, &profile, args).await,
+        Some(Commands::Liquidate(args)) => run_liquidate(&ctx, &profile, args).await,
         None => {
-            error!("at least one command must be given (init or test)");
+            error!(
+                "at least one command must be given (init, test, pdas, inspect, obligation, pool, position, lookup-table, apply-reserve-config, flash-loan, monitor, protect or liquidate)"
+            );
             return Err("missing command".into());
         }
     };
@@ -73,31 +761,757 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-#[expect(clippy::unwrap_used)]
-fn setup(cli: &Cli, admin: &Keypair) {
-    RPC_HTTP.set(cli.rpc.clone()).unwrap();
-    RPC_WS.set(cli.ws.clone()).unwrap();
-    TRX_PAYER.set(admin.to_bytes()).unwrap();
+/// The profile selected, with the values given on the command line, and the deployment of the
+/// state file.
+///
+/// The market and reserves not given on the command line nor by the profile are the ones of the
+/// state file.
+fn load_profile(cli: &Cli) -> Result<(Profile, Deployment)> {
+    let mut profile = match &cli.config {
+        Some(path) => Profile::load(path, &cli.profile)?,
+        None => Profile::default(),
+    };
+
+    let overrides = [
+        (&mut profile.rpc_url, &cli.rpc),
+        (&mut profile.ws_url, &cli.ws),
+        (&mut profile.admin, &cli.admin),
+        (&mut profile.user, &cli.user),
+    ];
+    for (field, value) in overrides {
+        if let Some(value) = value {
+            field.clone_from(value);
+        }
+    }
+    profile.commitment = cli.commitment.unwrap_or(profile.commitment);
+    let obligation = match &cli.command {
+        Some(Commands::Test(args)) => {
+            profile.sol_reserve = args.sol_reserve.or(profile.sol_reserve);
+            profile.bsol_reserve = args.bsol_reserve.or(profile.bsol_reserve);
+            Some(&args.obligation)
+        }
+        Some(Commands::Pdas(args)) => Some(&args.obligation),
+        Some(Commands::Obligation { obligation, .. }) => Some(obligation),
+        Some(Commands::Monitor(args)) => Some(&args.obligation),
+        Some(Commands::Protect(args)) => Some(&args.obligation),
+        _ => None,
+    };
+    profile.market = obligation.and_then(|args| args.market).or(profile.market);
+    if let Some(Commands::Liquidate(args)) = &cli.command {
+        profile.market = args.market.or(profile.market);
+    }
+
+    let deployment = Deployment::load(&cli.state, profile.klend_program)?;
+    if profile
+        .market
+        .is_none_or(|market| Some(market) == deployment.market)
+    {
+        profile.market = deployment.market;
+        let reserve = |mint| deployment.reserve(&mint).map(|reserve| reserve.address);
+        profile.sol_reserve = profile.sol_reserve.or_else(|| reserve(WSOL_MINT));
+        profile.bsol_reserve = profile.bsol_reserve.or_else(|| reserve(profile.bsol_mint));
+    }
+
+    profile.validate()?;
+    Ok((profile, deployment))
+}
+
+/// An address the command needs, given on the command line or by the profile.
+fn required(address: Option<Pubkey>, name: &str) -> Result<Pubkey> {
+    address.ok_or_else(|| format!("no {name} given, on the command line or in the profile").into())
+}
+
+/// The context of the commands, the admin paying for the transactions.
+fn setup(cli: &Cli, profile: &Profile, admin: Keypair) -> Context {
+    Context {
+        lookup_tables: cli.lookup_tables.clone(),
+        options: TransactionOptions {
+            compute_unit_limit: cli.cu_limit,
+            compute_unit_price: cli.cu_price,
+            auto: cli.auto_compute_budget,
+            priority_fee_percentile: cli.fee_percentile,
+            max_retries: cli.max_retries,
+            dry_run: cli.dry_run,
+        },
+        ..Context::from_profile(profile, admin)
+    }
 }
 
-fn run_test(cli: &Cli, _client: &Client<Rc<Keypair>>, admin: &Keypair) -> Result<()> {
+async fn run_test(
+    ctx: &Context,
+    profile: &Profile,
+    deployment: &Deployment,
+    args: &TestArgs,
+) -> Result<()> {
     info!("running test");
 
-    let user = read_keypair_file(&cli.user)?;
+    let admin = &ctx.payer;
+    let market = required(profile.market, "lending market")?;
+    let sol_reserve = required(profile.sol_reserve, "SOL reserve")?;
+    let bsol_reserve = required(profile.bsol_reserve, "bSOL reserve")?;
+
+    let user = read_keypair(&profile.user)?;
+    let owner = user.pubkey();
 
     debug!("Admin key: {}", admin.pubkey());
-    debug!("User key: {}", user.pubkey());
+    debug!("User key: {}", owner);
+
+    let (pool_type, pool) = args.pool(ctx, deployment)?;
+    let pool = TestPool::fetch(ctx, pool_type, pool).await?;
+
+    let obligation = init_obligation(ctx, &user, market, &args.obligation.seeds()).await?;
+
+    report_reserves(ctx, [sol_reserve, bsol_reserve], "Before the test").await?;
+
+    // 1. Deposit SOL in the lending market and borrow bSOL against it
+    run_step(ctx, profile, "SOL deposit", &owner, async {
+        execute_instructions(
+            ctx,
+            &wrap_sol(&admin.pubkey(), &owner, args.deposit)?,
+            &[&user],
+        )
+        .await?;
+        lend(ctx, &user, market, sol_reserve, WSOL_MINT, args.deposit).await
+    })
+    .await?;
+    let collateral_mint =
+        pda::ReservePdas::new(&ctx.klend_program, &market, &WSOL_MINT).collateral_mint;
+    let collateral =
+        get_token_balance(ctx, &create_ata(&owner, &owner, &collateral_mint).0).await?;
+    run_step(
+        ctx,
+        profile,
+        "SOL collateral deposit",
+        &owner,
+        deposit_collateral(
+            ctx,
+            &user,
+            market,
+            obligation,
+            sol_reserve,
+            WSOL_MINT,
+            collateral,
+        ),
+    )
+    .await?;
+    run_step(
+        ctx,
+        profile,
+        "bSOL borrow",
+        &owner,
+        borrow(
+            ctx,
+            &user,
+            market,
+            obligation,
+            bsol_reserve,
+            profile.bsol_mint,
+            args.borrow,
+        ),
+    )
+    .await?;
+
+    // 2. Add the SOL and the borrowed bSOL to the Raydium pool
+    run_step(ctx, profile, "Raydium deposit", &owner, async {
+        execute_instructions(
+            ctx,
+            &wrap_sol(&admin.pubkey(), &owner, args.max_sol)?,
+            &[&user],
+        )
+        .await?;
+        pool.deposit(ctx, &user, args).await
+    })
+    .await?;
+
+    // 3. Remove the liquidity from the pool
+    run_step(
+        ctx,
+        profile,
+        "Raydium withdrawal",
+        &owner,
+        pool.withdraw(ctx, &user, args),
+    )
+    .await?;
+
+    // 4. Repay the borrowed bSOL
+    run_step(
+        ctx,
+        profile,
+        "bSOL repayment",
+        &owner,
+        repay(
+            ctx,
+            &user,
+            market,
+            obligation,
+            bsol_reserve,
+            profile.bsol_mint,
+            u64::MAX,
+        ),
+    )
+    .await?;
+
+    report_reserves(ctx, [sol_reserve, bsol_reserve], "After the test").await?;
+    report_obligation(ctx, &obligation).await
+}
+
+/// Runs a step of the test and reports its signature and the balance changes it caused.
+///
+/// # Parameters
+/// * `ctx` - The cluster the balances are read from,
+/// * `profile` - The mints whose balances are tracked,
+/// * `name` - Name of the step in the report,
+/// * `owner` - The user whose balances are tracked,
+/// * `step` - The step to run.
+async fn run_step<F>(
+    ctx: &Context,
+    profile: &Profile,
+    name: &str,
+    owner: &Pubkey,
+    step: F,
+) -> Result<()>
+where
+    F: Future<Output = error::Result<Signature>>,
+{
+    let before = Balances::fetch(ctx, &profile.bsol_mint, owner).await?;
+    let sig = step.await?;
+    let after = Balances::fetch(ctx, &profile.bsol_mint, owner).await?;
+    before.report(name, &sig, &after);
 
     Ok(())
 }
 
-fn run_init(client: &Client<Rc<Keypair>>, admin: &Keypair) -> Result<()> {
+/// Logs the state of the reserves used by the test, with the price of their oracle.
+///
+/// # Errors
+/// If a reserve could not be fetched, or if its oracle price would be rejected by klend when
+/// refreshing it (stale or too uncertain).
+async fn report_reserves(ctx: &Context, reserves: [Pubkey; 2], when: &str) -> Result<()> {
+    for reserve in reserves {
+        let state = klend_state::fetch::<Reserve>(ctx, &reserve).await?;
+        let summary = ReserveSummary::from(&state);
+        let Some(price) = oracle::check_reserve(ctx, &state).await? else {
+            info!(%reserve, "{when}: {summary}");
+            continue;
+        };
+        let available = price.usd_value(
+            state.liquidity.available_amount,
+            u8::try_from(state.liquidity.mint_decimals).unwrap_or(u8::MAX),
+        );
+        info!(%reserve, "{when}: {summary}, oracle price {price} (${available:.2} available)");
+    }
+    Ok(())
+}
+
+/// Logs the deposits and borrows of an obligation.
+async fn report_obligation(ctx: &Context, address: &Pubkey) -> Result<()> {
+    let obligation = klend_state::fetch::<Obligation>(ctx, address).await?;
+    info!(
+        %address,
+        "Final obligation state: {}",
+        ObligationSummary::from(&obligation)
+    );
+
+    Ok(())
+}
+
+/// Creates the lending market and its reserves, unless the state file records them, and records
+/// the pool given.
+///
+/// The state file is saved after each step, so that an interrupted run can be resumed; the
+/// accounts already recorded are checked against their on-chain state instead of being created
+/// again.
+///
+/// # Parameters
+/// * `ctx` - The cluster to use, its payer owning the market,
+/// * `profile` - The sources of the admin and the bSOL mint,
+/// * `deployment` - The accounts already created,
+/// * `state` - Path of the state file,
+/// * `args` - The pool to record.
+async fn run_init(
+    ctx: &Context,
+    profile: &Profile,
+    deployment: &mut Deployment,
+    state: &Path,
+    args: &InitArgs,
+) -> Result<()> {
     info!("Initializing tests");
 
-    let market = Keypair::new();
-    info!("Market address: {}", market.pubkey());
+    let sources = [("WSOL", profile.wsol_source), ("bSOL", profile.bsol_source)];
+    for (name, source) in sources
+        .into_iter()
+        .filter_map(|(name, source)| Some((name, source?)))
+    {
+        let balance = get_token_balance(ctx, &source).await?;
+        if balance == 0 {
+            warn!("the admin’s {name} source {source} is empty");
+        }
+    }
+
+    // Nothing is created in dry-run mode, so there is nothing to record either
+    #[expect(clippy::result_large_err)]
+    let save = |current: &Deployment| -> error::Result<()> {
+        if !ctx.options.dry_run {
+            current.save(state)?;
+        }
+        Ok(())
+    };
+
+    if deployment.ensure_market(ctx, save).await?.is_none() {
+        info!("Dry run: the reserves are created once the lending market is");
+        return Ok(());
+    }
+    save(deployment)?;
+    for mint in [WSOL_MINT, profile.bsol_mint] {
+        deployment.ensure_reserve(ctx, mint, save).await?;
+        save(deployment)?;
+    }
+
+    if let Some(pool) = args.pool {
+        let pool = TestPool::fetch(ctx, args.pool_type, pool).await?;
+        let mut mints = pool.mints();
+        mints.sort();
+        let mut expected = [WSOL_MINT, profile.bsol_mint];
+        expected.sort();
+        if mints != expected {
+            return Err(format!(
+                "the pool {} does not hold SOL and bSOL",
+                pool.deployed().address
+            )
+            .into());
+        }
+        deployment.add_pool(pool.deployed());
+        save(deployment)?;
+    }
+
+    info!("Deployment: {deployment}");
+    Ok(())
+}
+
+async fn run_apply_reserve_config(ctx: &Context, reserve: Pubkey, file: &Path) -> Result<()> {
+    let config = ReserveConfigFile::load(file)?;
+    let signatures = reserve_config::apply(ctx, reserve, &config).await?;
+    for sig in signatures {
+        info!("Reserve configuration sent: {sig}");
+    }
+
+    Ok(())
+}
 
-    init_lending_market(client, admin, &market)?;
+async fn run_flash_loan(ctx: &Context, profile: &Profile, args: &FlashLoanArgs) -> Result<()> {
+    let user = read_keypair(&profile.user)?;
+    let owner = user.pubkey();
+    let reserve = klend_state::fetch::<Reserve>(ctx, &args.reserve).await?;
+    let mut loan = FlashLoan::new(
+        ctx.klend_program,
+        args.reserve,
+        &reserve,
+        owner,
+        args.amount,
+    );
+
+    if let Some(pool) = args.pool {
+        let keys = PoolKeys::fetch(ctx, pool).await?;
+        let product = keys.fetch_constant_product(ctx).await?;
+        let base_is_0 = keys.zero_for_one(&reserve.liquidity.mint_pubkey)?;
+        // Deposits the borrowed tokens with the matching amount of the other token
+        let deposit =
+            product.deposit_base(base_is_0, less_slippage(args.amount, args.slippage_bps))?;
+        let withdrawn = product.withdraw(deposit.lp_amount)?;
+        info!(?deposit, ?withdrawn, "liquidity provided during the loan");
+        loan = loan
+            .with(cpmm::deposit_instructions(
+                &owner,
+                &keys,
+                &deposit,
+                args.slippage_bps,
+            ))
+            .with([cpmm::withdraw_instruction(
+                &owner,
+                &keys,
+                deposit.lp_amount,
+                withdrawn,
+                args.slippage_bps,
+            )]);
+    }
+
+    info!(
+        fee = loan.fee()?,
+        repaid = loan.repay_amount()?,
+        "flash loan of {} tokens",
+        args.amount
+    );
+    loan.execute(ctx, &user, &[]).await?;
+
+    Ok(())
+}
+
+async fn run_inspect(ctx: &Context, address: &Pubkey) -> Result<()> {
+    let account = klend_state::Account::fetch(ctx, address).await?;
+    info!(%address, "{account}");
+
+    Ok(())
+}
+
+async fn run_obligation(
+    ctx: &Context,
+    profile: &Profile,
+    args: &ObligationArgs,
+    command: &ObligationCommand,
+) -> Result<()> {
+    let user = read_keypair(&profile.user)?;
+    let market = required(profile.market, "lending market")?;
+    let obligation = args.address(&ctx.klend_program, &market, &user.pubkey());
+
+    match command {
+        ObligationCommand::Init => {
+            init_obligation(ctx, &user, market, &args.seeds()).await?;
+        }
+        ObligationCommand::Deposit { reserve, amount } => {
+            let mint = klend_state::fetch::<Reserve>(ctx, reserve)
+                .await?
+                .liquidity
+                .mint_pubkey;
+            deposit_collateral(ctx, &user, market, obligation, *reserve, mint, *amount).await?;
+        }
+        ObligationCommand::Withdraw { reserve, amount } => {
+            let mint = klend_state::fetch::<Reserve>(ctx, reserve)
+                .await?
+                .liquidity
+                .mint_pubkey;
+            withdraw_collateral(ctx, &user, market, obligation, *reserve, mint, *amount).await?;
+        }
+        ObligationCommand::Close => {
+            obligation::close(ctx, &user, obligation).await?;
+        }
+    }
+
+    report_obligation(ctx, &obligation).await
+}
+
+async fn run_monitor(ctx: &Context, profile: &Profile, args: &MonitorArgs) -> Result<()> {
+    let obligations = if args.obligations.is_empty() {
+        let user = read_keypair(&profile.user)?;
+        let market = required(profile.market, "lending market")?;
+        vec![
+            args.obligation
+                .address(&ctx.klend_program, &market, &user.pubkey()),
+        ]
+    } else {
+        args.obligations.clone()
+    };
+    let thresholds = HealthThresholds {
+        warning: args.warning_health,
+        critical: args.critical_health,
+    };
+    if !(1.0_f64..=thresholds.warning).contains(&thresholds.critical) {
+        return Err("the critical health must be between 1 and the warning health".into());
+    }
+
+    info!(?obligations, ?thresholds, "monitoring");
+    Monitor::new(obligations, thresholds)
+        .run(ctx, Duration::from_secs(args.poll_interval_secs.max(1)))
+        .await;
+    Ok(())
+}
+
+async fn run_protect(ctx: &Context, profile: &Profile, args: &ProtectArgs) -> Result<()> {
+    let user = read_keypair(&profile.user)?;
+    let market = required(profile.market, "lending market")?;
+    let obligation = args
+        .obligation
+        .address(&ctx.klend_program, &market, &user.pubkey());
+    let config = ProtectConfig {
+        trigger_ltv: args.trigger_ltv,
+        target_ltv: args.target_ltv,
+        max_repay_value: args.max_repay_value,
+        slippage_bps: args.slippage_bps,
+    };
+
+    if protect::protect(ctx, &user, obligation, args.pool, &config)
+        .await?
+        .is_some()
+        && !ctx.options.dry_run
+    {
+        report_obligation(ctx, &obligation).await?;
+    }
+    Ok(())
+}
+
+async fn run_liquidate(ctx: &Context, profile: &Profile, args: &LiquidateArgs) -> Result<()> {
+    let liquidator = read_keypair(&profile.user)?;
+    let market = required(profile.market, "lending market")?;
+    let options = LiquidationOptions {
+        min_profit_value: args.min_profit_value,
+        max_liquidations: args.max_liquidations,
+        slippage_bps: args.slippage_bps,
+        flash_loan: args.flash_loan,
+    };
+
+    loop {
+        match liquidate::sweep(ctx, &liquidator, market, args.pool, &options).await {
+            Ok(signatures) => info!(liquidated = signatures.len(), "market swept"),
+            // the next sweep fetches the market again
+            Err(err) if args.interval_secs.is_some() => error!("could not sweep the market: {err}"),
+            Err(err) => return Err(err.into()),
+        }
+        let Some(interval) = args.interval_secs else {
+            return Ok(());
+        };
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+    }
+}
+
+async fn run_pool(ctx: &Context, profile: &Profile, command: &PoolCommand) -> Result<()> {
+    let user = read_keypair(&profile.user)?;
+
+    match command {
+        PoolCommand::Create {
+            config_index,
+            mint_a,
+            amount_a,
+            mint_b,
+            amount_b,
+        } => {
+            let config = cpmm::pda::amm_config(&ctx.cpmm_program, *config_index);
+            let keys = PoolKeys::new(ctx.cpmm_program, config, *mint_a, *mint_b);
+            let (amount_0, amount_1) = if keys.mint_0 == *mint_a {
+                (*amount_a, *amount_b)
+            } else {
+                (*amount_b, *amount_a)
+            };
+            cpmm::initialize(ctx, &user, &keys, amount_0, amount_1, 0).await?;
+            info!("Pool address: {}", keys.pool);
+        }
+        PoolCommand::Info {
+            pool,
+            pool_type: PoolType::Cpmm,
+        } => {
+            let state = klend_state::fetch::<PoolState>(ctx, pool).await?;
+            let config = klend_state::fetch::<AmmConfig>(ctx, &state.amm_config).await?;
+            info!(%pool, "{state}\n{config}");
+        }
+        PoolCommand::Info {
+            pool,
+            pool_type: PoolType::AmmV4,
+        } => {
+            let amm = AmmInfo::fetch(ctx, pool).await?;
+            info!(%pool, "{amm}");
+        }
+        PoolCommand::SwapIn {
+            pool,
+            pool_type,
+            input_mint,
+            amount_in,
+            slippage_bps,
+        } => match TestPool::fetch(ctx, *pool_type, *pool).await? {
+            TestPool::Cpmm(keys) => {
+                cpmm::swap_base_input(ctx, &user, &keys, *input_mint, *amount_in, *slippage_bps)
+                    .await?;
+            }
+            TestPool::AmmV4(keys) => {
+                amm_v4::swap_base_in(ctx, &user, &keys, *input_mint, *amount_in, *slippage_bps)
+                    .await?;
+            }
+        },
+        PoolCommand::SwapOut {
+            pool,
+            input_mint,
+            amount_out,
+            slippage_bps,
+        } => {
+            let keys = PoolKeys::fetch(ctx, *pool).await?;
+            cpmm::swap_base_output(ctx, &user, &keys, *input_mint, *amount_out, *slippage_bps)
+                .await?;
+        }
+        PoolCommand::Quote {
+            pool,
+            pool_type,
+            input_mint,
+            amount_in,
+        } => {
+            let (product, zero_for_one) = match TestPool::fetch(ctx, *pool_type, *pool).await? {
+                TestPool::Cpmm(keys) => (
+                    keys.fetch_constant_product(ctx).await?,
+                    keys.zero_for_one(input_mint)?,
+                ),
+                TestPool::AmmV4(keys) => (
+                    keys.fetch_constant_product(ctx).await?,
+                    keys.zero_for_one(input_mint)?,
+                ),
+            };
+            let quote = product.swap_base_input(zero_for_one, *amount_in)?;
+            info!(%pool, "{quote}");
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_position(ctx: &Context, profile: &Profile, command: &PositionCommand) -> Result<()> {
+    let user = read_keypair(&profile.user)?;
+
+    match command {
+        PositionCommand::Open {
+            pool,
+            price_lower,
+            price_upper,
+            amount_0,
+            amount_1,
+        } => {
+            let state = klend_state::fetch::<ClmmPoolState>(ctx, pool).await?;
+            let tick = |price: f64| -> Result<i32> {
+                Ok(clmm::math::tick_from_price(
+                    price,
+                    state.mint_decimals_0,
+                    state.mint_decimals_1,
+                    state.tick_spacing,
+                )?)
+            };
+            let (tick_lower, tick_upper) = (tick(*price_lower)?, tick(*price_upper)?);
+            let nft_mint = clmm::open_position(
+                ctx, &user, *pool, &state, tick_lower, tick_upper, *amount_0, *amount_1,
+            )
+            .await?;
+            info!("Position NFT: {nft_mint}");
+        }
+        PositionCommand::Increase {
+            nft_mint,
+            amount_0,
+            amount_1,
+        } => {
+            let position = Position::fetch(ctx, nft_mint).await?;
+            clmm::increase_liquidity(ctx, &user, &position, *amount_0, *amount_1).await?;
+        }
+        PositionCommand::Decrease {
+            nft_mint,
+            liquidity,
+            slippage_bps,
+        } => {
+            let position = Position::fetch(ctx, nft_mint).await?;
+            let liquidity = liquidity.unwrap_or(position.state.liquidity);
+            clmm::decrease_liquidity(ctx, &user, &position, liquidity, *slippage_bps).await?;
+        }
+        PositionCommand::Collect { nft_mint } => {
+            let position = Position::fetch(ctx, nft_mint).await?;
+            clmm::collect_fees(ctx, &user, &position).await?;
+        }
+        PositionCommand::Close {
+            nft_mint,
+            slippage_bps,
+        } => {
+            let position = Position::fetch(ctx, nft_mint).await?;
+            clmm::close_position(ctx, &user, &position, *slippage_bps).await?;
+        }
+        PositionCommand::Info { nft_mint } => {
+            let position = Position::fetch(ctx, nft_mint).await?;
+            let (amount_0, amount_1) = position.amounts()?;
+            info!(
+                "{}\n{}\n  worth {amount_0} / {amount_1}",
+                position.pool_state, position.state
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_lookup_table(ctx: &Context, command: &LookupTableCommand) -> Result<()> {
+    match command {
+        LookupTableCommand::Create => {
+            create_lookup_table(ctx, &ctx.payer).await?;
+        }
+        LookupTableCommand::Extend {
+            table,
+            market,
+            reserves,
+            pool,
+            pool_type,
+        } => {
+            // The programs and sysvars passed as accounts by most instructions
+            let mut addresses = vec![
+                spl_token::ID,
+                spl_token_2022::ID,
+                spl_associated_token_account::ID,
+                system_program::ID,
+                sysvar::instructions::ID,
+                sysvar::rent::ID,
+            ];
+            if let Some(market) = market {
+                addresses.extend([
+                    *market,
+                    pda::lending_market_authority(&ctx.klend_program, market),
+                ]);
+            }
+            for reserve in reserves {
+                let state = klend_state::fetch::<Reserve>(ctx, reserve).await?;
+                addresses.extend(klend::refresh::reserve_accounts(
+                    &ctx.klend_program,
+                    *reserve,
+                    &state,
+                ));
+            }
+            if let Some(pool) = pool {
+                addresses.extend(match TestPool::fetch(ctx, *pool_type, *pool).await? {
+                    TestPool::Cpmm(keys) => keys.addresses(),
+                    TestPool::AmmV4(keys) => keys.addresses(),
+                });
+            }
+            extend_lookup_table(ctx, &ctx.payer, table, &addresses).await?;
+        }
+        LookupTableCommand::Deactivate { table } => {
+            deactivate_lookup_table(ctx, &ctx.payer, table).await?;
+        }
+        LookupTableCommand::Close { table } => {
+            close_lookup_table(ctx, &ctx.payer, table).await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn run_pdas(ctx: &Context, profile: &Profile, args: &PdasArgs) -> Result<()> {
+    let owner = read_keypair(&profile.user)?.pubkey();
+    let program = &ctx.klend_program;
+
+    let market = required(profile.market, "lending market")?;
+    info!(
+        "Market authority: {}",
+        pda::lending_market_authority(program, &market)
+    );
+    for mint in &args.mint {
+        let pdas = pda::ReservePdas::new(program, &market, mint);
+        info!(%mint, "Reserve liquidity supply: {}", pdas.liquidity_supply);
+        info!(%mint, "Reserve fee receiver: {}", pdas.fee_receiver);
+        info!(%mint, "Reserve collateral mint: {}", pdas.collateral_mint);
+        info!(%mint, "Reserve collateral supply: {}", pdas.collateral_supply);
+    }
+
+    info!(
+        "Obligation of {owner}: {}",
+        args.obligation.address(program, &market, &owner)
+    );
+    info!(
+        "User metadata of {owner}: {}",
+        pda::user_metadata(program, &owner)
+    );
+
+    if let Some(referrer) = &args.referrer {
+        info!(
+            "Referrer state of {referrer}: {}",
+            pda::referrer_state(program, referrer)
+        );
+        for reserve in &args.reserve {
+            info!(
+                %reserve,
+                "Referrer token state of {referrer}: {}",
+                pda::referrer_token_state(program, referrer, reserve)
+            );
+        }
+    }
+    if let Some(url) = &args.short_url {
+        info!("Short URL {url}: {}", pda::short_url(program, url));
+    }
 
     Ok(())
 }
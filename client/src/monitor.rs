@@ -0,0 +1,751 @@
+//! Monitoring of the health of obligations.
+//!
+//! The obligations, the reserves they use and the oracles of those reserves are followed through
+//! websocket account subscriptions. Their health is computed again after every change, and an
+//! event is logged whenever it crosses a threshold. The accounts are polled when the websocket is
+//! quiet or unavailable, and the subscriptions are made again after the socket drops.
+
+use core::{fmt, time::Duration};
+use std::collections::{BTreeSet, HashMap};
+
+use ::klend::state::{Obligation, Reserve};
+use futures::{StreamExt as _, stream::select_all};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{nonblocking::pubsub_client::PubsubClient, rpc_config::RpcAccountInfoConfig};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use tracing::{debug, error, info, instrument, warn};
+
+use crate::{
+    config::Context,
+    error::{Error, Result},
+    klend::state::{self as klend_state, ReserveSummary, fraction},
+    oracle::Price,
+};
+
+/// The health factors below which an obligation is reported.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HealthThresholds {
+    /// Below it, the obligation is getting close to its liquidation.
+    pub warning: f64,
+    /// Below it, the obligation is about to be liquidated.
+    pub critical: f64,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            warning: 1.2,
+            critical: 1.05,
+        }
+    }
+}
+
+/// How close an obligation is to its liquidation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HealthLevel {
+    Healthy,
+    Warning,
+    Critical,
+    /// The borrowed value exceeds the liquidation threshold of the deposits.
+    Liquidatable,
+}
+
+impl HealthThresholds {
+    /// The level of a health factor.
+    pub fn level(&self, factor: f64) -> HealthLevel {
+        if factor < 1.0 {
+            HealthLevel::Liquidatable
+        } else if factor < self.critical {
+            HealthLevel::Critical
+        } else if factor < self.warning {
+            HealthLevel::Warning
+        } else {
+            HealthLevel::Healthy
+        }
+    }
+}
+
+/// The values an obligation's health is computed from, at the current prices.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Health {
+    /// Value of the deposits.
+    pub deposits: f64,
+    /// Value of the deposits weighted by the liquidation thresholds of their reserves.
+    pub liquidation_threshold: f64,
+    /// Value of the borrows (interests included), weighted by the borrow factors of their
+    /// reserves.
+    pub borrows: f64,
+}
+
+impl Health {
+    /// Computes the health of an obligation.
+    ///
+    /// The prices are those of the reserves' oracles when known, the last ones recorded by the
+    /// reserves otherwise. The borrows accrue the interests recorded by their reserves since the
+    /// last refresh of the obligation.
+    ///
+    /// # Parameters
+    /// * `obligation` - The obligation,
+    /// * `reserves` - The reserves it uses, by address,
+    /// * `prices` - The prices of the oracles, by address.
+    ///
+    /// # Errors
+    /// If a reserve of the obligation is missing.
+    #[expect(clippy::result_large_err)]
+    pub fn compute(
+        obligation: &Obligation,
+        reserves: &HashMap<Pubkey, Reserve>,
+        prices: &HashMap<Pubkey, Price>,
+    ) -> Result<Self> {
+        let get_reserve = |address: &Pubkey| {
+            reserves
+                .get(address)
+                .ok_or_else(|| Error::Monitor(format!("unknown reserve {address}")))
+        };
+        let mut health = Self::default();
+        for deposit in obligation
+            .deposits
+            .iter()
+            .filter(|deposit| deposit.deposit_reserve != Pubkey::default())
+        {
+            let state = get_reserve(&deposit.deposit_reserve)?;
+            let value =
+                collateral_liquidity(state, deposit.deposited_amount) * price(state, prices);
+            health.deposits += value;
+            health.liquidation_threshold +=
+                value * f64::from(state.config.liquidation_threshold_pct) / 100.0_f64;
+        }
+
+        for borrow in obligation
+            .borrows
+            .iter()
+            .filter(|borrow| borrow.borrow_reserve != Pubkey::default())
+        {
+            let state = get_reserve(&borrow.borrow_reserve)?;
+            let amount = owed_liquidity(
+                state,
+                borrow.borrowed_amount_sf,
+                &borrow.cumulative_borrow_rate_bsf.value,
+            );
+            health.borrows =
+                (amount * price(state, prices)).mul_add(borrow_factor(state), health.borrows);
+        }
+
+        Ok(health)
+    }
+
+    /// The health factor: the liquidation value over the borrowed value (infinite without
+    /// borrows). The obligation can be liquidated below 1.
+    pub fn factor(&self) -> f64 {
+        if self.borrows > 0.0 {
+            self.liquidation_threshold / self.borrows
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    /// The loan to value: the borrowed value over the deposited value (0 without deposits).
+    pub fn loan_to_value(&self) -> f64 {
+        if self.deposits > 0.0 {
+            self.borrows / self.deposits
+        } else {
+            0.0
+        }
+    }
+}
+
+impl fmt::Display for Health {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "health factor {:.4} (deposited {:.4}, liquidation value {:.4}, borrowed {:.4})",
+            self.factor(),
+            self.deposits,
+            self.liquidation_threshold,
+            self.borrows
+        )
+    }
+}
+
+/// The price of the liquidity of a reserve: the price of its oracle when known, the last one
+/// recorded by the reserve otherwise.
+pub fn price(reserve: &Reserve, prices: &HashMap<Pubkey, Price>) -> f64 {
+    prices
+        .get(&reserve.config.token_info.pyth_configuration.price)
+        .map_or_else(|| fraction(reserve.liquidity.market_price_sf), Price::value)
+}
+
+/// The amount of liquidity (in tokens) collateral tokens of a reserve are worth.
+#[expect(clippy::cast_precision_loss)]
+pub fn collateral_liquidity(reserve: &Reserve, collateral: u64) -> f64 {
+    let collateral_supply = reserve.collateral.mint_total_supply;
+    if collateral_supply == 0 {
+        return 0.0;
+    }
+    // the share of the reserve's liquidity the collateral is worth
+    collateral as f64 / collateral_supply as f64 * ReserveSummary::from(reserve).total_supply
+}
+
+/// The amount of liquidity (in tokens) owed on a borrow, including the interests recorded by its
+/// reserve since the last refresh of the obligation.
+///
+/// # Parameters
+/// * `reserve` - The reserve the liquidity was borrowed from,
+/// * `borrowed_amount_sf` - The borrowed amount recorded by the obligation (base units),
+/// * `cumulative_borrow_rate` - The cumulative borrow rate recorded with it.
+pub fn owed_liquidity(
+    reserve: &Reserve,
+    borrowed_amount_sf: u128,
+    cumulative_borrow_rate: &[u64; 4],
+) -> f64 {
+    let rate = big_fraction(&reserve.liquidity.cumulative_borrow_rate_bsf.value)
+        / big_fraction(cumulative_borrow_rate);
+    let interests = if rate.is_finite() { rate } else { 1.0_f64 };
+    fraction(borrowed_amount_sf) * interests / scale(reserve)
+}
+
+/// The factor the value borrowed from a reserve is weighted by.
+#[expect(clippy::cast_precision_loss)]
+pub fn borrow_factor(reserve: &Reserve) -> f64 {
+    match reserve.config.borrow_factor_pct {
+        0 => 1.0_f64,
+        pct => pct as f64 / 100.0_f64,
+    }
+}
+
+/// A position of an obligation, with the reserve it is in.
+pub struct Position<'state> {
+    pub address: Pubkey,
+    pub reserve: &'state Reserve,
+    /// Amount held: collateral tokens deposited, or liquidity owed (base units).
+    pub amount: f64,
+    /// Value of the position.
+    pub value: f64,
+    /// Price of a token.
+    pub price: f64,
+    /// Base units of a token.
+    pub scale: f64,
+}
+
+impl<'state> Position<'state> {
+    /// A position in a reserve.
+    ///
+    /// # Parameters
+    /// * `address` - The reserve,
+    /// * `reserves` - The reserves, by address,
+    /// * `prices` - The prices of their oracles, by address,
+    /// * `held` - The amount held in the reserve, and the liquidity (in tokens) it is worth.
+    #[expect(clippy::result_large_err)]
+    fn new<F: FnOnce(&Reserve) -> (f64, f64)>(
+        address: Pubkey,
+        reserves: &'state HashMap<Pubkey, Reserve>,
+        prices: &HashMap<Pubkey, Price>,
+        held: F,
+    ) -> Result<Self> {
+        let reserve = reserves
+            .get(&address)
+            .ok_or_else(|| Error::Monitor(format!("unknown reserve {address}")))?;
+        let price = price(reserve, prices);
+        let (amount, liquidity) = held(reserve);
+        Ok(Self {
+            address,
+            reserve,
+            amount,
+            value: liquidity * price,
+            price,
+            scale: scale(reserve),
+        })
+    }
+
+    /// The mint of the reserve's liquidity.
+    pub const fn mint(&self) -> Pubkey {
+        self.reserve.liquidity.mint_pubkey
+    }
+
+    /// The value of an amount of tokens (base units).
+    #[expect(clippy::cast_precision_loss)]
+    pub fn value_of(&self, amount: u64) -> f64 {
+        amount as f64 / self.scale * self.price
+    }
+}
+
+/// The base units of a token of a reserve.
+pub fn scale(reserve: &Reserve) -> f64 {
+    10_f64.powi(i32::try_from(reserve.liquidity.mint_decimals).unwrap_or(i32::MAX))
+}
+
+/// The borrows and the deposits of an obligation.
+///
+/// # Errors
+/// If a reserve of the obligation is missing.
+#[expect(clippy::result_large_err, clippy::cast_precision_loss)]
+pub fn positions<'state>(
+    obligation: &Obligation,
+    reserves: &'state HashMap<Pubkey, Reserve>,
+    prices: &HashMap<Pubkey, Price>,
+) -> Result<(Vec<Position<'state>>, Vec<Position<'state>>)> {
+    let mut borrows = vec![];
+    for borrow in obligation
+        .borrows
+        .iter()
+        .filter(|borrow| borrow.borrow_reserve != Pubkey::default())
+    {
+        borrows.push(Position::new(
+            borrow.borrow_reserve,
+            reserves,
+            prices,
+            |reserve| {
+                let owed = owed_liquidity(
+                    reserve,
+                    borrow.borrowed_amount_sf,
+                    &borrow.cumulative_borrow_rate_bsf.value,
+                );
+                (owed * scale(reserve), owed)
+            },
+        )?);
+    }
+    let mut deposits = vec![];
+    for deposit in obligation
+        .deposits
+        .iter()
+        .filter(|deposit| deposit.deposit_reserve != Pubkey::default())
+    {
+        deposits.push(Position::new(
+            deposit.deposit_reserve,
+            reserves,
+            prices,
+            |reserve| {
+                (
+                    deposit.deposited_amount as f64,
+                    collateral_liquidity(reserve, deposit.deposited_amount),
+                )
+            },
+        )?);
+    }
+    Ok((borrows, deposits))
+}
+
+/// The value of a 256 bits klend fraction, up to its scale (only used in ratios).
+#[expect(clippy::cast_precision_loss)]
+fn big_fraction(words: &[u64; 4]) -> f64 {
+    words.iter().rev().fold(0.0, |value, word| {
+        value.mul_add(2_f64.powi(64), *word as f64)
+    })
+}
+
+/// A change of the health level of an obligation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HealthEvent {
+    pub obligation: Pubkey,
+    pub health: Health,
+    pub level: HealthLevel,
+    /// The previous level, `None` on the first evaluation.
+    pub previous: Option<HealthLevel>,
+}
+
+/// The state of the followed obligations and of the accounts their health depends on.
+pub struct Monitor {
+    obligations: Vec<Pubkey>,
+    thresholds: HealthThresholds,
+    states: HashMap<Pubkey, Obligation>,
+    reserves: HashMap<Pubkey, Reserve>,
+    prices: HashMap<Pubkey, Price>,
+    levels: HashMap<Pubkey, HealthLevel>,
+}
+
+impl Monitor {
+    /// A monitor of obligations, which must be polled before being evaluated.
+    pub fn new(obligations: Vec<Pubkey>, thresholds: HealthThresholds) -> Self {
+        Self {
+            obligations,
+            thresholds,
+            states: HashMap::new(),
+            reserves: HashMap::new(),
+            prices: HashMap::new(),
+            levels: HashMap::new(),
+        }
+    }
+
+    /// The accounts followed: the obligations, the reserves they use and their oracles.
+    pub fn accounts(&self) -> BTreeSet<Pubkey> {
+        let reserves = self.reserve_addresses();
+        let oracles = reserves
+            .iter()
+            .filter_map(|reserve| self.reserves.get(reserve))
+            .map(|reserve| reserve.config.token_info.pyth_configuration.price)
+            .filter(|oracle| *oracle != Pubkey::default());
+        self.obligations
+            .iter()
+            .copied()
+            .chain(reserves.iter().copied())
+            .chain(oracles)
+            .collect()
+    }
+
+    /// The reserves used by the known obligations.
+    fn reserve_addresses(&self) -> BTreeSet<Pubkey> {
+        self.states
+            .values()
+            .flat_map(|obligation| {
+                obligation
+                    .deposits
+                    .iter()
+                    .map(|deposit| deposit.deposit_reserve)
+                    .chain(
+                        obligation
+                            .borrows
+                            .iter()
+                            .map(|borrow| borrow.borrow_reserve),
+                    )
+            })
+            .filter(|reserve| *reserve != Pubkey::default())
+            .collect()
+    }
+
+    /// Records the new data of a followed account.
+    ///
+    /// # Errors
+    /// If the data does not match the kind of account it was followed as.
+    #[expect(clippy::result_large_err)]
+    pub fn update(&mut self, address: &Pubkey, data: &[u8]) -> Result<()> {
+        if self.obligations.contains(address) {
+            self.states
+                .insert(*address, klend_state::decode(address, data)?);
+        } else if self.reserves.contains_key(address) || self.reserve_addresses().contains(address)
+        {
+            self.reserves
+                .insert(*address, klend_state::decode(address, data)?);
+        } else {
+            self.prices.insert(*address, Price::decode(address, data)?);
+        }
+        Ok(())
+    }
+
+    /// Fetches all the followed accounts.
+    ///
+    /// # Errors
+    /// If an obligation or a reserve could not be fetched. The oracles that could not be read
+    /// are skipped, the prices of their reserves being used instead.
+    #[instrument(skip_all)]
+    pub async fn poll(&mut self, ctx: &Context) -> Result<()> {
+        for obligation in &self.obligations {
+            let state = klend_state::fetch(ctx, obligation).await?;
+            self.states.insert(*obligation, state);
+        }
+        for reserve in self.reserve_addresses() {
+            let state = klend_state::fetch(ctx, &reserve).await?;
+            self.reserves.insert(reserve, state);
+        }
+        let oracles: Vec<_> = self
+            .reserves
+            .values()
+            .map(|reserve| reserve.config.token_info.pyth_configuration.price)
+            .filter(|oracle| *oracle != Pubkey::default())
+            .collect();
+        for oracle in oracles {
+            match Price::fetch(ctx, &oracle).await {
+                Ok(price) => {
+                    self.prices.insert(oracle, price);
+                }
+                Err(err) => warn!(%oracle, %err, "could not read the oracle"),
+            }
+        }
+        debug!(accounts = self.accounts().len(), "accounts polled");
+
+        Ok(())
+    }
+
+    /// Computes the health of the obligations.
+    ///
+    /// # Returns
+    /// The obligations whose health level changed.
+    ///
+    /// # Errors
+    /// If a reserve of an obligation is unknown.
+    #[expect(clippy::result_large_err)]
+    pub fn evaluate(&mut self) -> Result<Vec<HealthEvent>> {
+        let mut events = vec![];
+        for obligation in &self.obligations {
+            let Some(state) = self.states.get(obligation) else {
+                continue;
+            };
+            let health = Health::compute(state, &self.reserves, &self.prices)?;
+            let level = self.thresholds.level(health.factor());
+            debug!(%obligation, %health, ?level);
+            let previous = self.levels.insert(*obligation, level);
+            if previous != Some(level) {
+                events.push(HealthEvent {
+                    obligation: *obligation,
+                    health,
+                    level,
+                    previous,
+                });
+            }
+        }
+        Ok(events)
+    }
+
+    /// Polls the followed accounts and computes the health of the obligations.
+    ///
+    /// # Returns
+    /// The obligations whose health level changed.
+    ///
+    /// # Errors
+    /// If an obligation or a reserve could not be fetched.
+    async fn refresh(&mut self, ctx: &Context) -> Result<Vec<HealthEvent>> {
+        self.poll(ctx).await?;
+        self.evaluate()
+    }
+
+    /// Follows the obligations until the process is stopped.
+    ///
+    /// The failures of the RPC and of the websocket are logged, and the accounts polled again
+    /// after `poll_interval`.
+    ///
+    /// # Parameters
+    /// * `ctx` - The cluster to follow,
+    /// * `poll_interval` - Time without any update after which the accounts are polled.
+    #[instrument(skip_all)]
+    pub async fn run(mut self, ctx: &Context, poll_interval: Duration) {
+        loop {
+            match self.refresh(ctx).await {
+                Ok(events) => report(&events),
+                Err(err) => {
+                    warn!(%err, "could not poll the obligations, retrying");
+                    tokio::time::sleep(poll_interval).await;
+                    continue;
+                }
+            }
+
+            match ctx.pubsub().await {
+                Ok(pubsub) => {
+                    if let Err(err) = self.follow(ctx, &pubsub, poll_interval).await {
+                        warn!(%err, "subscriptions lost, reconnecting");
+                    }
+                    if let Err(err) = pubsub.shutdown().await {
+                        debug!(%err, "could not close the websocket");
+                    }
+                }
+                Err(err) => {
+                    warn!(%err, "websocket unavailable, polling");
+                    tokio::time::sleep(poll_interval).await;
+                }
+            }
+        }
+    }
+
+    /// Follows the accounts over websocket subscriptions.
+    ///
+    /// # Returns
+    /// When the followed accounts changed (an obligation uses a new reserve), so that the
+    /// subscriptions are made again.
+    ///
+    /// # Errors
+    /// If the subscriptions failed or ended (the socket dropped), or an account could not be
+    /// polled or decoded.
+    async fn follow(
+        &mut self,
+        ctx: &Context,
+        pubsub: &PubsubClient,
+        poll_interval: Duration,
+    ) -> Result<()> {
+        let accounts = self.accounts();
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(ctx.rpc.commitment()),
+            ..RpcAccountInfoConfig::default()
+        };
+        let mut streams = Vec::with_capacity(accounts.len());
+        let mut unsubscribes = Vec::with_capacity(accounts.len());
+        for address in accounts.iter().copied() {
+            let (stream, unsubscribe) = pubsub
+                .account_subscribe(&address, Some(config.clone()))
+                .await?;
+            streams.push(stream.map(move |response| (address, response.value)));
+            unsubscribes.push(unsubscribe);
+        }
+        info!(accounts = accounts.len(), "following the obligations");
+
+        let mut updates = select_all(streams);
+        while self.accounts() == accounts {
+            match tokio::time::timeout(poll_interval, updates.next()).await {
+                Err(_elapsed) => self.poll(ctx).await?,
+                Ok(None) => return Err(Error::Monitor("the subscriptions ended".to_owned())),
+                Ok(Some((address, account))) => {
+                    let Some(account) = account.decode::<Account>() else {
+                        warn!(%address, "could not decode the account update");
+                        continue;
+                    };
+                    self.update(&address, &account.data)?;
+                    if self.accounts() != accounts {
+                        // the new reserves are polled before the health is computed
+                        break;
+                    }
+                }
+            }
+            report(&self.evaluate()?);
+        }
+
+        drop(updates);
+        for unsubscribe in unsubscribes {
+            unsubscribe().await;
+        }
+        info!("the followed accounts changed");
+        Ok(())
+    }
+}
+
+/// Logs the changes of health level.
+fn report(events: &[HealthEvent]) {
+    for event in events {
+        let HealthEvent {
+            obligation,
+            health,
+            level,
+            previous,
+        } = event;
+        let factor = health.factor();
+        match level {
+            HealthLevel::Healthy => info!(%obligation, factor, ?level, ?previous, "{health}"),
+            HealthLevel::Warning => warn!(%obligation, factor, ?level, ?previous, "{health}"),
+            HealthLevel::Critical | HealthLevel::Liquidatable => {
+                error!(%obligation, factor, ?level, ?previous, "{health}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use std::assert_matches;
+
+    use test_log::test;
+
+    use super::*;
+    use crate::klend::state::{account_data, zeroed};
+
+    type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
+
+    const ONE: u128 = 1 << 60;
+
+    /// A reserve of 9 decimals tokens worth `price`, holding 100 tokens for 50 collateral tokens.
+    #[expect(clippy::result_large_err)]
+    fn reserve(price: u128, liquidation_threshold_pct: u8) -> Result<Reserve> {
+        let mut reserve: Reserve = zeroed()?;
+        reserve.liquidity.mint_decimals = 9;
+        reserve.liquidity.available_amount = 100_000_000_000;
+        reserve.liquidity.market_price_sf = price * ONE;
+        reserve.liquidity.cumulative_borrow_rate_bsf.value[0] = 1;
+        reserve.collateral.mint_total_supply = 50_000_000_000;
+        reserve.config.liquidation_threshold_pct = liquidation_threshold_pct;
+        reserve.config.borrow_factor_pct = 100;
+        Ok(reserve)
+    }
+
+    #[test]
+    fn obligation_health() -> TestResult {
+        // Given
+        let (collateral, debt) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut reserves = HashMap::new();
+        reserves.insert(collateral, reserve(10, 80)?);
+        reserves.insert(debt, reserve(4, 0)?);
+        let mut obligation: Obligation = zeroed()?;
+        // 5 collateral tokens, i.e. 10 tokens worth $100
+        obligation.deposits[0].deposit_reserve = collateral;
+        obligation.deposits[0].deposited_amount = 5_000_000_000;
+        // 15 tokens worth $60, 20 with the interests accrued since
+        obligation.borrows[0].borrow_reserve = debt;
+        obligation.borrows[0].borrowed_amount_sf = 15_000_000_000 * ONE;
+        obligation.borrows[0].cumulative_borrow_rate_bsf.value[0] = 3;
+        if let Some(debt_reserve) = reserves.get_mut(&debt) {
+            debt_reserve.liquidity.cumulative_borrow_rate_bsf.value[0] = 4;
+        }
+        let oracle = Pubkey::new_unique();
+        if let Some(collateral_reserve) = reserves.get_mut(&collateral) {
+            collateral_reserve
+                .config
+                .token_info
+                .pyth_configuration
+                .price = oracle;
+        }
+        let crash = Price {
+            price: 9,
+            confidence: 0,
+            exponent: 0,
+            publish_time: 0,
+            trading: true,
+        };
+
+        // When
+        let health = Health::compute(&obligation, &reserves, &HashMap::new())?;
+        let crashed = Health::compute(&obligation, &reserves, &HashMap::from([(oracle, crash)]))?;
+        let missing = Health::compute(&obligation, &HashMap::new(), &HashMap::new());
+
+        // Then
+        assert!((health.deposits - 100.0).abs() < 1e-6, "{health}");
+        assert!(
+            (health.liquidation_threshold - 80.0).abs() < 1e-6,
+            "{health}"
+        );
+        assert!((health.borrows - 80.0).abs() < 1e-6, "{health}");
+        assert!((health.factor() - 1.0).abs() < 1e-6, "{health}");
+        assert!(
+            crashed.factor() < 1.0,
+            "the oracle price is used: {crashed}"
+        );
+        assert_matches!(missing, Err(Error::Monitor(_)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn health_events() -> TestResult {
+        // Given
+        let thresholds = HealthThresholds::default();
+        let address = Pubkey::new_unique();
+        let mut monitor = Monitor::new(vec![address], thresholds);
+        monitor.states.insert(address, zeroed()?);
+
+        // When
+        let first = monitor.evaluate()?;
+        let unchanged = monitor.evaluate()?;
+
+        // Then
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].level, HealthLevel::Healthy, "nothing borrowed");
+        assert_eq!(first[0].previous, None);
+        assert!(unchanged.is_empty(), "{unchanged:?}");
+        assert_eq!(thresholds.level(1.1), HealthLevel::Warning);
+        assert_eq!(thresholds.level(1.01), HealthLevel::Critical);
+        assert_eq!(thresholds.level(0.99), HealthLevel::Liquidatable);
+        assert_eq!(monitor.accounts(), BTreeSet::from([address]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn new_reserve_changes_the_accounts() -> TestResult {
+        // Given
+        let (address, reserve_address) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut monitor = Monitor::new(vec![address], HealthThresholds::default());
+        monitor.states.insert(address, zeroed()?);
+        let accounts = monitor.accounts();
+        let mut obligation: Obligation = zeroed()?;
+        obligation.deposits[0].deposit_reserve = reserve_address;
+        obligation.deposits[0].deposited_amount = 1;
+
+        // When
+        monitor.update(&address, &account_data(&address, &obligation)?)?;
+
+        // Then
+        assert_ne!(monitor.accounts(), accounts, "the reserve is followed");
+        assert_matches!(
+            monitor.evaluate(),
+            Err(_),
+            "the reserve must be polled before the health is computed"
+        );
+
+        Ok(())
+    }
+}
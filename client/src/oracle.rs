@@ -0,0 +1,434 @@
+//! Prices of the Pyth oracles read by klend: the price accounts of the legacy (v2) push oracle and
+//! the `PriceUpdateV2` accounts of the pull oracle.
+
+use core::fmt;
+
+use ::klend::state::Reserve;
+use anchor_client::anchor_lang::{AnchorDeserialize, Discriminator};
+use solana_sdk::{account::from_account, clock::Clock, pubkey::Pubkey, sysvar};
+use tracing::{debug, instrument};
+
+use crate::{
+    config::Context,
+    error::{Error, Result},
+    transaction::process_rpc_error,
+};
+
+/// Magic number starting the Pyth v2 accounts.
+const PYTH_MAGIC: u32 = 0xa1b2_c3d4;
+/// Version of the Pyth accounts decoded.
+const PYTH_VERSION: u32 = 2;
+/// Type of the Pyth price accounts.
+const PYTH_PRICE_ACCOUNT: u32 = 3;
+/// Status of a Pyth aggregate price that can be used.
+const PYTH_STATUS_TRADING: u32 = 1;
+/// Confidence interval above which klend rejects a Pyth price, in basis points of the price.
+pub const KLEND_MAX_CONFIDENCE_BPS: u64 = 200;
+/// 100% in basis points.
+const FULL_BPS: u64 = 10_000;
+
+/// The beginning of a Pyth v2 price account, up to its aggregate price.
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[expect(dead_code)]
+struct PythPriceAccount {
+    magic: u32,
+    version: u32,
+    account_type: u32,
+    size: u32,
+    price_type: u32,
+    exponent: i32,
+    num_components: u32,
+    num_quoters: u32,
+    last_slot: u64,
+    valid_slot: u64,
+    ema_price: [i64; 3],
+    ema_confidence: [i64; 3],
+    /// Time of the aggregate price.
+    timestamp: i64,
+    min_publishers: u8,
+    reserved: [u8; 7],
+    product: Pubkey,
+    next: Pubkey,
+    prev_slot: u64,
+    prev_price: i64,
+    prev_confidence: u64,
+    prev_timestamp: i64,
+    aggregate: PythPriceInfo,
+}
+
+/// A price of a Pyth v2 price account.
+#[derive(Debug, Clone, Copy, AnchorDeserialize)]
+#[expect(dead_code)]
+struct PythPriceInfo {
+    price: i64,
+    confidence: u64,
+    status: u32,
+    corporate_action: u32,
+    publish_slot: u64,
+}
+
+/// How much a pull oracle price update was verified.
+#[derive(Debug, Clone, Copy, AnchorDeserialize)]
+#[expect(dead_code)]
+enum VerificationLevel {
+    Partial { num_signatures: u8 },
+    Full,
+}
+
+/// A price posted by the Pyth pull oracle.
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[expect(dead_code)]
+struct PriceUpdateV2 {
+    write_authority: Pubkey,
+    verification_level: VerificationLevel,
+    message: PriceFeedMessage,
+    posted_slot: u64,
+}
+
+impl Discriminator for PriceUpdateV2 {
+    const DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+}
+
+/// The price of a feed, as signed by the Pyth publishers.
+#[derive(Debug, Clone, Copy, AnchorDeserialize)]
+#[expect(dead_code)]
+struct PriceFeedMessage {
+    feed_id: [u8; 32],
+    price: i64,
+    confidence: u64,
+    exponent: i32,
+    publish_time: i64,
+    prev_publish_time: i64,
+    ema_price: i64,
+    ema_confidence: u64,
+}
+
+/// A price given by an oracle: `price * 10^exponent` dollars for a token,
+/// give or take `confidence * 10^exponent`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[expect(clippy::struct_field_names)]
+pub struct Price {
+    pub price: i64,
+    pub confidence: u64,
+    pub exponent: i32,
+    /// Unix timestamp of the price.
+    pub publish_time: i64,
+    /// Whether the publishers agreed on a price (always true for the pull oracle).
+    pub trading: bool,
+}
+
+/// The conditions for a price to be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceChecks {
+    /// Maximum age of the price, in seconds.
+    pub max_age_secs: u64,
+    /// Maximum confidence interval, in basis points of the price.
+    pub max_confidence_bps: u64,
+}
+
+impl Default for PriceChecks {
+    fn default() -> Self {
+        Self {
+            max_age_secs: 60,
+            max_confidence_bps: KLEND_MAX_CONFIDENCE_BPS,
+        }
+    }
+}
+
+impl Price {
+    /// Decodes a Pyth v2 price account, or a pull oracle `PriceUpdateV2` account.
+    ///
+    /// # Parameters
+    /// * `address` - Address of the account (for error reporting),
+    /// * `data` - The raw data of the account.
+    ///
+    /// # Errors
+    /// If the account is neither of those.
+    #[expect(clippy::result_large_err)]
+    pub fn decode(address: &Pubkey, data: &[u8]) -> Result<Self> {
+        let invalid = |reason: &str| Error::AccountDecode {
+            address: *address,
+            reason: reason.to_owned(),
+        };
+
+        if data.starts_with(&PriceUpdateV2::DISCRIMINATOR) {
+            let mut buf = data.get(8..).unwrap_or_default();
+            let update = PriceUpdateV2::deserialize(&mut buf)
+                .map_err(|err| invalid(&format!("invalid price update: {err}")))?;
+            let message = update.message;
+            return Ok(Self {
+                price: message.price,
+                confidence: message.confidence,
+                exponent: message.exponent,
+                publish_time: message.publish_time,
+                trading: true,
+            });
+        }
+
+        let mut buf = data;
+        let account = PythPriceAccount::deserialize(&mut buf)
+            .map_err(|_err| invalid("neither a Pyth price account nor a price update"))?;
+        if account.magic != PYTH_MAGIC {
+            return Err(invalid("neither a Pyth price account nor a price update"));
+        }
+        if account.version != PYTH_VERSION || account.account_type != PYTH_PRICE_ACCOUNT {
+            return Err(invalid(&format!(
+                "Pyth account of version {} and type {}, not a v2 price account",
+                account.version, account.account_type
+            )));
+        }
+
+        Ok(Self {
+            price: account.aggregate.price,
+            confidence: account.aggregate.confidence,
+            exponent: account.exponent,
+            publish_time: account.timestamp,
+            trading: account.aggregate.status == PYTH_STATUS_TRADING,
+        })
+    }
+
+    /// Fetches and decodes a price account.
+    ///
+    /// # Parameters
+    /// * `ctx` - The cluster to read from,
+    /// * `address` - Address of the price account.
+    ///
+    /// # Errors
+    /// If the account does not exist or is not a price account.
+    #[instrument(skip(ctx))]
+    pub async fn fetch(ctx: &Context, address: &Pubkey) -> Result<Self> {
+        debug!("fetching price");
+        let account = ctx
+            .rpc
+            .get_account(address)
+            .await
+            .map_err(process_rpc_error)?;
+        Self::decode(address, &account.data)
+    }
+
+    /// Checks that the price can be used: its publishers agree, it is recent and precise enough.
+    ///
+    /// # Parameters
+    /// * `now` - The current Unix timestamp (see [`cluster_time`]),
+    /// * `checks` - The conditions to meet.
+    ///
+    /// # Errors
+    /// If any condition is not met.
+    #[expect(clippy::result_large_err, clippy::integer_division)]
+    pub fn check(&self, now: i64, checks: &PriceChecks) -> Result<()> {
+        if !self.trading {
+            return Err(Error::Oracle(
+                "the publishers do not agree on a price".to_owned(),
+            ));
+        }
+        if self.price <= 0 {
+            return Err(Error::Oracle(format!("non positive price {}", self.price)));
+        }
+        let age = now.saturating_sub(self.publish_time);
+        if age > i64::try_from(checks.max_age_secs).unwrap_or(i64::MAX) {
+            return Err(Error::Oracle(format!(
+                "stale price, published {age}s ago (at most {}s)",
+                checks.max_age_secs
+            )));
+        }
+        let confidence_bps = u128::from(self.confidence) * u128::from(FULL_BPS)
+            / u128::from(self.price.unsigned_abs());
+        if confidence_bps > u128::from(checks.max_confidence_bps) {
+            return Err(Error::Oracle(format!(
+                "confidence interval of {confidence_bps} bps (at most {} bps)",
+                checks.max_confidence_bps
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// The price, in dollars per token.
+    #[expect(clippy::cast_precision_loss)]
+    pub fn value(&self) -> f64 {
+        self.price as f64 * 10_f64.powi(self.exponent)
+    }
+
+    /// The confidence interval, in dollars per token.
+    #[expect(clippy::cast_precision_loss)]
+    pub fn confidence_value(&self) -> f64 {
+        self.confidence as f64 * 10_f64.powi(self.exponent)
+    }
+
+    /// The value of an amount of tokens, in dollars.
+    ///
+    /// # Parameters
+    /// * `amount` - The amount of tokens, in base units,
+    /// * `decimals` - The decimals of the token.
+    #[expect(clippy::cast_precision_loss)]
+    pub fn usd_value(&self, amount: u64, decimals: u8) -> f64 {
+        amount as f64 / 10_f64.powi(i32::from(decimals)) * self.value()
+    }
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "${:.6} ± {:.6} at {}{}",
+            self.value(),
+            self.confidence_value(),
+            self.publish_time,
+            if self.trading { "" } else { " (not trading)" }
+        )
+    }
+}
+
+/// The current Unix timestamp of the cluster.
+///
+/// # Parameters
+/// * `ctx` - The cluster to read from.
+///
+/// # Errors
+/// If the clock could not be fetched.
+#[instrument(skip(ctx))]
+pub async fn cluster_time(ctx: &Context) -> Result<i64> {
+    let account = ctx
+        .rpc
+        .get_account(&sysvar::clock::ID)
+        .await
+        .map_err(process_rpc_error)?;
+    let clock: Clock = from_account(&account).ok_or_else(|| Error::AccountDecode {
+        address: sysvar::clock::ID,
+        reason: "invalid clock".to_owned(),
+    })?;
+    Ok(clock.unix_timestamp)
+}
+
+/// Checks the Pyth price of a reserve, the way klend does when refreshing it.
+///
+/// # Parameters
+/// * `ctx` - The cluster to read from,
+/// * `reserve` - The reserve, giving its oracle and the maximum age of its price.
+///
+/// # Returns
+/// The price, `None` if the reserve has no Pyth oracle.
+///
+/// # Errors
+/// If the price could not be fetched or would be rejected.
+#[instrument(skip_all)]
+pub async fn check_reserve(ctx: &Context, reserve: &Reserve) -> Result<Option<Price>> {
+    let token_info = &reserve.config.token_info;
+    let oracle = token_info.pyth_configuration.price;
+    if oracle == Pubkey::default() {
+        return Ok(None);
+    }
+
+    let price = Price::fetch(ctx, &oracle).await?;
+    let checks = PriceChecks {
+        max_age_secs: token_info.max_age_price_seconds,
+        max_confidence_bps: KLEND_MAX_CONFIDENCE_BPS,
+    };
+    price
+        .check(cluster_time(ctx).await?, &checks)
+        .map_err(|err| Error::Oracle(format!("oracle {oracle} of the reserve: {err}")))?;
+
+    Ok(Some(price))
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+#[expect(clippy::little_endian_bytes)]
+mod tests {
+    use std::assert_matches;
+
+    use test_log::test;
+
+    use super::*;
+
+    type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
+
+    /// A Pyth v2 price account of $150 ± $0.15 (exponent -8), published at `time`.
+    fn pyth_account(time: i64, status: u32) -> Vec<u8> {
+        let mut data = vec![0; 3312];
+        let mut put = |offset: usize, bytes: &[u8]| {
+            data[offset..offset + bytes.len()].copy_from_slice(bytes);
+        };
+        put(0, &PYTH_MAGIC.to_le_bytes());
+        put(4, &PYTH_VERSION.to_le_bytes());
+        put(8, &PYTH_PRICE_ACCOUNT.to_le_bytes());
+        put(20, &(-8_i32).to_le_bytes());
+        put(96, &time.to_le_bytes());
+        // the aggregate price, after the product, next and previous price
+        put(208, &15_000_000_000_i64.to_le_bytes());
+        put(216, &15_000_000_u64.to_le_bytes());
+        put(224, &status.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn decode_price_accounts() -> TestResult {
+        // Given
+        let address = Pubkey::new_unique();
+        let mut update = PriceUpdateV2::DISCRIMINATOR.to_vec();
+        update.extend(Pubkey::new_unique().to_bytes());
+        // a full verification, then the feed id
+        update.push(1);
+        update.extend([7; 32]);
+        update.extend(2_000_i64.to_le_bytes());
+        update.extend(1_u64.to_le_bytes());
+        update.extend((-2_i32).to_le_bytes());
+        update.extend(1_000_i64.to_le_bytes());
+        update.extend([0; 8 * 4]);
+        update.extend(42_u64.to_le_bytes());
+
+        // When
+        let pyth = Price::decode(&address, &pyth_account(1_000, PYTH_STATUS_TRADING))?;
+        let pull = Price::decode(&address, &update)?;
+        let invalid = Price::decode(&address, &[0; 64]);
+
+        // Then
+        assert_eq!(pyth.price, 15_000_000_000);
+        assert_eq!(pyth.exponent, -8);
+        assert_eq!(pyth.publish_time, 1_000);
+        assert!((pyth.value() - 150.0).abs() < 1e-9, "{pyth}");
+        assert!((pyth.usd_value(2_500_000_000, 9) - 375.0).abs() < 1e-9);
+        assert_eq!(
+            pull,
+            Price {
+                price: 2_000,
+                confidence: 1,
+                exponent: -2,
+                publish_time: 1_000,
+                trading: true,
+            }
+        );
+        assert_matches!(invalid, Err(Error::AccountDecode { .. }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn reject_unusable_prices() -> TestResult {
+        // Given
+        let address = Pubkey::new_unique();
+        let price = Price::decode(&address, &pyth_account(1_000, PYTH_STATUS_TRADING))?;
+        let halted = Price::decode(&address, &pyth_account(1_000, 0))?;
+        let checks = PriceChecks::default();
+
+        // When
+        let fresh = price.check(1_030, &checks);
+        let stale = price.check(1_061, &checks);
+        let imprecise = price.check(
+            1_030,
+            &PriceChecks {
+                max_confidence_bps: 5,
+                ..checks
+            },
+        );
+        let not_trading = halted.check(1_000, &checks);
+
+        // Then
+        assert_matches!(fresh, Ok(()));
+        assert_matches!(stale, Err(Error::Oracle(_)));
+        assert_matches!(imprecise, Err(Error::Oracle(_)));
+        assert_matches!(not_trading, Err(Error::Oracle(_)));
+
+        Ok(())
+    }
+}
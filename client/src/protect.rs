@@ -0,0 +1,708 @@
+//! Automatic deleveraging of an obligation nearing its liquidation.
+//!
+//! When the loan to value of an obligation crosses a trigger, enough of its debt is repaid to
+//! bring it back to a target. The tokens repaid come first from the user's LP tokens of a Raydium
+//! CPMM pool of the collateral and debt tokens, then from the collateral of the obligation,
+//! swapped through the pool when it is not the borrowed token.
+//!
+//! klend refuses to withdraw collateral from an obligation this close to its liquidation, so the
+//! debt is then repaid with a flash loan of the borrowed tokens, paid back by the swapped
+//! collateral.
+//!
+//! Everything is computed from the current state of the chain: once a run brought the obligation
+//! under its trigger, the next ones have nothing to do. A run repays at most a configured value,
+//! in a single transaction (only simulated in dry-run mode).
+
+use core::fmt;
+use std::collections::HashMap;
+
+use ::klend::state::{Obligation, Reserve};
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+};
+use tracing::{info, instrument, warn};
+
+use crate::{
+    config::Context,
+    error::{Error, Result},
+    klend::{
+        flash_loan::FlashLoan,
+        obligation::withdraw_and_redeem_instructions,
+        refresh::{obligation_reserves, refresh_obligation, refresh_reserve},
+        repay_instruction,
+        state::{self as klend_state, ReserveSummary},
+    },
+    lending::{create_ata, get_token_balance},
+    monitor::{Health, Position, borrow_factor, positions},
+    oracle::Price,
+    raydium::{
+        cpmm::{PoolKeys, swap_base_input_instructions, withdraw_instruction},
+        quote::{ConstantProduct, less_slippage},
+    },
+    transaction::execute_instructions,
+};
+
+/// When and how much an obligation is deleveraged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProtectConfig {
+    /// Loan to value above which the obligation is deleveraged.
+    pub trigger_ltv: f64,
+    /// Loan to value the obligation is brought back to.
+    pub target_ltv: f64,
+    /// Maximum value of the debt repaid by a run.
+    pub max_repay_value: f64,
+    /// Tolerance on the amounts of tokens received from the pool, in basis points.
+    pub slippage_bps: u16,
+}
+
+impl ProtectConfig {
+    /// Checks that the target is under the trigger, itself under 100%.
+    ///
+    /// # Errors
+    /// If the loan to values or the maximum repaid value are out of range.
+    #[expect(clippy::result_large_err)]
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0_f64 < self.target_ltv
+            && self.target_ltv < self.trigger_ltv
+            && self.trigger_ltv < 1.0_f64)
+        {
+            return Err(Error::Protect(format!(
+                "the target LTV ({}) must be positive and under the trigger LTV ({}), itself \
+                 under 1",
+                self.target_ltv, self.trigger_ltv
+            )));
+        }
+        if self.max_repay_value.is_nan() || self.max_repay_value <= 0.0_f64 {
+            return Err(Error::Protect(format!(
+                "invalid maximum repaid value {}",
+                self.max_repay_value
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The user's LP tokens in the pool used to deleverage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiquidityPosition {
+    pub keys: PoolKeys,
+    pub pool: ConstantProduct,
+    /// LP tokens held by the user.
+    pub lp_balance: u64,
+}
+
+/// The operations deleveraging an obligation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plan {
+    /// Loan to value of the obligation at the current prices.
+    pub loan_to_value: f64,
+    /// Loan to value expected once the plan is executed.
+    pub expected_loan_to_value: f64,
+    /// Reserve of the debt repaid.
+    pub debt_reserve: Pubkey,
+    pub debt_mint: Pubkey,
+    /// Reserve of the collateral withdrawn.
+    pub collateral_reserve: Pubkey,
+    pub collateral_mint: Pubkey,
+    /// LP tokens burnt.
+    pub lp_amount: u64,
+    /// Expected amounts of the tokens of the pool received for them, in the pool's order.
+    pub lp_amounts: (u64, u64),
+    /// Collateral tokens withdrawn from the obligation.
+    pub collateral_amount: u64,
+    /// Liquidity they are redeemed for, at least.
+    pub collateral_liquidity: u64,
+    /// Collateral liquidity swapped for the borrowed tokens.
+    pub swap_in: u64,
+    /// Minimum amount of borrowed tokens received from the swap.
+    pub swap_min_out: u64,
+    /// Borrowed tokens available to repay the debt (flash loan fee included).
+    pub repay_amount: u64,
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "LTV {:.2}% → {:.2}%: burn {} LP tokens, withdraw {} collateral tokens (at least {} \
+             tokens), swap {} tokens (for at least {}), repay {} tokens",
+            self.loan_to_value * 100.0_f64,
+            self.expected_loan_to_value * 100.0_f64,
+            self.lp_amount,
+            self.collateral_amount,
+            self.collateral_liquidity,
+            self.swap_in,
+            self.swap_min_out,
+            self.repay_amount,
+        )
+    }
+}
+
+/// The largest borrow and the largest deposit of an obligation.
+#[expect(clippy::result_large_err)]
+fn largest_positions<'state>(
+    obligation: &Obligation,
+    reserves: &'state HashMap<Pubkey, Reserve>,
+    prices: &HashMap<Pubkey, Price>,
+) -> Result<(Position<'state>, Position<'state>)> {
+    let (borrows, deposits) = positions(obligation, reserves, prices)?;
+    let largest = |positions: Vec<Position<'state>>| {
+        positions
+            .into_iter()
+            .max_by(|a, b| a.value.total_cmp(&b.value))
+    };
+    largest(borrows)
+        .zip(largest(deposits))
+        .ok_or_else(|| Error::Protect("nothing borrowed or deposited".to_owned()))
+}
+
+/// The LP tokens burnt to repay the debt.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct LpWithdrawal {
+    lp_amount: u64,
+    /// Expected amounts received, in the pool's order.
+    amounts: (u64, u64),
+    /// Value of the amounts.
+    value: f64,
+    /// Minimum amounts received, of collateral and borrowed tokens.
+    minimums: (u64, u64),
+}
+
+impl LpWithdrawal {
+    /// Burns as many LP tokens as needed to get a value, if the user holds them.
+    #[expect(
+        clippy::result_large_err,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    fn new(
+        position: &LiquidityPosition,
+        collateral: &Position<'_>,
+        debt: &Position<'_>,
+        wanted: f64,
+        slippage_bps: u16,
+    ) -> Result<Self> {
+        let collateral_is_0 = position.keys.mint_0 == collateral.mint();
+        let split = |(amount_0, amount_1)| {
+            if collateral_is_0 {
+                (amount_0, amount_1)
+            } else {
+                (amount_1, amount_0)
+            }
+        };
+        let (all_collateral, all_debt) = split(position.pool.withdraw(position.lp_balance)?);
+        let all_value = collateral.value_of(all_collateral) + debt.value_of(all_debt);
+        if all_value <= 0.0_f64 {
+            return Ok(Self::default());
+        }
+
+        let lp_amount = (position.lp_balance as f64 * wanted.min(all_value) / all_value) as u64;
+        let amounts = position.pool.withdraw(lp_amount)?;
+        let (expected_collateral, expected_debt) = split(amounts);
+        Ok(Self {
+            lp_amount,
+            amounts,
+            value: collateral.value_of(expected_collateral) + debt.value_of(expected_debt),
+            minimums: (
+                less_slippage(expected_collateral, slippage_bps),
+                less_slippage(expected_debt, slippage_bps),
+            ),
+        })
+    }
+}
+
+/// Plans the deleveraging of an obligation.
+///
+/// The largest borrow is repaid, with the tokens of the LP position first, then with the
+/// largest collateral: the withdrawn value `w` brings the loan to value to its target `t` when
+/// `(B - f·w) / (D - w) = t`, `B` being the borrowed value weighted by the borrow factor `f` and
+/// `D` the deposited value.
+///
+/// # Parameters
+/// * `obligation` - The obligation,
+/// * `reserves` - Its reserves, by address,
+/// * `prices` - The prices of their oracles, by address (see [`Health::compute`]),
+/// * `liquidity` - The pool of the collateral and borrowed tokens, with the user's LP tokens,
+/// * `config` - When and how much to deleverage.
+///
+/// # Returns
+/// Nothing if the loan to value is under the trigger.
+///
+/// # Errors
+/// If a reserve is unknown, the pool does not trade the collateral for the borrowed tokens, or
+/// the collateral cannot cover the debt.
+#[expect(
+    clippy::result_large_err,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+pub fn plan(
+    obligation: &Obligation,
+    reserves: &HashMap<Pubkey, Reserve>,
+    prices: &HashMap<Pubkey, Price>,
+    liquidity: Option<&LiquidityPosition>,
+    config: &ProtectConfig,
+) -> Result<Option<Plan>> {
+    let health = Health::compute(obligation, reserves, prices)?;
+    let loan_to_value = health.loan_to_value();
+    if loan_to_value < config.trigger_ltv {
+        return Ok(None);
+    }
+    let (debt, collateral) = largest_positions(obligation, reserves, prices)?;
+    let factor = borrow_factor(debt.reserve);
+    let same_mint = debt.mint() == collateral.mint();
+    if let Some(position) = liquidity {
+        let mints = [position.keys.mint_0, position.keys.mint_1];
+        if same_mint || !mints.contains(&debt.mint()) || !mints.contains(&collateral.mint()) {
+            return Err(Error::Protect(format!(
+                "the pool {} does not trade {} for {}",
+                position.keys.pool,
+                collateral.mint(),
+                debt.mint()
+            )));
+        }
+    }
+
+    // the value to remove from the weighted borrows, the LP tokens not counting in the deposits
+    let excess = config.target_ltv.mul_add(-health.deposits, health.borrows);
+    let lp = match liquidity.filter(|position| position.lp_balance > 0) {
+        Some(position) => LpWithdrawal::new(
+            position,
+            &collateral,
+            &debt,
+            (excess / factor).min(config.max_repay_value),
+            config.slippage_bps,
+        )?,
+        None => LpWithdrawal::default(),
+    };
+    let (mut collateral_tokens, mut debt_tokens) = lp.minimums;
+
+    // the collateral, whose value counts in both the deposits and the borrows
+    let remaining = factor.mul_add(-lp.value, excess);
+    let budget = config.max_repay_value - lp.value;
+    let (mut collateral_amount, mut redeemed) = (0, 0);
+    if remaining > 0.0_f64 && budget > 0.0_f64 {
+        if factor <= config.target_ltv {
+            return Err(Error::Protect(format!(
+                "withdrawing collateral cannot bring the LTV under {} with a borrow factor of {factor}",
+                config.target_ltv
+            )));
+        }
+        let value = (remaining / (factor - config.target_ltv)).min(budget);
+        redeemed = (value / collateral.price * collateral.scale) as u64;
+        // rounded up, so that at least the liquidity planned is redeemed
+        collateral_amount = (redeemed as f64
+            * collateral.reserve.collateral.mint_total_supply as f64
+            / (ReserveSummary::from(collateral.reserve).total_supply * collateral.scale))
+            .ceil() as u64;
+        if collateral_amount as f64 >= collateral.amount {
+            return Err(Error::Protect(format!(
+                "withdrawing {value:.4} out of a collateral worth {:.4}",
+                collateral.value
+            )));
+        }
+        collateral_tokens += redeemed;
+    }
+
+    let (mut swap_in, mut swap_min_out) = (0, 0);
+    if same_mint {
+        debt_tokens += collateral_tokens;
+    } else if collateral_tokens > 0 {
+        let Some(position) = liquidity else {
+            return Err(Error::Protect(format!(
+                "a pool is needed to swap {} for {}",
+                collateral.mint(),
+                debt.mint()
+            )));
+        };
+        let quote = position.pool.swap_base_input(
+            position.keys.zero_for_one(&collateral.mint())?,
+            collateral_tokens,
+        )?;
+        swap_in = collateral_tokens;
+        swap_min_out = quote.minimum_amount_out(config.slippage_bps);
+        debt_tokens += swap_min_out;
+    } else {
+        // nothing to swap
+    }
+
+    // the borrow is never fully repaid, so that the reserves of the obligation do not change
+    let repay_amount = debt_tokens.min((debt.amount as u64).saturating_sub(1));
+    if repay_amount == 0 {
+        return Err(Error::Protect("nothing to repay the debt with".to_owned()));
+    }
+
+    Ok(Some(Plan {
+        loan_to_value,
+        expected_loan_to_value: factor.mul_add(-debt.value_of(repay_amount), health.borrows)
+            / (health.deposits - collateral.value_of(redeemed)),
+        debt_reserve: debt.address,
+        debt_mint: debt.mint(),
+        collateral_reserve: collateral.address,
+        collateral_mint: collateral.mint(),
+        lp_amount: lp.lp_amount,
+        lp_amounts: lp.amounts,
+        collateral_amount,
+        collateral_liquidity: redeemed,
+        swap_in,
+        swap_min_out,
+        repay_amount,
+    }))
+}
+
+/// Deleverages an obligation if its loan to value crossed the trigger (see [`plan`]).
+///
+/// # Parameters
+/// * `ctx` - The cluster and programs to use,
+/// * `owner` - Owner of the obligation and of the LP tokens,
+/// * `obligation` - The obligation to protect,
+/// * `pool` - A Raydium CPMM pool of the collateral and borrowed tokens,
+/// * `config` - When and how much to deleverage.
+///
+/// # Returns
+/// The signature of the transaction, if the obligation was deleveraged.
+///
+/// # Errors
+/// If the configuration is invalid, the accounts could not be fetched, the obligation cannot be
+/// deleveraged, or the transaction fails.
+#[instrument(skip(ctx, owner, config))]
+pub async fn protect(
+    ctx: &Context,
+    owner: &Keypair,
+    obligation: Pubkey,
+    pool: Option<Pubkey>,
+    config: &ProtectConfig,
+) -> Result<Option<Signature>> {
+    config.validate()?;
+    let state: Obligation = klend_state::fetch(ctx, &obligation).await?;
+    let mut reserves = HashMap::new();
+    let mut prices = HashMap::new();
+    for address in obligation_reserves(&state) {
+        let reserve: Reserve = klend_state::fetch(ctx, &address).await?;
+        let oracle = reserve.config.token_info.pyth_configuration.price;
+        if oracle != Pubkey::default() {
+            match Price::fetch(ctx, &oracle).await {
+                Ok(price) => {
+                    prices.insert(oracle, price);
+                }
+                Err(err) => warn!(%oracle, %err, "could not read the oracle"),
+            }
+        }
+        reserves.insert(address, reserve);
+    }
+    let liquidity = match pool {
+        Some(pool) => {
+            let keys = PoolKeys::fetch(ctx, pool).await?;
+            let lp_account = create_ata(&owner.pubkey(), &owner.pubkey(), &keys.lp_mint).0;
+            Some(LiquidityPosition {
+                pool: keys.fetch_constant_product(ctx).await?,
+                lp_balance: get_token_balance(ctx, &lp_account).await?,
+                keys,
+            })
+        }
+        None => None,
+    };
+
+    let Some(plan) = plan(&state, &reserves, &prices, liquidity.as_ref(), config)? else {
+        info!(
+            trigger_ltv = config.trigger_ltv,
+            "the obligation is under its trigger, nothing to do"
+        );
+        return Ok(None);
+    };
+    info!("Deleveraging: {plan}");
+
+    let program = ctx.klend_program;
+    let user = owner.pubkey();
+    let refresh = || -> Vec<Instruction> {
+        obligation_reserves(&state)
+            .into_iter()
+            .filter_map(|address| {
+                reserves
+                    .get(&address)
+                    .map(|reserve| refresh_reserve(&program, address, reserve))
+            })
+            .chain([refresh_obligation(&program, obligation, &state)])
+            .collect()
+    };
+    let repay = |amount| {
+        refresh().into_iter().chain([
+            create_ata(&user, &user, &plan.debt_mint).1,
+            repay_instruction(
+                &program,
+                &user,
+                state.lending_market,
+                obligation,
+                plan.debt_reserve,
+                plan.debt_mint,
+                amount,
+            ),
+        ])
+    };
+    let mut sources = vec![];
+    if let Some(position) = liquidity.filter(|_| plan.lp_amount > 0) {
+        sources.extend([
+            create_ata(&user, &user, &position.keys.mint_0).1,
+            create_ata(&user, &user, &position.keys.mint_1).1,
+            withdraw_instruction(
+                &user,
+                &position.keys,
+                plan.lp_amount,
+                plan.lp_amounts,
+                config.slippage_bps,
+            ),
+        ]);
+    }
+    let swap = match liquidity.filter(|_| plan.swap_in > 0) {
+        Some(position) => swap_base_input_instructions(
+            &user,
+            &position.keys,
+            plan.collateral_mint,
+            plan.swap_in,
+            plan.swap_min_out,
+        )?,
+        None => vec![],
+    };
+
+    let sig = if plan.collateral_amount == 0 {
+        let instructions: Vec<_> = sources
+            .into_iter()
+            .chain(swap)
+            .chain(repay(plan.repay_amount))
+            .collect();
+        execute_instructions(ctx, &instructions, &[owner]).await?
+    } else {
+        // the loan and its fee are paid back with the tokens available to repay
+        let debt_reserve = reserves
+            .get(&plan.debt_reserve)
+            .ok_or_else(|| Error::Protect(format!("unknown reserve {}", plan.debt_reserve)))?;
+        let fee = FlashLoan::new(
+            program,
+            plan.debt_reserve,
+            debt_reserve,
+            user,
+            plan.repay_amount,
+        )
+        .fee()?;
+        let amount = plan.repay_amount.saturating_sub(fee);
+        let withdraw = withdraw_and_redeem_instructions(
+            &program,
+            &user,
+            state.lending_market,
+            obligation,
+            plan.collateral_reserve,
+            plan.collateral_mint,
+            plan.collateral_amount,
+        );
+        FlashLoan::new(program, plan.debt_reserve, debt_reserve, user, amount)
+            .before(sources)
+            .with(repay(amount))
+            .with(refresh())
+            .with(withdraw)
+            .with(swap)
+            .execute(ctx, owner, &[])
+            .await?
+    };
+    info!("Deleveraged: {sig}");
+
+    Ok(Some(sig))
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use std::assert_matches;
+
+    use test_log::test;
+
+    use super::*;
+    use crate::{config::profile::DEVNET_CPMM_PROGRAM, klend::state::zeroed, raydium::quote::Fees};
+
+    type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
+
+    const ONE: u128 = 1 << 60;
+    /// Base units of a token.
+    const TOKEN: u64 = 1_000_000_000;
+    const HALF_TOKEN: u64 = 500_000_000;
+
+    /// A reserve of a 9 decimals token worth $100, holding 1000 tokens for as many collateral
+    /// tokens.
+    #[expect(clippy::result_large_err)]
+    fn reserve(mint: Pubkey) -> Result<Reserve> {
+        let mut reserve: Reserve = zeroed()?;
+        reserve.liquidity.mint_pubkey = mint;
+        reserve.liquidity.mint_decimals = 9;
+        reserve.liquidity.available_amount = 1000 * TOKEN;
+        reserve.liquidity.market_price_sf = 100 * ONE;
+        reserve.liquidity.cumulative_borrow_rate_bsf.value[0] = 1;
+        reserve.collateral.mint_total_supply = 1000 * TOKEN;
+        reserve.config.borrow_factor_pct = 100;
+        Ok(reserve)
+    }
+
+    /// An obligation with its reserves, and a pool of their tokens.
+    struct Setup {
+        obligation: Obligation,
+        reserves: HashMap<Pubkey, Reserve>,
+        position: LiquidityPosition,
+        sol_reserve: Pubkey,
+        bsol_reserve: Pubkey,
+    }
+
+    /// An obligation depositing $1000 of SOL and borrowing $800 of bSOL, with a pool of both.
+    #[expect(clippy::result_large_err)]
+    fn setup() -> Result<Setup> {
+        let (sol, bsol) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (sol_reserve, bsol_reserve) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut reserves = HashMap::new();
+        reserves.insert(sol_reserve, reserve(sol)?);
+        reserves.insert(bsol_reserve, reserve(bsol)?);
+
+        let mut obligation: Obligation = zeroed()?;
+        obligation.deposits[0].deposit_reserve = sol_reserve;
+        obligation.deposits[0].deposited_amount = 10 * TOKEN;
+        obligation.borrows[0].borrow_reserve = bsol_reserve;
+        obligation.borrows[0].borrowed_amount_sf = u128::from(8 * TOKEN) * ONE;
+        obligation.borrows[0].cumulative_borrow_rate_bsf.value[0] = 1;
+
+        let position = LiquidityPosition {
+            keys: PoolKeys::new(DEVNET_CPMM_PROGRAM, Pubkey::new_unique(), sol, bsol),
+            pool: ConstantProduct {
+                reserve_0: 1000 * TOKEN,
+                reserve_1: 1000 * TOKEN,
+                lp_supply: 1000 * TOKEN,
+                fees: Fees {
+                    trade_fee_numerator: 25,
+                    trade_fee_denominator: 10_000,
+                    protocol_share_numerator: 0,
+                    protocol_share_denominator: 1,
+                },
+            },
+            lp_balance: 0,
+        };
+
+        Ok(Setup {
+            obligation,
+            reserves,
+            position,
+            sol_reserve,
+            bsol_reserve,
+        })
+    }
+
+    const CONFIG: ProtectConfig = ProtectConfig {
+        trigger_ltv: 0.75,
+        target_ltv: 0.6,
+        max_repay_value: 1000.0,
+        slippage_bps: 50,
+    };
+
+    #[test]
+    fn deleverage_with_collateral() -> TestResult {
+        // Given
+        let Setup {
+            obligation,
+            reserves,
+            position,
+            sol_reserve,
+            bsol_reserve,
+        } = setup()?;
+        let prices = HashMap::new();
+        let calm = ProtectConfig {
+            trigger_ltv: 0.85,
+            ..CONFIG
+        };
+        let bounded = ProtectConfig {
+            max_repay_value: 100.0,
+            ..CONFIG
+        };
+
+        // When
+        let idle = plan(&obligation, &reserves, &prices, Some(&position), &calm)?;
+        let full =
+            plan(&obligation, &reserves, &prices, Some(&position), &CONFIG)?.ok_or("no plan")?;
+        let partial =
+            plan(&obligation, &reserves, &prices, Some(&position), &bounded)?.ok_or("no plan")?;
+        let no_pool = plan(&obligation, &reserves, &prices, None, &CONFIG);
+
+        // Then
+        assert_eq!(idle, None, "under the trigger");
+        assert!((full.loan_to_value - 0.8).abs() < 1e-9, "{full}");
+        assert_eq!(full.collateral_reserve, sol_reserve);
+        assert_eq!(full.debt_reserve, bsol_reserve);
+        // (800 - w) / (1000 - w) = 0.6 for w = 500
+        assert_eq!(full.collateral_amount, 5 * TOKEN, "{full}");
+        assert_eq!(full.swap_in, full.collateral_liquidity);
+        assert!(full.repay_amount == full.swap_min_out && full.repay_amount < 5 * TOKEN);
+        assert!(
+            (0.6..0.62).contains(&full.expected_loan_to_value),
+            "the swap costs a little: {full}"
+        );
+        assert_eq!(partial.collateral_amount, TOKEN, "{partial}");
+        assert!(partial.expected_loan_to_value > full.expected_loan_to_value);
+        assert_matches!(no_pool, Err(Error::Protect(_)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn deleverage_with_lp_tokens_first() -> TestResult {
+        // Given
+        let Setup {
+            obligation,
+            reserves,
+            mut position,
+            ..
+        } = setup()?;
+        // half a token of each, worth $100
+        position.lp_balance = HALF_TOKEN;
+        let mut rich = position;
+        rich.lp_balance = 10 * TOKEN;
+
+        // When
+        let mixed = plan(
+            &obligation,
+            &reserves,
+            &HashMap::new(),
+            Some(&position),
+            &CONFIG,
+        )?
+        .ok_or("no plan")?;
+        let lp_only = plan(
+            &obligation,
+            &reserves,
+            &HashMap::new(),
+            Some(&rich),
+            &CONFIG,
+        )?
+        .ok_or("no plan")?;
+
+        // Then
+        assert_eq!(mixed.lp_amount, HALF_TOKEN, "all the LP tokens are used");
+        // (700 - w) / (1000 - w) = 0.6 for w = 250
+        assert_eq!(mixed.collateral_amount, 5 * HALF_TOKEN, "{mixed}");
+        assert_eq!(lp_only.collateral_amount, 0, "{lp_only}");
+        // $200 out of $2000 of LP tokens
+        assert_eq!(lp_only.lp_amount, TOKEN, "{lp_only}");
+        assert!(
+            (0.6..0.62).contains(&lp_only.expected_loan_to_value),
+            "{lp_only}"
+        );
+        CONFIG.validate()?;
+        assert_matches!(
+            ProtectConfig {
+                target_ltv: 0.8,
+                ..CONFIG
+            }
+            .validate(),
+            Err(Error::Protect(_))
+        );
+
+        Ok(())
+    }
+}
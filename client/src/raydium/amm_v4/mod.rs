@@ -0,0 +1,583 @@
+pub mod state;
+
+use anchor_client::anchor_lang::AnchorSerialize;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+};
+use tracing::{debug, info, instrument};
+
+use crate::{
+    config::Context,
+    error::{Error, Result},
+    lending::{create_ata, get_token_balance},
+    raydium::quote::{ConstantProduct, plus_slippage},
+    transaction::execute_instructions,
+};
+use state::{AmmInfo, MarketState};
+
+const AUTHORITY_SEED: &[u8] = b"amm authority";
+
+const DEPOSIT_TAG: u8 = 3;
+const WITHDRAW_TAG: u8 = 4;
+const SWAP_BASE_IN_TAG: u8 = 9;
+
+/// Arguments of the `deposit` (add liquidity) instruction.
+#[derive(AnchorSerialize)]
+struct Deposit {
+    instruction: u8,
+    max_coin_amount: u64,
+    max_pc_amount: u64,
+    base_side: u64,
+}
+
+/// Arguments of the `withdraw` (remove liquidity) instruction.
+#[derive(AnchorSerialize)]
+struct Withdraw {
+    instruction: u8,
+    amount: u64,
+}
+
+/// Arguments of the `swapBaseIn` instruction.
+#[derive(AnchorSerialize)]
+struct SwapBaseIn {
+    instruction: u8,
+    amount_in: u64,
+    minimum_amount_out: u64,
+}
+
+/// The side of a deposit whose amount is fixed, the other being computed from the pool's ratio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaseSide {
+    Coin,
+    Pc,
+}
+
+impl From<BaseSide> for u64 {
+    fn from(side: BaseSide) -> Self {
+        match side {
+            BaseSide::Coin => 0,
+            BaseSide::Pc => 1,
+        }
+    }
+}
+
+/// The authority owning the vaults and LP mints of all the pools.
+pub fn authority(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[AUTHORITY_SEED], program_id).0
+}
+
+/// The addresses involved in operations on an AMM v4 pool and its `OpenBook` market.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmmKeys {
+    /// The AMM v4 program owning the pool.
+    pub program: Pubkey,
+    /// The AMM account.
+    pub amm: Pubkey,
+    /// The authority owning the vaults and the LP mint.
+    pub authority: Pubkey,
+    pub open_orders: Pubkey,
+    pub target_orders: Pubkey,
+    /// Mint of the base token.
+    pub coin_mint: Pubkey,
+    /// Mint of the quote token.
+    pub pc_mint: Pubkey,
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+    /// Mint of the LP tokens.
+    pub lp_mint: Pubkey,
+    /// The `OpenBook` program.
+    pub market_program: Pubkey,
+    /// The `OpenBook` market backing the pool.
+    pub market: Pubkey,
+    pub market_bids: Pubkey,
+    pub market_asks: Pubkey,
+    pub market_event_queue: Pubkey,
+    pub market_coin_vault: Pubkey,
+    pub market_pc_vault: Pubkey,
+    /// The signer owning the market vaults.
+    pub market_vault_signer: Pubkey,
+}
+
+impl AmmKeys {
+    /// The addresses of a pool, from its state and the state of its market.
+    ///
+    /// # Parameters
+    /// * `program` - The AMM v4 program owning the pool,
+    /// * `amm` - The AMM account,
+    /// * `info` - Its decoded state,
+    /// * `market` - The decoded state of its market.
+    ///
+    /// # Errors
+    /// If the vault signer of the market could not be derived.
+    #[expect(clippy::result_large_err)]
+    pub fn from_states(
+        program: Pubkey,
+        amm: Pubkey,
+        info: &AmmInfo,
+        market: &MarketState,
+    ) -> Result<Self> {
+        Ok(Self {
+            program,
+            amm,
+            authority: authority(&program),
+            open_orders: info.open_orders,
+            target_orders: info.target_orders,
+            coin_mint: info.coin_vault_mint,
+            pc_mint: info.pc_vault_mint,
+            coin_vault: info.coin_vault,
+            pc_vault: info.pc_vault,
+            lp_mint: info.lp_mint,
+            market_program: info.market_program,
+            market: info.market,
+            market_bids: market.bids,
+            market_asks: market.asks,
+            market_event_queue: market.event_queue,
+            market_coin_vault: market.coin_vault,
+            market_pc_vault: market.pc_vault,
+            market_vault_signer: market.vault_signer(&info.market_program)?,
+        })
+    }
+
+    /// Fetches the states of a pool and of its market to get their addresses.
+    ///
+    /// # Parameters
+    /// * `ctx` - The cluster and payer to use,
+    /// * `amm` - The AMM account.
+    ///
+    /// # Errors
+    /// If the pool or its market could not be fetched.
+    pub async fn fetch(ctx: &Context, amm: Pubkey) -> Result<Self> {
+        let info = AmmInfo::fetch(ctx, &amm).await?;
+        let market = MarketState::fetch(ctx, &info.market).await?;
+        Self::from_states(ctx.amm_v4_program, amm, &info, &market)
+    }
+
+    /// Fetches the state and vault balances of the pool to quote its operations.
+    ///
+    /// # Parameters
+    /// * `ctx` - The cluster and payer to use.
+    ///
+    /// # Errors
+    /// If the pool could not be fetched.
+    pub async fn fetch_constant_product(&self, ctx: &Context) -> Result<ConstantProduct> {
+        let info = AmmInfo::fetch(ctx, &self.amm).await?;
+        Ok(info.constant_product(
+            get_token_balance(ctx, &self.coin_vault).await?,
+            get_token_balance(ctx, &self.pc_vault).await?,
+        ))
+    }
+
+    /// The user accounts of the coin and PC tokens.
+    fn user_accounts(&self, owner: &Pubkey) -> (Pubkey, Pubkey) {
+        (
+            create_ata(owner, owner, &self.coin_mint).0,
+            create_ata(owner, owner, &self.pc_mint).0,
+        )
+    }
+
+    /// The accounts of the pool and its market used by its instructions, to put in a lookup
+    /// table.
+    pub fn addresses(&self) -> Vec<Pubkey> {
+        vec![
+            self.amm,
+            self.authority,
+            self.open_orders,
+            self.target_orders,
+            self.coin_mint,
+            self.pc_mint,
+            self.coin_vault,
+            self.pc_vault,
+            self.lp_mint,
+            self.market,
+            self.market_bids,
+            self.market_asks,
+            self.market_event_queue,
+            self.market_coin_vault,
+            self.market_pc_vault,
+            self.market_vault_signer,
+        ]
+    }
+
+    /// Whether a swap sells the coin tokens of the pool.
+    ///
+    /// # Errors
+    /// If `input_mint` is not one of the pool's mints.
+    #[expect(clippy::result_large_err)]
+    pub fn zero_for_one(&self, input_mint: &Pubkey) -> Result<bool> {
+        Ok(self.other_mint(input_mint)? == self.pc_mint)
+    }
+
+    /// The other mint of the pool.
+    ///
+    /// # Errors
+    /// If `mint` is not one of the pool's mints.
+    #[expect(clippy::result_large_err)]
+    fn other_mint(&self, mint: &Pubkey) -> Result<Pubkey> {
+        if *mint == self.coin_mint {
+            Ok(self.pc_mint)
+        } else if *mint == self.pc_mint {
+            Ok(self.coin_mint)
+        } else {
+            Err(Error::Instruction(format!(
+                "{mint} is not a mint of the pool {}",
+                self.amm
+            )))
+        }
+    }
+}
+
+/// Serializes the arguments of an instruction.
+#[expect(clippy::result_large_err)]
+fn data<T: AnchorSerialize>(args: &T) -> Result<Vec<u8>> {
+    args.try_to_vec()
+        .map_err(|err| Error::Instruction(err.to_string()))
+}
+
+/// The instruction depositing liquidity in a pool.
+#[expect(clippy::result_large_err)]
+fn deposit_instruction(
+    owner: &Pubkey,
+    keys: &AmmKeys,
+    max_coin_amount: u64,
+    max_pc_amount: u64,
+    base_side: BaseSide,
+) -> Result<Instruction> {
+    let (user_coin, user_pc) = keys.user_accounts(owner);
+    Ok(Instruction::new_with_bytes(
+        keys.program,
+        &data(&Deposit {
+            instruction: DEPOSIT_TAG,
+            max_coin_amount,
+            max_pc_amount,
+            base_side: base_side.into(),
+        })?,
+        vec![
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new(keys.amm, false),
+            AccountMeta::new_readonly(keys.authority, false),
+            AccountMeta::new_readonly(keys.open_orders, false),
+            AccountMeta::new(keys.target_orders, false),
+            AccountMeta::new(keys.lp_mint, false),
+            AccountMeta::new(keys.coin_vault, false),
+            AccountMeta::new(keys.pc_vault, false),
+            AccountMeta::new_readonly(keys.market, false),
+            AccountMeta::new(user_coin, false),
+            AccountMeta::new(user_pc, false),
+            AccountMeta::new(create_ata(owner, owner, &keys.lp_mint).0, false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(keys.market_event_queue, false),
+        ],
+    ))
+}
+
+/// The instruction withdrawing liquidity from a pool.
+#[expect(clippy::result_large_err)]
+fn withdraw_instruction(owner: &Pubkey, keys: &AmmKeys, amount: u64) -> Result<Instruction> {
+    let (user_coin, user_pc) = keys.user_accounts(owner);
+    Ok(Instruction::new_with_bytes(
+        keys.program,
+        &data(&Withdraw {
+            instruction: WITHDRAW_TAG,
+            amount,
+        })?,
+        vec![
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new(keys.amm, false),
+            AccountMeta::new_readonly(keys.authority, false),
+            AccountMeta::new(keys.open_orders, false),
+            AccountMeta::new(keys.target_orders, false),
+            AccountMeta::new(keys.lp_mint, false),
+            AccountMeta::new(keys.coin_vault, false),
+            AccountMeta::new(keys.pc_vault, false),
+            AccountMeta::new_readonly(keys.market_program, false),
+            AccountMeta::new(keys.market, false),
+            AccountMeta::new(keys.market_coin_vault, false),
+            AccountMeta::new(keys.market_pc_vault, false),
+            AccountMeta::new_readonly(keys.market_vault_signer, false),
+            AccountMeta::new(create_ata(owner, owner, &keys.lp_mint).0, false),
+            AccountMeta::new(user_coin, false),
+            AccountMeta::new(user_pc, false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(keys.market_event_queue, false),
+            AccountMeta::new(keys.market_bids, false),
+            AccountMeta::new(keys.market_asks, false),
+        ],
+    ))
+}
+
+/// The instruction swapping an exact amount of `input_mint` tokens.
+#[expect(clippy::result_large_err)]
+fn swap_base_in_instruction(
+    owner: &Pubkey,
+    keys: &AmmKeys,
+    input_mint: &Pubkey,
+    amount_in: u64,
+    minimum_amount_out: u64,
+) -> Result<Instruction> {
+    let output_mint = keys.other_mint(input_mint)?;
+    Ok(Instruction::new_with_bytes(
+        keys.program,
+        &data(&SwapBaseIn {
+            instruction: SWAP_BASE_IN_TAG,
+            amount_in,
+            minimum_amount_out,
+        })?,
+        vec![
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new(keys.amm, false),
+            AccountMeta::new_readonly(keys.authority, false),
+            AccountMeta::new(keys.open_orders, false),
+            AccountMeta::new(keys.target_orders, false),
+            AccountMeta::new(keys.coin_vault, false),
+            AccountMeta::new(keys.pc_vault, false),
+            AccountMeta::new_readonly(keys.market_program, false),
+            AccountMeta::new(keys.market, false),
+            AccountMeta::new(keys.market_bids, false),
+            AccountMeta::new(keys.market_asks, false),
+            AccountMeta::new(keys.market_event_queue, false),
+            AccountMeta::new(keys.market_coin_vault, false),
+            AccountMeta::new(keys.market_pc_vault, false),
+            AccountMeta::new_readonly(keys.market_vault_signer, false),
+            AccountMeta::new(create_ata(owner, owner, input_mint).0, false),
+            AccountMeta::new(create_ata(owner, owner, &output_mint).0, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+    ))
+}
+
+/// Deposits liquidity in a pool in exchange for LP tokens.
+///
+/// # Parameters
+/// * `ctx` - The cluster and payer to use,
+/// * `owner` - Owner of the deposited tokens,
+/// * `keys` - Addresses of the pool,
+/// * `amount` - Amount of tokens of the base side to deposit,
+/// * `base_side` - The side whose amount is deposited exactly,
+/// * `slippage_bps` - Tolerance on the amount of tokens of the other side, in basis points.
+///
+/// # Errors
+/// If the pool could not be quoted, or the transaction fails.
+#[instrument(skip(ctx, owner))]
+pub async fn add_liquidity(
+    ctx: &Context,
+    owner: &Keypair,
+    keys: &AmmKeys,
+    amount: u64,
+    base_side: BaseSide,
+    slippage_bps: u16,
+) -> Result<Signature> {
+    let quote = keys
+        .fetch_constant_product(ctx)
+        .await?
+        .deposit_base(base_side == BaseSide::Coin, amount)?;
+    let (max_coin_amount, max_pc_amount) = match base_side {
+        BaseSide::Coin => (amount, plus_slippage(quote.amount_1, slippage_bps)),
+        BaseSide::Pc => (plus_slippage(quote.amount_0, slippage_bps), amount),
+    };
+    debug!(?quote, %max_coin_amount, %max_pc_amount, "Deposit quoted");
+
+    let create_lp_ata = create_ata(&owner.pubkey(), &owner.pubkey(), &keys.lp_mint).1;
+    let ix = deposit_instruction(
+        &owner.pubkey(),
+        keys,
+        max_coin_amount,
+        max_pc_amount,
+        base_side,
+    )?;
+    let sig = execute_instructions(ctx, &[create_lp_ata, ix], &[owner]).await?;
+    info!("Added liquidity: {sig}");
+
+    Ok(sig)
+}
+
+/// Burns LP tokens to withdraw liquidity from a pool.
+///
+/// # Parameters
+/// * `ctx` - The cluster and payer to use,
+/// * `owner` - Owner of the LP tokens,
+/// * `keys` - Addresses of the pool,
+/// * `amount` - Amount of LP tokens to burn.
+///
+/// # Errors
+/// If the transaction fails.
+#[instrument(skip(ctx, owner))]
+pub async fn remove_liquidity(
+    ctx: &Context,
+    owner: &Keypair,
+    keys: &AmmKeys,
+    amount: u64,
+) -> Result<Signature> {
+    let ix = withdraw_instruction(&owner.pubkey(), keys, amount)?;
+    let sig = execute_instructions(ctx, &[ix], &[owner]).await?;
+    info!("Removed liquidity: {sig}");
+
+    Ok(sig)
+}
+
+/// Swaps an exact amount of tokens for as many tokens of the other mint as possible.
+///
+/// # Parameters
+/// * `ctx` - The cluster and payer to use,
+/// * `owner` - Owner of the swapped tokens,
+/// * `keys` - Addresses of the pool,
+/// * `input_mint` - Mint of the tokens sold,
+/// * `amount_in` - Amount of tokens sold,
+/// * `slippage_bps` - Tolerance on the amount of tokens received, in basis points.
+///
+/// # Errors
+/// If `input_mint` is not a mint of the pool, the pool could not be quoted, or the transaction
+/// fails.
+#[instrument(skip(ctx, owner))]
+pub async fn swap_base_in(
+    ctx: &Context,
+    owner: &Keypair,
+    keys: &AmmKeys,
+    input_mint: Pubkey,
+    amount_in: u64,
+    slippage_bps: u16,
+) -> Result<Signature> {
+    let quote = keys
+        .fetch_constant_product(ctx)
+        .await?
+        .swap_base_input(keys.zero_for_one(&input_mint)?, amount_in)?;
+    let minimum_amount_out = quote.minimum_amount_out(slippage_bps);
+    info!(%minimum_amount_out, "Swap quoted: {quote}");
+
+    let ix = swap_base_in_instruction(
+        &owner.pubkey(),
+        keys,
+        &input_mint,
+        amount_in,
+        minimum_amount_out,
+    )?;
+    let create_output_ata = create_ata(
+        &owner.pubkey(),
+        &owner.pubkey(),
+        &keys.other_mint(&input_mint)?,
+    )
+    .1;
+    let sig = execute_instructions(ctx, &[create_output_ata, ix], &[owner]).await?;
+    info!("Swapped: {sig}");
+
+    Ok(sig)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+
+    use solana_sdk::pubkey;
+    use test_log::test;
+
+    use super::*;
+    use crate::config::profile::DEVNET_AMM_V4_PROGRAM;
+    type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
+
+    fn keys() -> AmmKeys {
+        AmmKeys {
+            program: DEVNET_AMM_V4_PROGRAM,
+            amm: Pubkey::new_unique(),
+            authority: authority(&DEVNET_AMM_V4_PROGRAM),
+            open_orders: Pubkey::new_unique(),
+            target_orders: Pubkey::new_unique(),
+            coin_mint: Pubkey::new_unique(),
+            pc_mint: Pubkey::new_unique(),
+            coin_vault: Pubkey::new_unique(),
+            pc_vault: Pubkey::new_unique(),
+            lp_mint: Pubkey::new_unique(),
+            market_program: Pubkey::new_unique(),
+            market: Pubkey::new_unique(),
+            market_bids: Pubkey::new_unique(),
+            market_asks: Pubkey::new_unique(),
+            market_event_queue: Pubkey::new_unique(),
+            market_coin_vault: Pubkey::new_unique(),
+            market_pc_vault: Pubkey::new_unique(),
+            market_vault_signer: Pubkey::new_unique(),
+        }
+    }
+
+    #[test]
+    fn mainnet_authority() {
+        // Given
+        const MAINNET_PROGRAM_ID: Pubkey = pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+        const AUTHORITY: Pubkey = pubkey!("5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1");
+
+        // When
+        let derived = authority(&MAINNET_PROGRAM_ID);
+
+        // Then
+        assert_eq!(derived, AUTHORITY);
+    }
+
+    #[test]
+    fn deposit_encoding() -> TestResult {
+        // Given
+        let owner = Pubkey::new_unique();
+        let keys = keys();
+
+        // When
+        let ix = deposit_instruction(&owner, &keys, 1, 2, BaseSide::Pc)?;
+
+        // Then
+        assert_eq!(
+            ix.data,
+            [
+                [DEPOSIT_TAG].as_slice(),
+                &[1, 0, 0, 0, 0, 0, 0, 0],
+                &[2, 0, 0, 0, 0, 0, 0, 0],
+                &[1, 0, 0, 0, 0, 0, 0, 0]
+            ]
+            .concat(),
+            "tag then little endian amounts"
+        );
+        assert_eq!(ix.accounts.len(), 14);
+        assert_eq!(ix.accounts[13].pubkey, keys.market_event_queue);
+        Ok(())
+    }
+
+    #[test]
+    fn withdraw_accounts() -> TestResult {
+        // Given
+        let owner = Pubkey::new_unique();
+        let keys = keys();
+
+        // When
+        let ix = withdraw_instruction(&owner, &keys, 10)?;
+
+        // Then
+        assert_eq!(ix.data.len(), 9);
+        assert_eq!(ix.accounts.len(), 20);
+        assert_eq!(ix.accounts[12].pubkey, keys.market_vault_signer);
+        assert!(ix.accounts[16].is_signer, "the owner signs");
+        Ok(())
+    }
+
+    #[test]
+    fn swap_direction() -> TestResult {
+        // Given
+        let owner = Pubkey::new_unique();
+        let keys = keys();
+
+        // When
+        let ix = swap_base_in_instruction(&owner, &keys, &keys.pc_mint, 10, 5)?;
+        let res = swap_base_in_instruction(&owner, &keys, &Pubkey::new_unique(), 10, 5);
+
+        // Then
+        assert_eq!(ix.data[0], SWAP_BASE_IN_TAG);
+        assert_eq!(
+            ix.accounts[15].pubkey,
+            create_ata(&owner, &owner, &keys.pc_mint).0,
+            "the PC tokens are sold"
+        );
+        assert_eq!(
+            ix.accounts[16].pubkey,
+            create_ata(&owner, &owner, &keys.coin_mint).0,
+            "the coin tokens are bought"
+        );
+        assert!(res.is_err(), "the input mint must belong to the pool");
+        Ok(())
+    }
+}
@@ -0,0 +1,368 @@
+use core::fmt;
+
+use anchor_client::anchor_lang::AnchorDeserialize;
+use solana_sdk::pubkey::Pubkey;
+use tracing::{debug, instrument};
+
+use crate::{
+    config::Context,
+    error::{Error, Result},
+    raydium::quote::{self, ConstantProduct},
+    transaction::process_rpc_error,
+};
+
+/// The fees of an AMM, as fractions.
+#[derive(Debug, Clone, Copy, AnchorDeserialize)]
+#[expect(dead_code)]
+pub struct Fees {
+    pub min_separate_numerator: u64,
+    pub min_separate_denominator: u64,
+    /// Fee taken on swaps and kept in the pool.
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+    pub pnl_numerator: u64,
+    pub pnl_denominator: u64,
+    /// Total fee taken on swaps.
+    pub swap_fee_numerator: u64,
+    pub swap_fee_denominator: u64,
+}
+
+/// Accounting of an AMM.
+#[derive(Debug, Clone, Copy, AnchorDeserialize)]
+#[expect(dead_code)]
+pub struct StateData {
+    /// Coin tokens held in the vault owed to the protocol.
+    pub need_take_pnl_coin: u64,
+    /// PC tokens held in the vault owed to the protocol.
+    pub need_take_pnl_pc: u64,
+    pub total_pnl_pc: u64,
+    pub total_pnl_coin: u64,
+    /// Timestamp from which the pool accepts swaps.
+    pub pool_open_time: u64,
+    pub padding: [u64; 2],
+    pub orderbook_to_init_time: u64,
+    pub swap_coin_in_amount: u128,
+    pub swap_pc_out_amount: u128,
+    pub swap_acc_pc_fee: u64,
+    pub swap_pc_in_amount: u128,
+    pub swap_coin_out_amount: u128,
+    pub swap_acc_coin_fee: u64,
+}
+
+/// The state of a Raydium AMM v4 pool.
+///
+/// The "coin" is the base token of the pool and the "PC" (price currency) its quote token.
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[expect(dead_code)]
+pub struct AmmInfo {
+    pub status: u64,
+    /// Bump of the AMM authority.
+    pub nonce: u64,
+    pub order_num: u64,
+    pub depth: u64,
+    pub coin_decimals: u64,
+    pub pc_decimals: u64,
+    pub state: u64,
+    pub reset_flag: u64,
+    pub min_size: u64,
+    pub vol_max_cut_ratio: u64,
+    pub amount_wave: u64,
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+    pub min_price_multiplier: u64,
+    pub max_price_multiplier: u64,
+    pub sys_decimal_value: u64,
+    pub fees: Fees,
+    pub state_data: StateData,
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+    pub coin_vault_mint: Pubkey,
+    pub pc_vault_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    /// The open orders of the AMM on its market.
+    pub open_orders: Pubkey,
+    /// The `OpenBook` market backing the AMM.
+    pub market: Pubkey,
+    /// The `OpenBook` program owning the market.
+    pub market_program: Pubkey,
+    pub target_orders: Pubkey,
+    pub padding1: [u64; 8],
+    pub amm_owner: Pubkey,
+    /// LP tokens in circulation.
+    pub lp_amount: u64,
+    pub client_order_id: u64,
+    pub recent_epoch: u64,
+    pub padding2: u64,
+}
+
+impl AmmInfo {
+    /// Size of the account.
+    pub const LEN: usize = 752;
+
+    /// Decodes the data of an AMM account.
+    ///
+    /// # Parameters
+    /// * `address` - Address of the account (for error reporting),
+    /// * `data` - The raw data of the account.
+    ///
+    /// # Errors
+    /// If the data is not the one of an AMM.
+    #[expect(clippy::result_large_err)]
+    pub fn decode(address: &Pubkey, data: &[u8]) -> Result<Self> {
+        decode(address, data, Self::LEN)
+    }
+
+    /// Fetches and decodes an AMM account.
+    ///
+    /// # Parameters
+    /// * `ctx` - The cluster to read from.
+    ///
+    /// # Errors
+    /// If the account does not exist or is not an AMM.
+    pub async fn fetch(ctx: &Context, address: &Pubkey) -> Result<Self> {
+        Self::decode(address, &fetch_data(ctx, address).await?)
+    }
+
+    /// The pool as a constant product (coin first), to quote its operations.
+    ///
+    /// The tokens the AMM placed on its market are ignored.
+    ///
+    /// # Parameters
+    /// * `coin_vault` - Balance of the coin vault,
+    /// * `pc_vault` - Balance of the PC vault.
+    pub const fn constant_product(&self, coin_vault: u64, pc_vault: u64) -> ConstantProduct {
+        // The PnL owed to the protocol is not part of the liquidity
+        ConstantProduct {
+            reserve_0: coin_vault.saturating_sub(self.state_data.need_take_pnl_coin),
+            reserve_1: pc_vault.saturating_sub(self.state_data.need_take_pnl_pc),
+            lp_supply: self.lp_amount,
+            fees: quote::Fees {
+                trade_fee_numerator: self.fees.swap_fee_numerator,
+                trade_fee_denominator: self.fees.swap_fee_denominator,
+                protocol_share_numerator: self.fees.pnl_numerator,
+                protocol_share_denominator: self.fees.pnl_denominator,
+            },
+        }
+    }
+}
+
+impl fmt::Display for AmmInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "AMM v4 pool (market {})", self.market)?;
+        writeln!(
+            f,
+            "  coin: {} ({} decimals), vault {}",
+            self.coin_vault_mint, self.coin_decimals, self.coin_vault
+        )?;
+        writeln!(
+            f,
+            "  pc: {} ({} decimals), vault {}",
+            self.pc_vault_mint, self.pc_decimals, self.pc_vault
+        )?;
+        writeln!(f, "  LP mint: {}, supply {}", self.lp_mint, self.lp_amount)?;
+        write!(
+            f,
+            "  swap fee {}/{}, status {}, open since {}",
+            self.fees.swap_fee_numerator,
+            self.fees.swap_fee_denominator,
+            self.status,
+            self.state_data.pool_open_time
+        )
+    }
+}
+
+/// The state of an `OpenBook` (Serum v3) market.
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[expect(dead_code)]
+pub struct MarketState {
+    /// The "serum" prefix of the account.
+    pub head_padding: [u8; 5],
+    pub account_flags: u64,
+    pub own_address: Pubkey,
+    /// Nonce of the vault signer.
+    pub vault_signer_nonce: u64,
+    pub coin_mint: Pubkey,
+    pub pc_mint: Pubkey,
+    pub coin_vault: Pubkey,
+    pub coin_deposits_total: u64,
+    pub coin_fees_accrued: u64,
+    pub pc_vault: Pubkey,
+    pub pc_deposits_total: u64,
+    pub pc_fees_accrued: u64,
+    pub pc_dust_threshold: u64,
+    pub request_queue: Pubkey,
+    pub event_queue: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+    pub fee_rate_bps: u64,
+    pub referrer_rebates_accrued: u64,
+    /// The "padding" suffix of the account.
+    pub tail_padding: [u8; 7],
+}
+
+impl MarketState {
+    /// Size of the account.
+    pub const LEN: usize = 388;
+
+    /// Decodes the data of a market account.
+    ///
+    /// # Parameters
+    /// * `address` - Address of the account (for error reporting),
+    /// * `data` - The raw data of the account.
+    ///
+    /// # Errors
+    /// If the data is not the one of a market.
+    #[expect(clippy::result_large_err)]
+    pub fn decode(address: &Pubkey, data: &[u8]) -> Result<Self> {
+        decode(address, data, Self::LEN)
+    }
+
+    /// Fetches and decodes a market account.
+    ///
+    /// # Parameters
+    /// * `ctx` - The cluster to read from.
+    ///
+    /// # Errors
+    /// If the account does not exist or is not a market.
+    pub async fn fetch(ctx: &Context, address: &Pubkey) -> Result<Self> {
+        Self::decode(address, &fetch_data(ctx, address).await?)
+    }
+
+    /// The signer owning the vaults of the market.
+    ///
+    /// # Parameters
+    /// * `program_id` - The `OpenBook` program owning the market.
+    ///
+    /// # Errors
+    /// If the nonce of the market does not give a valid address.
+    #[expect(clippy::result_large_err)]
+    #[expect(clippy::little_endian_bytes)]
+    pub fn vault_signer(&self, program_id: &Pubkey) -> Result<Pubkey> {
+        // The program uses the little endian representation of the nonce as seed.
+        Pubkey::create_program_address(
+            &[
+                self.own_address.as_ref(),
+                &self.vault_signer_nonce.to_le_bytes(),
+            ],
+            program_id,
+        )
+        .map_err(|err| Error::AccountDecode {
+            address: self.own_address,
+            reason: format!("invalid vault signer nonce: {err}"),
+        })
+    }
+}
+
+/// Decodes an account of a fixed size, without discriminator.
+#[expect(clippy::result_large_err)]
+fn decode<T: AnchorDeserialize>(address: &Pubkey, data: &[u8], len: usize) -> Result<T> {
+    if data.len() != len {
+        return Err(Error::AccountDecode {
+            address: *address,
+            reason: format!("expected {len} bytes, got {}", data.len()),
+        });
+    }
+    let mut buf = data;
+    T::deserialize(&mut buf).map_err(|err| Error::AccountDecode {
+        address: *address,
+        reason: err.to_string(),
+    })
+}
+
+#[instrument(skip(ctx))]
+async fn fetch_data(ctx: &Context, address: &Pubkey) -> Result<Vec<u8>> {
+    debug!("fetching account");
+    Ok(ctx
+        .rpc
+        .get_account(address)
+        .await
+        .map_err(process_rpc_error)?
+        .data)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+
+    use test_log::test;
+
+    use super::*;
+    type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
+
+    #[test]
+    fn decode_amm_info() -> TestResult {
+        // Given
+        let address = Pubkey::new_unique();
+        let market = Pubkey::new_unique();
+        let mut data = vec![0; AmmInfo::LEN];
+        // 16 u64, the fees (8 u64) and the state data (144 bytes), then 6 keys
+        let offset = 16 * 8 + 8 * 8 + 144 + 6 * 32;
+        data[offset..offset + 32].copy_from_slice(market.as_ref());
+
+        // When
+        let amm = AmmInfo::decode(&address, &data)?;
+
+        // Then
+        assert_eq!(amm.market, market);
+        Ok(())
+    }
+
+    #[test]
+    #[expect(clippy::little_endian_bytes)]
+    fn reserves_exclude_pnl() -> TestResult {
+        // Given
+        let address = Pubkey::new_unique();
+        let mut data = vec![0; AmmInfo::LEN];
+        // the swap fee is the last fraction of the fees, after the 16 u64
+        let fees = 16 * 8;
+        data[fees + 6 * 8..fees + 7 * 8].copy_from_slice(&25_u64.to_le_bytes());
+        data[fees + 7 * 8..fees + 8 * 8].copy_from_slice(&10_000_u64.to_le_bytes());
+        // the PnL owed in coin tokens starts the state data
+        data[fees + 8 * 8..fees + 9 * 8].copy_from_slice(&100_u64.to_le_bytes());
+
+        // When
+        let amm = AmmInfo::decode(&address, &data)?;
+        let product = amm.constant_product(1_100, 2_000);
+
+        // Then
+        assert_eq!(product.reserve_0, 1_000, "the coin PnL is excluded");
+        assert_eq!(product.reserve_1, 2_000);
+        assert_eq!(product.fees.trade_fee_numerator, 25);
+        assert_eq!(product.fees.trade_fee_denominator, 10_000);
+        Ok(())
+    }
+
+    #[test]
+    fn decode_market() -> TestResult {
+        // Given
+        let address = Pubkey::new_unique();
+        let event_queue = Pubkey::new_unique();
+        let mut data = vec![0; MarketState::LEN];
+        data[..5].copy_from_slice(b"serum");
+        // after the head, the flags, 6 keys and 6 u64
+        let offset = 5 + 8 + 6 * 32 + 6 * 8;
+        data[offset..offset + 32].copy_from_slice(event_queue.as_ref());
+
+        // When
+        let market = MarketState::decode(&address, &data)?;
+
+        // Then
+        assert_eq!(market.event_queue, event_queue);
+        Ok(())
+    }
+
+    #[test]
+    fn reject_wrong_size() {
+        // Given
+        let address = Pubkey::new_unique();
+        let data = vec![0; MarketState::LEN];
+
+        // When
+        let res = AmmInfo::decode(&address, &data);
+
+        // Then
+        assert!(res.is_err(), "a market is not an AMM");
+    }
+}
@@ -0,0 +1,568 @@
+use crate::error::{Error, Result};
+use big_num::U256;
+
+/// Kept apart as the generated code needs the `Result` of the prelude, and trips some lints.
+#[expect(
+    clippy::integer_division,
+    clippy::manual_div_ceil,
+    clippy::assign_op_pattern
+)]
+mod big_num {
+    uint::construct_uint! {
+        /// Unsigned 256 bits integer, for the intermediate products of the liquidity math.
+        pub struct U256(4);
+    }
+}
+
+/// The lowest tick of a pool.
+pub const MIN_TICK: i32 = -443_636;
+/// The highest tick of a pool.
+pub const MAX_TICK: i32 = -MIN_TICK;
+/// The square root price at [`MIN_TICK`].
+pub const MIN_SQRT_PRICE_X64: u128 = 4_295_048_016;
+/// The square root price at [`MAX_TICK`].
+pub const MAX_SQRT_PRICE_X64: u128 = 79_226_673_521_066_979_257_578_248_091;
+
+/// Number of initializable ticks in a tick array.
+const TICK_ARRAY_SIZE: i32 = 60;
+/// Number of tick arrays on each side of 0 tracked by the bitmap of the pool state.
+const TICK_ARRAY_BITMAP_SIZE: i32 = 512;
+/// 1.0 as a Q64.64 number.
+const Q64: u128 = 1 << 64;
+/// Number of bits of the fractional part of the logarithm computed in [`tick_at_sqrt_price`].
+const BIT_PRECISION: u32 = 16;
+
+/// `2^64 / 1.0001^(2^(i - 1))` for each bit `i` of a tick, from the second one.
+const FACTORS: [u128; 18] = [
+    0xfff9_7272_373d_4000,
+    0xfff2_e50f_5f65_7000,
+    0xffe5_caca_7e10_f000,
+    0xffcb_9843_d60f_7000,
+    0xff97_3b41_fa98_e800,
+    0xff2e_a164_66c9_b000,
+    0xfe5d_ee04_6a9a_3800,
+    0xfcbe_86c7_900b_b000,
+    0xf987_a725_3ac6_5800,
+    0xf339_2b08_22bb_6000,
+    0xe715_9475_a2ca_f000,
+    0xd097_f3bd_fd2f_2000,
+    0xa9f7_4646_2d9f_8000,
+    0x70d8_69a1_56f3_1c00,
+    0x31be_135f_97ed_3200,
+    0x09aa_508b_5b85_a500,
+    0x005d_6af8_dedc_582c,
+    0x0000_2216_e584_f5fa,
+];
+
+/// The square root of the price at a tick, `sqrt(1.0001^tick)`, as a Q64.64 number.
+///
+/// # Errors
+/// If the tick is out of [`MIN_TICK`]..=[`MAX_TICK`].
+#[expect(clippy::result_large_err)]
+#[expect(clippy::integer_division)]
+pub fn sqrt_price_at_tick(tick: i32) -> Result<u128> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(Error::Math(format!("tick {tick} is out of bounds")));
+    }
+    let abs_tick = tick.unsigned_abs();
+
+    // Both the ratio and the factors are at most 2^64, so their product fits.
+    let mut ratio = if abs_tick & 1 == 0 {
+        Q64
+    } else {
+        0xfffc_b933_bd6f_b800
+    };
+    for (bit, factor) in FACTORS.iter().enumerate() {
+        if abs_tick & (2 << bit) != 0 {
+            ratio = (ratio * factor) >> 64_i32;
+        }
+    }
+    // The factors compute the price of the negative tick, invert it for positive ones.
+    if tick > 0_i32 {
+        ratio = u128::MAX / ratio;
+    }
+
+    Ok(ratio)
+}
+
+/// The greatest tick whose square root price is lower or equal to a Q64.64 square root price.
+///
+/// # Errors
+/// If the price is out of [`MIN_SQRT_PRICE_X64`]..[`MAX_SQRT_PRICE_X64`].
+#[expect(clippy::result_large_err)]
+#[expect(clippy::cast_possible_truncation)]
+pub fn tick_at_sqrt_price(sqrt_price_x64: u128) -> Result<i32> {
+    if !(MIN_SQRT_PRICE_X64..MAX_SQRT_PRICE_X64).contains(&sqrt_price_x64) {
+        return Err(Error::Math(format!(
+            "square root price {sqrt_price_x64} is out of bounds"
+        )));
+    }
+
+    // Integer part of log2(sqrt price), in Q32.32
+    let msb = sqrt_price_x64.ilog2();
+    let log2p_integer_x32 = (i128::from(msb) - 64) << 32_i32;
+
+    // Fractional part, squaring the price normalized in [1, 2) once per bit
+    let mut r = if msb >= 64 {
+        sqrt_price_x64 >> (msb - 63)
+    } else {
+        sqrt_price_x64 << (63 - msb)
+    };
+    let mut bit = 1_i128 << 63_i32;
+    let mut log2p_fraction_x64 = 0_i128;
+    for _ in 0..BIT_PRECISION {
+        r *= r;
+        let more_than_two = r >> 127_i32;
+        r >>= 63 + more_than_two;
+        if more_than_two == 1 {
+            log2p_fraction_x64 += bit;
+        }
+        bit >>= 1_i32;
+    }
+    let log2p_x32 = log2p_integer_x32 + (log2p_fraction_x64 >> 32_i32);
+
+    // Change of base to sqrt(1.0001), with the error margins of the approximation.
+    // The shifted values are ticks, so they fit in an i32.
+    let log_sqrt_10001_x64 = log2p_x32 * 59_543_866_431_248;
+    let tick_low = ((log_sqrt_10001_x64 - 184_467_440_737_095_516) >> 64_i32) as i32;
+    let tick_high = ((log_sqrt_10001_x64 + 15_793_534_762_490_258_745) >> 64_i32) as i32;
+
+    Ok(
+        if tick_low == tick_high || sqrt_price_at_tick(tick_high)? > sqrt_price_x64 {
+            tick_low
+        } else {
+            tick_high
+        },
+    )
+}
+
+/// The Q64.64 square root of a price, expressed in tokens 1 per token 0.
+///
+/// # Parameters
+/// * `price` - The price, in UI amounts,
+/// * `decimals_0` - Decimals of the first token,
+/// * `decimals_1` - Decimals of the second token.
+#[expect(clippy::cast_possible_truncation)]
+#[expect(clippy::cast_sign_loss)]
+#[expect(clippy::cast_precision_loss)]
+pub fn sqrt_price_from_price(price: f64, decimals_0: u8, decimals_1: u8) -> u128 {
+    let raw_price = price * 10_f64.powi(i32::from(decimals_1) - i32::from(decimals_0));
+    (raw_price.sqrt() * Q64 as f64) as u128
+}
+
+/// The price of a Q64.64 square root price, expressed in tokens 1 per token 0.
+///
+/// # Parameters
+/// * `sqrt_price_x64` - The square root price,
+/// * `decimals_0` - Decimals of the first token,
+/// * `decimals_1` - Decimals of the second token.
+#[expect(clippy::cast_precision_loss)]
+pub fn price_from_sqrt_price(sqrt_price_x64: u128, decimals_0: u8, decimals_1: u8) -> f64 {
+    let sqrt_price = sqrt_price_x64 as f64 / Q64 as f64;
+    sqrt_price * sqrt_price * 10_f64.powi(i32::from(decimals_0) - i32::from(decimals_1))
+}
+
+/// The greatest initializable tick whose price is lower or equal to a price.
+///
+/// # Parameters
+/// * `price` - The price in tokens 1 per token 0, in UI amounts,
+/// * `decimals_0` - Decimals of the first token,
+/// * `decimals_1` - Decimals of the second token,
+/// * `tick_spacing` - The tick spacing of the pool.
+///
+/// # Errors
+/// If the price is out of the range of the pool.
+#[expect(clippy::result_large_err)]
+pub fn tick_from_price(
+    price: f64,
+    decimals_0: u8,
+    decimals_1: u8,
+    tick_spacing: u16,
+) -> Result<i32> {
+    let tick = tick_at_sqrt_price(sqrt_price_from_price(price, decimals_0, decimals_1))?;
+    let spacing = i32::from(tick_spacing);
+    Ok(tick.div_euclid(spacing) * spacing)
+}
+
+/// Checks that a range of ticks can hold a position.
+///
+/// # Errors
+/// If the ticks are not ordered, out of bounds or not multiples of the tick spacing.
+#[expect(clippy::result_large_err)]
+pub fn check_range(tick_lower: i32, tick_upper: i32, tick_spacing: u16) -> Result<()> {
+    let spacing = i32::from(tick_spacing);
+    if tick_lower >= tick_upper {
+        return Err(Error::Math(format!(
+            "lower tick {tick_lower} is not below the upper tick {tick_upper}"
+        )));
+    }
+    if tick_lower < MIN_TICK || tick_upper > MAX_TICK {
+        return Err(Error::Math(format!(
+            "range [{tick_lower}, {tick_upper}] is out of bounds"
+        )));
+    }
+    if tick_lower % spacing != 0_i32 || tick_upper % spacing != 0_i32 {
+        return Err(Error::Math(format!(
+            "ticks {tick_lower} and {tick_upper} are not multiples of the tick spacing {tick_spacing}"
+        )));
+    }
+    Ok(())
+}
+
+/// The first tick of the tick array containing a tick.
+pub const fn tick_array_start_index(tick: i32, tick_spacing: u16) -> i32 {
+    let ticks_in_array = TICK_ARRAY_SIZE * tick_spacing as i32;
+    tick.div_euclid(ticks_in_array) * ticks_in_array
+}
+
+/// Whether a tick array is out of the range of the bitmap stored in the pool state, and
+/// is tracked by the bitmap extension account instead.
+pub const fn in_bitmap_extension(start_index: i32, tick_spacing: u16) -> bool {
+    let boundary = TICK_ARRAY_SIZE * TICK_ARRAY_BITMAP_SIZE * tick_spacing as i32;
+    start_index >= boundary || start_index < -boundary
+}
+
+/// The liquidity provided by amounts of tokens in a range at the current price.
+///
+/// The liquidity is limited by the scarcest token, so part of the other one may not be used.
+///
+/// # Parameters
+/// * `sqrt_price_x64` - The current square root price of the pool,
+/// * `tick_lower` - The lower tick of the range,
+/// * `tick_upper` - The upper tick of the range,
+/// * `amount_0` - Amount of the first token available,
+/// * `amount_1` - Amount of the second token available.
+///
+/// # Errors
+/// If a tick is out of bounds or the liquidity overflows.
+#[expect(clippy::result_large_err)]
+pub fn liquidity_from_amounts(
+    sqrt_price_x64: u128,
+    tick_lower: i32,
+    tick_upper: i32,
+    amount_0: u64,
+    amount_1: u64,
+) -> Result<u128> {
+    let sqrt_price_lower = sqrt_price_at_tick(tick_lower)?;
+    let sqrt_price_upper = sqrt_price_at_tick(tick_upper)?;
+
+    if sqrt_price_x64 <= sqrt_price_lower {
+        // Below the range, only the first token is provided.
+        liquidity_from_amount_0(sqrt_price_lower, sqrt_price_upper, amount_0)
+    } else if sqrt_price_x64 < sqrt_price_upper {
+        Ok(u128::min(
+            liquidity_from_amount_0(sqrt_price_x64, sqrt_price_upper, amount_0)?,
+            liquidity_from_amount_1(sqrt_price_lower, sqrt_price_x64, amount_1)?,
+        ))
+    } else {
+        // Above the range, only the second token is provided.
+        liquidity_from_amount_1(sqrt_price_lower, sqrt_price_upper, amount_1)
+    }
+}
+
+/// The amounts of tokens represented by a liquidity in a range at the current price.
+///
+/// # Parameters
+/// * `tick_current` - The current tick of the pool,
+/// * `sqrt_price_x64` - The current square root price of the pool,
+/// * `tick_lower` - The lower tick of the range,
+/// * `tick_upper` - The upper tick of the range,
+/// * `liquidity` - The liquidity,
+/// * `round_up` - Whether to round up (amounts to deposit) or down (amounts to withdraw).
+///
+/// # Errors
+/// If a tick is out of bounds or an amount overflows.
+#[expect(clippy::result_large_err)]
+pub fn amounts_from_liquidity(
+    tick_current: i32,
+    sqrt_price_x64: u128,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<(u64, u64)> {
+    let sqrt_price_lower = sqrt_price_at_tick(tick_lower)?;
+    let sqrt_price_upper = sqrt_price_at_tick(tick_upper)?;
+
+    Ok(if tick_current < tick_lower {
+        (
+            delta_amount_0(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?,
+            0,
+        )
+    } else if tick_current < tick_upper {
+        (
+            delta_amount_0(sqrt_price_x64, sqrt_price_upper, liquidity, round_up)?,
+            delta_amount_1(sqrt_price_lower, sqrt_price_x64, liquidity, round_up)?,
+        )
+    } else {
+        (
+            0,
+            delta_amount_1(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?,
+        )
+    })
+}
+
+/// `ΔL = Δx * √P_upper * √P_lower / (√P_upper - √P_lower)`
+#[expect(clippy::result_large_err)]
+fn liquidity_from_amount_0(sqrt_price_a: u128, sqrt_price_b: u128, amount_0: u64) -> Result<u128> {
+    let (lower, upper) = ordered(sqrt_price_a, sqrt_price_b);
+    let intermediate = U256::from(lower) * U256::from(upper) / U256::from(Q64);
+    to_u128(U256::from(amount_0) * intermediate / U256::from(upper - lower))
+}
+
+/// `ΔL = Δy / (√P_upper - √P_lower)`
+#[expect(clippy::result_large_err)]
+fn liquidity_from_amount_1(sqrt_price_a: u128, sqrt_price_b: u128, amount_1: u64) -> Result<u128> {
+    let (lower, upper) = ordered(sqrt_price_a, sqrt_price_b);
+    to_u128(U256::from(amount_1) * U256::from(Q64) / U256::from(upper - lower))
+}
+
+/// `Δx = L * (√P_upper - √P_lower) / (√P_upper * √P_lower)`
+#[expect(clippy::result_large_err)]
+fn delta_amount_0(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u64> {
+    let (lower, upper) = ordered(sqrt_price_a, sqrt_price_b);
+    let numerator = (U256::from(liquidity) << 64_i32)
+        .checked_mul(U256::from(upper - lower))
+        .ok_or_else(|| Error::Math(format!("liquidity {liquidity} is too large")))?;
+    let (upper, lower) = (U256::from(upper), U256::from(lower));
+
+    let amount = if round_up {
+        div_ceil(div_ceil(numerator, upper), lower)
+    } else {
+        numerator / upper / lower
+    };
+    to_u64(amount)
+}
+
+/// `Δy = L * (√P_upper - √P_lower)`
+#[expect(clippy::result_large_err)]
+fn delta_amount_1(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u64> {
+    let (lower, upper) = ordered(sqrt_price_a, sqrt_price_b);
+    let numerator = U256::from(liquidity) * U256::from(upper - lower);
+    let amount = if round_up {
+        div_ceil(numerator, U256::from(Q64))
+    } else {
+        numerator / U256::from(Q64)
+    };
+    to_u64(amount)
+}
+
+const fn ordered(a: u128, b: u128) -> (u128, u128) {
+    if a > b { (b, a) } else { (a, b) }
+}
+
+fn div_ceil(numerator: U256, denominator: U256) -> U256 {
+    let (quotient, remainder) = numerator.div_mod(denominator);
+    if remainder.is_zero() {
+        quotient
+    } else {
+        quotient + U256::one()
+    }
+}
+
+#[expect(clippy::result_large_err)]
+fn to_u128(value: U256) -> Result<u128> {
+    u128::try_from(value).map_err(|_err| Error::Math(format!("{value} overflows a u128")))
+}
+
+#[expect(clippy::result_large_err)]
+fn to_u64(value: U256) -> Result<u64> {
+    u64::try_from(value).map_err(|_err| Error::Math(format!("{value} overflows a u64")))
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+
+    use test_log::test;
+
+    use super::*;
+    type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
+
+    #[test]
+    fn sqrt_price_at_bounds() -> TestResult {
+        // Given
+        let ticks = [MIN_TICK, 0, MAX_TICK];
+
+        // When
+        let prices = ticks
+            .into_iter()
+            .map(sqrt_price_at_tick)
+            .collect::<core::result::Result<Vec<_>, _>>()?;
+
+        // Then
+        assert_eq!(prices, [MIN_SQRT_PRICE_X64, Q64, MAX_SQRT_PRICE_X64]);
+        assert!(sqrt_price_at_tick(MAX_TICK + 1).is_err(), "out of bounds");
+        Ok(())
+    }
+
+    #[test]
+    fn tick_at_bounds() -> TestResult {
+        // Given
+        let (min, max) = (MIN_SQRT_PRICE_X64, MAX_SQRT_PRICE_X64 - 1);
+
+        // When
+        let (min_tick, max_tick) = (tick_at_sqrt_price(min)?, tick_at_sqrt_price(max)?);
+
+        // Then
+        assert_eq!(min_tick, MIN_TICK);
+        assert_eq!(max_tick, MAX_TICK - 1, "the max price cannot be reached");
+        assert!(
+            tick_at_sqrt_price(MAX_SQRT_PRICE_X64).is_err(),
+            "out of bounds"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn tick_round_down() -> TestResult {
+        for tick in [-28_861, 28_861] {
+            // Given
+            let price = sqrt_price_at_tick(tick)?;
+            let next_price = sqrt_price_at_tick(tick + 1)?;
+
+            // When
+            let at_price = tick_at_sqrt_price(price)?;
+            let above = tick_at_sqrt_price(price + 1)?;
+            let below_next = tick_at_sqrt_price(next_price - 1)?;
+            let below = tick_at_sqrt_price(price - 1)?;
+
+            // Then
+            assert_eq!(at_price, tick);
+            assert_eq!(above, tick);
+            assert_eq!(below_next, tick);
+            assert_eq!(below, tick - 1);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn price_conversions() -> TestResult {
+        // Given
+        // 1 SOL (9 decimals) for 150 USDC (6 decimals)
+        let price = 150.0;
+
+        // When
+        let sqrt_price = sqrt_price_from_price(price, 9, 6);
+        let tick = tick_from_price(price, 9, 6, 10)?;
+
+        // Then
+        let back = price_from_sqrt_price(sqrt_price, 9, 6);
+        assert!((back - price).abs() < 1e-9, "{back} is not {price}");
+        assert_eq!(tick % 10, 0, "ticks are rounded to the spacing");
+        assert!(sqrt_price_at_tick(tick)? <= sqrt_price, "rounded down");
+        assert!(
+            sqrt_price_at_tick(tick + 10)? > sqrt_price,
+            "to the closest tick"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn array_start_index() {
+        // Given
+        let cases: [(i32, u16, i32); 10] = [
+            (120, 3, 0),
+            (1002, 30, 0),
+            (-120, 3, -180),
+            (-1002, 30, -1800),
+            (-20, 10, -600),
+            (20, 10, 0),
+            (-1002, 10, -1200),
+            (-600, 10, -600),
+            (-30720, 1, -30720),
+            (30720, 1, 30720),
+        ];
+
+        for (tick, spacing, expected) in cases {
+            // When
+            let start = tick_array_start_index(tick, spacing);
+
+            // Then
+            assert_eq!(start, expected, "tick {tick}, spacing {spacing}");
+        }
+    }
+
+    #[test]
+    fn bitmap_extension() {
+        // Given
+        let boundary: i32 = 60 * 512 * 10;
+
+        // When / Then
+        assert!(!in_bitmap_extension(0, 10), "0 is in the pool bitmap");
+        assert!(!in_bitmap_extension(-boundary, 10), "lower bound included");
+        assert!(in_bitmap_extension(boundary, 10), "upper bound excluded");
+        assert!(in_bitmap_extension(-boundary - 600, 10), "below the bitmap");
+    }
+
+    #[test]
+    fn ranges() {
+        // Given / When / Then
+        assert!(check_range(-600, 600, 10).is_ok(), "valid range");
+        assert!(check_range(600, -600, 10).is_err(), "unordered");
+        assert!(check_range(-605, 600, 10).is_err(), "not on the spacing");
+        assert!(check_range(MIN_TICK - 4, 0, 10).is_err(), "out of bounds");
+    }
+
+    #[test]
+    fn amounts_in_range() -> TestResult {
+        // Given
+        // Raydium's reference values
+        let tick = -1860;
+        let price = sqrt_price_at_tick(tick)?;
+
+        // When
+        let amounts = amounts_from_liquidity(tick, price, -6960, 4080, 100_000, true)?;
+
+        // Then
+        assert_eq!(price, 16_808_631_456_335_284_239);
+        assert_eq!(amounts, (28_199, 20_509));
+        Ok(())
+    }
+
+    #[test]
+    fn amounts_out_of_range() -> TestResult {
+        // Given
+        let price = sqrt_price_at_tick(0)?;
+
+        // When
+        let below = amounts_from_liquidity(0, price, 600, 1200, 1_000_000, true)?;
+        let above = amounts_from_liquidity(0, price, -1200, -600, 1_000_000, true)?;
+
+        // Then
+        assert_eq!(below.1, 0, "only the first token below the range");
+        assert!(below.0 > 0, "some of the first token below the range");
+        assert_eq!(above.0, 0, "only the second token above the range");
+        assert!(above.1 > 0, "some of the second token above the range");
+        Ok(())
+    }
+
+    #[test]
+    fn liquidity_round_trip() -> TestResult {
+        // Given
+        let tick = -1860;
+        let price = sqrt_price_at_tick(tick)?;
+        let (amount_0, amount_1) = (1_000_000_000, 1_000_000_000);
+
+        // When
+        let liquidity = liquidity_from_amounts(price, -6960, 4080, amount_0, amount_1)?;
+        let deposit = amounts_from_liquidity(tick, price, -6960, 4080, liquidity, true)?;
+        let withdraw = amounts_from_liquidity(tick, price, -6960, 4080, liquidity, false)?;
+
+        // Then
+        assert_eq!(liquidity, 3_546_276_050);
+        assert_eq!(deposit, (1_000_000_000, 727_293_611));
+        assert_eq!(withdraw, (999_999_999, 727_293_610));
+        Ok(())
+    }
+}
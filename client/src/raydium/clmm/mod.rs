@@ -0,0 +1,654 @@
+pub mod math;
+pub mod pda;
+pub mod state;
+
+use anchor_client::anchor_lang::{AnchorSerialize, Discriminator, InstructionData};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    system_program, sysvar,
+};
+use tracing::{info, instrument};
+
+use crate::{
+    config::Context,
+    error::{Error, Result},
+    klend::state::fetch,
+    lending::create_ata,
+    raydium::quote::less_slippage,
+    transaction::execute_instructions,
+};
+use state::{PersonalPositionState, PoolState};
+
+/// The SPL memo program, required by the decrease liquidity instruction.
+const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+/// The Metaplex token metadata program, for the metadata of the position NFTs.
+const METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Arguments of the `open_position_v2` instruction.
+#[derive(AnchorSerialize)]
+struct OpenPositionV2 {
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    tick_array_lower_start_index: i32,
+    tick_array_upper_start_index: i32,
+    liquidity: u128,
+    amount_0_max: u64,
+    amount_1_max: u64,
+    with_metadata: bool,
+    base_flag: Option<bool>,
+}
+
+impl Discriminator for OpenPositionV2 {
+    const DISCRIMINATOR: [u8; 8] = [77, 184, 74, 214, 112, 86, 241, 199];
+}
+
+impl InstructionData for OpenPositionV2 {}
+
+/// Arguments of the `increase_liquidity_v2` instruction.
+#[derive(AnchorSerialize)]
+struct IncreaseLiquidityV2 {
+    liquidity: u128,
+    amount_0_max: u64,
+    amount_1_max: u64,
+    base_flag: Option<bool>,
+}
+
+impl Discriminator for IncreaseLiquidityV2 {
+    const DISCRIMINATOR: [u8; 8] = [133, 29, 89, 223, 69, 238, 176, 10];
+}
+
+impl InstructionData for IncreaseLiquidityV2 {}
+
+/// Arguments of the `decrease_liquidity_v2` instruction.
+#[derive(AnchorSerialize)]
+struct DecreaseLiquidityV2 {
+    liquidity: u128,
+    amount_0_min: u64,
+    amount_1_min: u64,
+}
+
+impl Discriminator for DecreaseLiquidityV2 {
+    const DISCRIMINATOR: [u8; 8] = [58, 127, 188, 62, 79, 82, 196, 96];
+}
+
+impl InstructionData for DecreaseLiquidityV2 {}
+
+/// Arguments of the `close_position` instruction.
+#[derive(AnchorSerialize)]
+struct ClosePosition;
+
+impl Discriminator for ClosePosition {
+    const DISCRIMINATOR: [u8; 8] = [123, 134, 81, 0, 49, 68, 98, 98];
+}
+
+impl InstructionData for ClosePosition {}
+
+/// A position in a CLMM pool, with the state of the pool.
+#[derive(Debug, Clone)]
+pub struct Position {
+    /// The CLMM program owning the position.
+    pub program: Pubkey,
+    /// The pool state account.
+    pub pool: Pubkey,
+    /// The state of the pool.
+    pub pool_state: PoolState,
+    /// The state of the position.
+    pub state: PersonalPositionState,
+}
+
+impl Position {
+    /// Fetches a position and its pool.
+    ///
+    /// # Parameters
+    /// * `ctx` - The cluster and payer to use,
+    /// * `nft_mint` - The mint of the NFT of the position.
+    ///
+    /// # Errors
+    /// If the position or its pool cannot be fetched.
+    pub async fn fetch(ctx: &Context, nft_mint: &Pubkey) -> Result<Self> {
+        let program = ctx.clmm_program;
+        let state =
+            fetch::<PersonalPositionState>(ctx, &pda::personal_position(&program, nft_mint))
+                .await?;
+        let pool_state = fetch::<PoolState>(ctx, &state.pool_id).await?;
+
+        Ok(Self {
+            program,
+            pool: state.pool_id,
+            pool_state,
+            state,
+        })
+    }
+
+    /// The amounts of tokens the liquidity of the position is worth at the current price.
+    ///
+    /// # Errors
+    /// If the range of the position is invalid.
+    #[expect(clippy::result_large_err)]
+    pub fn amounts(&self) -> Result<(u64, u64)> {
+        math::amounts_from_liquidity(
+            self.pool_state.tick_current,
+            self.pool_state.sqrt_price_x64,
+            self.state.tick_lower_index,
+            self.state.tick_upper_index,
+            self.state.liquidity,
+            false,
+        )
+    }
+
+    /// The minimum amounts of tokens to accept for removing liquidity from the position.
+    ///
+    /// # Parameters
+    /// * `liquidity` - Liquidity removed,
+    /// * `slippage_bps` - Tolerance on the amounts worth the liquidity at the current price.
+    ///
+    /// # Errors
+    /// If the range of the position is invalid.
+    #[expect(clippy::result_large_err)]
+    pub fn minimum_amounts(&self, liquidity: u128, slippage_bps: u16) -> Result<(u64, u64)> {
+        let (amount_0, amount_1) = math::amounts_from_liquidity(
+            self.pool_state.tick_current,
+            self.pool_state.sqrt_price_x64,
+            self.state.tick_lower_index,
+            self.state.tick_upper_index,
+            liquidity,
+            false,
+        )?;
+        Ok((
+            less_slippage(amount_0, slippage_bps),
+            less_slippage(amount_1, slippage_bps),
+        ))
+    }
+
+    /// The accounts of the range of the position, shared by the liquidity instructions.
+    fn range_accounts(&self) -> RangeAccounts {
+        RangeAccounts::new(
+            &self.program,
+            &self.pool,
+            &self.pool_state,
+            self.state.tick_lower_index,
+            self.state.tick_upper_index,
+        )
+    }
+}
+
+/// The accounts tracking the liquidity of a range of a pool.
+struct RangeAccounts {
+    protocol_position: Pubkey,
+    tick_array_lower_start_index: i32,
+    tick_array_upper_start_index: i32,
+    tick_array_lower: Pubkey,
+    tick_array_upper: Pubkey,
+    /// The bitmap extension, if one of the tick arrays is tracked by it.
+    bitmap_extension: Option<Pubkey>,
+}
+
+impl RangeAccounts {
+    fn new(
+        program: &Pubkey,
+        pool: &Pubkey,
+        state: &PoolState,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Self {
+        let lower_start = math::tick_array_start_index(tick_lower, state.tick_spacing);
+        let upper_start = math::tick_array_start_index(tick_upper, state.tick_spacing);
+        let in_extension = math::in_bitmap_extension(lower_start, state.tick_spacing)
+            || math::in_bitmap_extension(upper_start, state.tick_spacing);
+
+        Self {
+            protocol_position: pda::protocol_position(program, pool, tick_lower, tick_upper),
+            tick_array_lower_start_index: lower_start,
+            tick_array_upper_start_index: upper_start,
+            tick_array_lower: pda::tick_array(program, pool, lower_start),
+            tick_array_upper: pda::tick_array(program, pool, upper_start),
+            bitmap_extension: in_extension.then(|| pda::tick_array_bitmap_extension(program, pool)),
+        }
+    }
+
+    /// The bitmap extension as a remaining account, if needed.
+    fn remaining_accounts(&self) -> Vec<AccountMeta> {
+        self.bitmap_extension
+            .iter()
+            .map(|extension| AccountMeta::new(*extension, false))
+            .collect()
+    }
+}
+
+/// Opens a position in a range of a pool, minting its NFT to the owner.
+///
+/// The liquidity of the position is the largest one the amounts can provide at the
+/// current price of the pool.
+///
+/// # Parameters
+/// * `ctx` - The cluster and payer to use,
+/// * `owner` - Owner of the deposited tokens and of the position,
+/// * `pool` - The pool state account,
+/// * `state` - The state of the pool,
+/// * `tick_lower` - Lower tick of the range, a multiple of the tick spacing,
+/// * `tick_upper` - Upper tick of the range, a multiple of the tick spacing,
+/// * `amount_0_max` - Maximum amount of the first token to deposit,
+/// * `amount_1_max` - Maximum amount of the second token to deposit.
+///
+/// # Returns
+/// The mint of the NFT of the position.
+///
+/// # Errors
+/// If the range is invalid, the amounts provide no liquidity or the transaction fails.
+#[expect(clippy::too_many_arguments)]
+#[instrument(skip(ctx, owner, state))]
+pub async fn open_position(
+    ctx: &Context,
+    owner: &Keypair,
+    pool: Pubkey,
+    state: &PoolState,
+    tick_lower: i32,
+    tick_upper: i32,
+    amount_0_max: u64,
+    amount_1_max: u64,
+) -> Result<Pubkey> {
+    math::check_range(tick_lower, tick_upper, state.tick_spacing)?;
+    let liquidity = math::liquidity_from_amounts(
+        state.sqrt_price_x64,
+        tick_lower,
+        tick_upper,
+        amount_0_max,
+        amount_1_max,
+    )?;
+    if liquidity == 0 {
+        return Err(Error::Math(format!(
+            "{amount_0_max} / {amount_1_max} provide no liquidity in [{tick_lower}, {tick_upper}]"
+        )));
+    }
+
+    let program = ctx.clmm_program;
+    let owner_key = owner.pubkey();
+    let nft_mint = Keypair::new();
+    let range = RangeAccounts::new(&program, &pool, state, tick_lower, tick_upper);
+    let (account_0, create_account_0) = create_ata(&owner_key, &owner_key, &state.token_mint_0);
+    let (account_1, create_account_1) = create_ata(&owner_key, &owner_key, &state.token_mint_1);
+
+    let mut accounts = vec![
+        AccountMeta::new(owner_key, true),
+        AccountMeta::new_readonly(owner_key, false),
+        AccountMeta::new(nft_mint.pubkey(), true),
+        AccountMeta::new(
+            create_ata(&owner_key, &owner_key, &nft_mint.pubkey()).0,
+            false,
+        ),
+        AccountMeta::new(metadata(&nft_mint.pubkey()), false),
+        AccountMeta::new(pool, false),
+        AccountMeta::new(range.protocol_position, false),
+        AccountMeta::new(range.tick_array_lower, false),
+        AccountMeta::new(range.tick_array_upper, false),
+        AccountMeta::new(pda::personal_position(&program, &nft_mint.pubkey()), false),
+        AccountMeta::new(account_0, false),
+        AccountMeta::new(account_1, false),
+        AccountMeta::new(state.token_vault_0, false),
+        AccountMeta::new(state.token_vault_1, false),
+        AccountMeta::new_readonly(sysvar::rent::ID, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new_readonly(spl_token::ID, false),
+        AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+        AccountMeta::new_readonly(METADATA_PROGRAM_ID, false),
+        AccountMeta::new_readonly(spl_token_2022::ID, false),
+        AccountMeta::new_readonly(state.token_mint_0, false),
+        AccountMeta::new_readonly(state.token_mint_1, false),
+    ];
+    accounts.extend(range.remaining_accounts());
+    let ix = Instruction::new_with_bytes(
+        program,
+        &OpenPositionV2 {
+            tick_lower_index: tick_lower,
+            tick_upper_index: tick_upper,
+            tick_array_lower_start_index: range.tick_array_lower_start_index,
+            tick_array_upper_start_index: range.tick_array_upper_start_index,
+            liquidity,
+            amount_0_max,
+            amount_1_max,
+            // The metadata is not needed, and its rent would not be recovered on close.
+            with_metadata: false,
+            base_flag: None,
+        }
+        .data(),
+        accounts,
+    );
+    let sig = execute_instructions(
+        ctx,
+        &[create_account_0, create_account_1, ix],
+        &[owner, &nft_mint],
+    )
+    .await?;
+    info!(nft_mint = %nft_mint.pubkey(), %liquidity, "Position opened: {sig}");
+
+    Ok(nft_mint.pubkey())
+}
+
+/// Adds liquidity to a position.
+///
+/// # Parameters
+/// * `ctx` - The cluster and payer to use,
+/// * `owner` - Owner of the position and of the deposited tokens,
+/// * `position` - The position,
+/// * `amount_0_max` - Maximum amount of the first token to deposit,
+/// * `amount_1_max` - Maximum amount of the second token to deposit.
+///
+/// # Errors
+/// If the amounts provide no liquidity or the transaction fails.
+#[instrument(skip_all, fields(nft_mint = %position.state.nft_mint))]
+pub async fn increase_liquidity(
+    ctx: &Context,
+    owner: &Keypair,
+    position: &Position,
+    amount_0_max: u64,
+    amount_1_max: u64,
+) -> Result<Signature> {
+    let liquidity = math::liquidity_from_amounts(
+        position.pool_state.sqrt_price_x64,
+        position.state.tick_lower_index,
+        position.state.tick_upper_index,
+        amount_0_max,
+        amount_1_max,
+    )?;
+    if liquidity == 0 {
+        return Err(Error::Math(format!(
+            "{amount_0_max} / {amount_1_max} provide no liquidity to the position"
+        )));
+    }
+
+    let owner_key = owner.pubkey();
+    let pool_state = &position.pool_state;
+    let range = position.range_accounts();
+    let ata = |mint: &Pubkey| create_ata(&owner_key, &owner_key, mint).0;
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(owner_key, true),
+        AccountMeta::new_readonly(ata(&position.state.nft_mint), false),
+        AccountMeta::new(position.pool, false),
+        AccountMeta::new(range.protocol_position, false),
+        AccountMeta::new(
+            pda::personal_position(&position.program, &position.state.nft_mint),
+            false,
+        ),
+        AccountMeta::new(range.tick_array_lower, false),
+        AccountMeta::new(range.tick_array_upper, false),
+        AccountMeta::new(ata(&pool_state.token_mint_0), false),
+        AccountMeta::new(ata(&pool_state.token_mint_1), false),
+        AccountMeta::new(pool_state.token_vault_0, false),
+        AccountMeta::new(pool_state.token_vault_1, false),
+        AccountMeta::new_readonly(spl_token::ID, false),
+        AccountMeta::new_readonly(spl_token_2022::ID, false),
+        AccountMeta::new_readonly(pool_state.token_mint_0, false),
+        AccountMeta::new_readonly(pool_state.token_mint_1, false),
+    ];
+    accounts.extend(range.remaining_accounts());
+    let ix = Instruction::new_with_bytes(
+        position.program,
+        &IncreaseLiquidityV2 {
+            liquidity,
+            amount_0_max,
+            amount_1_max,
+            base_flag: None,
+        }
+        .data(),
+        accounts,
+    );
+    let sig = execute_instructions(ctx, &[ix], &[owner]).await?;
+    info!(%liquidity, "Increased liquidity: {sig}");
+
+    Ok(sig)
+}
+
+/// Removes liquidity from a position, collecting its fees and rewards.
+///
+/// # Parameters
+/// * `ctx` - The cluster and payer to use,
+/// * `owner` - Owner of the position, receiving the tokens,
+/// * `position` - The position,
+/// * `liquidity` - Liquidity to remove (0 to only collect the fees and rewards),
+/// * `slippage_bps` - Tolerance on the amounts of tokens received, in basis points.
+///
+/// # Errors
+/// If the range of the position is invalid, or the transaction fails.
+#[instrument(skip_all, fields(nft_mint = %position.state.nft_mint))]
+pub async fn decrease_liquidity(
+    ctx: &Context,
+    owner: &Keypair,
+    position: &Position,
+    liquidity: u128,
+    slippage_bps: u16,
+) -> Result<Signature> {
+    let (amount_0_min, amount_1_min) = position.minimum_amounts(liquidity, slippage_bps)?;
+    let instructions = decrease_liquidity_instructions(
+        &owner.pubkey(),
+        position,
+        liquidity,
+        amount_0_min,
+        amount_1_min,
+    );
+    let sig = execute_instructions(ctx, &instructions, &[owner]).await?;
+    info!(%liquidity, %amount_0_min, %amount_1_min, "Decreased liquidity: {sig}");
+
+    Ok(sig)
+}
+
+/// Collects the fees and rewards of a position, leaving its liquidity.
+///
+/// # Parameters
+/// * `ctx` - The cluster and payer to use,
+/// * `owner` - Owner of the position, receiving the tokens,
+/// * `position` - The position.
+///
+/// # Errors
+/// If the transaction fails.
+pub async fn collect_fees(
+    ctx: &Context,
+    owner: &Keypair,
+    position: &Position,
+) -> Result<Signature> {
+    decrease_liquidity(ctx, owner, position, 0, 0).await
+}
+
+/// Closes a position and burns its NFT, withdrawing its remaining liquidity, fees and
+/// rewards first.
+///
+/// # Parameters
+/// * `ctx` - The cluster and payer to use,
+/// * `owner` - Owner of the position, receiving the tokens and the rent,
+/// * `position` - The position,
+/// * `slippage_bps` - Tolerance on the amounts of tokens withdrawn, in basis points.
+///
+/// # Errors
+/// If the range of the position is invalid, or the transaction fails.
+#[instrument(skip_all, fields(nft_mint = %position.state.nft_mint))]
+pub async fn close_position(
+    ctx: &Context,
+    owner: &Keypair,
+    position: &Position,
+    slippage_bps: u16,
+) -> Result<Signature> {
+    let owner_key = owner.pubkey();
+    let nft_mint = position.state.nft_mint;
+
+    let mut instructions = if position.state.is_empty() {
+        vec![]
+    } else {
+        let liquidity = position.state.liquidity;
+        let (amount_0_min, amount_1_min) = position.minimum_amounts(liquidity, slippage_bps)?;
+        decrease_liquidity_instructions(&owner_key, position, liquidity, amount_0_min, amount_1_min)
+    };
+    instructions.push(Instruction::new_with_bytes(
+        position.program,
+        &ClosePosition.data(),
+        vec![
+            AccountMeta::new(owner_key, true),
+            AccountMeta::new(nft_mint, false),
+            AccountMeta::new(create_ata(&owner_key, &owner_key, &nft_mint).0, false),
+            AccountMeta::new(pda::personal_position(&position.program, &nft_mint), false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+        ],
+    ));
+    let sig = execute_instructions(ctx, &instructions, &[owner]).await?;
+    info!("Position closed: {sig}");
+
+    Ok(sig)
+}
+
+/// The instructions removing liquidity from a position, creating the accounts receiving
+/// the tokens if needed.
+fn decrease_liquidity_instructions(
+    owner: &Pubkey,
+    position: &Position,
+    liquidity: u128,
+    amount_0_min: u64,
+    amount_1_min: u64,
+) -> Vec<Instruction> {
+    let pool_state = &position.pool_state;
+    let range = position.range_accounts();
+    let (account_0, create_account_0) = create_ata(owner, owner, &pool_state.token_mint_0);
+    let (account_1, create_account_1) = create_ata(owner, owner, &pool_state.token_mint_1);
+    let mut instructions = vec![create_account_0, create_account_1];
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new_readonly(create_ata(owner, owner, &position.state.nft_mint).0, false),
+        AccountMeta::new(
+            pda::personal_position(&position.program, &position.state.nft_mint),
+            false,
+        ),
+        AccountMeta::new(position.pool, false),
+        AccountMeta::new(range.protocol_position, false),
+        AccountMeta::new(pool_state.token_vault_0, false),
+        AccountMeta::new(pool_state.token_vault_1, false),
+        AccountMeta::new(range.tick_array_lower, false),
+        AccountMeta::new(range.tick_array_upper, false),
+        AccountMeta::new(account_0, false),
+        AccountMeta::new(account_1, false),
+        AccountMeta::new_readonly(spl_token::ID, false),
+        AccountMeta::new_readonly(spl_token_2022::ID, false),
+        AccountMeta::new_readonly(MEMO_PROGRAM_ID, false),
+        AccountMeta::new_readonly(pool_state.token_mint_0, false),
+        AccountMeta::new_readonly(pool_state.token_mint_1, false),
+    ];
+    accounts.extend(range.remaining_accounts());
+    // The program pays the rewards of the pool: vault, recipient and mint of each one
+    for reward in pool_state.reward_infos.iter().filter(|r| r.initialized()) {
+        let (recipient, create_recipient) = create_ata(owner, owner, &reward.token_mint);
+        instructions.push(create_recipient);
+        accounts.extend([
+            AccountMeta::new(reward.token_vault, false),
+            AccountMeta::new(recipient, false),
+            AccountMeta::new_readonly(reward.token_mint, false),
+        ]);
+    }
+
+    instructions.push(Instruction::new_with_bytes(
+        position.program,
+        &DecreaseLiquidityV2 {
+            liquidity,
+            amount_0_min,
+            amount_1_min,
+        }
+        .data(),
+        accounts,
+    ));
+    instructions
+}
+
+/// The Metaplex metadata account of a mint.
+fn metadata(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+
+    use test_log::test;
+
+    use super::*;
+    use crate::{config::profile::DEVNET_CLMM_PROGRAM, klend::state::decode};
+    type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
+
+    /// A position of 1000 liquidity in [-600, 600] in a pool with a tick spacing of 10.
+    fn position() -> core::result::Result<Position, Box<dyn core::error::Error>> {
+        let pool = Pubkey::new_unique();
+        let mut pool_data = PoolState::DISCRIMINATOR.to_vec();
+        pool_data.resize(1544, 0);
+        let mut pool_state: PoolState = decode(&pool, &pool_data)?;
+        pool_state.tick_spacing = 10;
+        pool_state.sqrt_price_x64 = math::sqrt_price_at_tick(0)?;
+
+        let mut position_data = PersonalPositionState::DISCRIMINATOR.to_vec();
+        position_data.resize(281, 0);
+        let mut state: PersonalPositionState = decode(&pool, &position_data)?;
+        state.nft_mint = Pubkey::new_unique();
+        state.pool_id = pool;
+        state.tick_lower_index = -600_i32;
+        state.tick_upper_index = 600_i32;
+        state.liquidity = 1000;
+
+        Ok(Position {
+            program: DEVNET_CLMM_PROGRAM,
+            pool,
+            pool_state,
+            state,
+        })
+    }
+
+    #[test]
+    fn range_tick_arrays() -> TestResult {
+        // Given
+        let mut position = position()?;
+
+        // When
+        let range = position.range_accounts();
+        position.state.tick_lower_index = -600 * 512 - 600;
+        let extended = position.range_accounts();
+
+        // Then
+        assert_eq!(range.tick_array_lower_start_index, -600);
+        assert_eq!(range.tick_array_upper_start_index, 600);
+        assert!(range.remaining_accounts().is_empty(), "in the pool bitmap");
+        assert_eq!(
+            extended.remaining_accounts()[0].pubkey,
+            pda::tick_array_bitmap_extension(&position.program, &position.pool),
+            "out of the pool bitmap"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn decrease_pays_rewards() -> TestResult {
+        // Given
+        let owner = Pubkey::new_unique();
+        let mut position = position()?;
+        let reward_mint = Pubkey::new_unique();
+        position.pool_state.reward_infos[1].token_mint = reward_mint;
+
+        // When
+        let instructions = decrease_liquidity_instructions(&owner, &position, 1000, 0, 0);
+
+        // Then
+        let Some(decrease) = instructions.last() else {
+            return Err("no instruction".into());
+        };
+        assert_eq!(instructions.len(), 4, "the reward account is created");
+        assert_eq!(
+            decrease.accounts.len(),
+            16 + 3,
+            "the reward accounts follow"
+        );
+        assert_eq!(decrease.accounts[18].pubkey, reward_mint);
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use solana_sdk::pubkey::Pubkey;
+
+const POSITION_SEED: &[u8] = b"position";
+const TICK_ARRAY_SEED: &[u8] = b"tick_array";
+const TICK_ARRAY_BITMAP_SEED: &[u8] = b"pool_tick_array_bitmap_extension";
+
+fn find(program_id: &Pubkey, seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, program_id).0
+}
+
+/// The tick array of a pool starting at a tick.
+#[expect(clippy::big_endian_bytes)]
+pub fn tick_array(program_id: &Pubkey, pool: &Pubkey, start_index: i32) -> Pubkey {
+    // The program uses the big endian representation of the index as seed.
+    find(
+        program_id,
+        &[TICK_ARRAY_SEED, pool.as_ref(), &start_index.to_be_bytes()],
+    )
+}
+
+/// The extension of the bitmap of the initialized tick arrays of a pool.
+pub fn tick_array_bitmap_extension(program_id: &Pubkey, pool: &Pubkey) -> Pubkey {
+    find(program_id, &[TICK_ARRAY_BITMAP_SEED, pool.as_ref()])
+}
+
+/// The liquidity of all the positions of a pool in a range.
+#[expect(clippy::big_endian_bytes)]
+pub fn protocol_position(
+    program_id: &Pubkey,
+    pool: &Pubkey,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> Pubkey {
+    // The program uses the big endian representation of the ticks as seeds.
+    find(
+        program_id,
+        &[
+            POSITION_SEED,
+            pool.as_ref(),
+            &tick_lower.to_be_bytes(),
+            &tick_upper.to_be_bytes(),
+        ],
+    )
+}
+
+/// The position represented by an NFT.
+pub fn personal_position(program_id: &Pubkey, nft_mint: &Pubkey) -> Pubkey {
+    find(program_id, &[POSITION_SEED, nft_mint.as_ref()])
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+
+    use test_log::test;
+
+    use super::*;
+    use crate::config::profile::DEVNET_CLMM_PROGRAM;
+
+    #[test]
+    fn tick_arrays_are_signed() {
+        // Given
+        let pool = Pubkey::new_unique();
+
+        // When
+        let positive = tick_array(&DEVNET_CLMM_PROGRAM, &pool, 600);
+        let negative = tick_array(&DEVNET_CLMM_PROGRAM, &pool, -600);
+
+        // Then
+        assert_ne!(positive, negative, "the sign of the index matters");
+    }
+
+    #[test]
+    fn positions_are_distinct() {
+        // Given
+        let pool = Pubkey::new_unique();
+
+        // When
+        let range = protocol_position(&DEVNET_CLMM_PROGRAM, &pool, -600, 600);
+        let swapped = protocol_position(&DEVNET_CLMM_PROGRAM, &pool, 600, -600);
+
+        // Then
+        assert_ne!(range, swapped, "the order of the ticks matters");
+        assert_ne!(
+            personal_position(&DEVNET_CLMM_PROGRAM, &pool),
+            tick_array_bitmap_extension(&DEVNET_CLMM_PROGRAM, &pool),
+            "seeds are prefixed"
+        );
+    }
+}
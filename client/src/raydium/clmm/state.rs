@@ -0,0 +1,292 @@
+use core::fmt;
+
+use anchor_client::anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator};
+use solana_sdk::pubkey::Pubkey;
+
+use super::math::price_from_sqrt_price;
+use crate::raydium::{try_deserialize, try_deserialize_unchecked};
+
+/// Number of reward tokens a pool can distribute.
+const REWARD_NUM: usize = 3;
+
+/// Bit of the pool status disabling new positions and liquidity increases.
+const STATUS_OPEN_POSITION_DISABLED: u8 = 1;
+/// Bit of the pool status disabling liquidity decreases.
+const STATUS_DECREASE_LIQUIDITY_DISABLED: u8 = 1 << 1;
+/// Bit of the pool status disabling the collection of fees.
+const STATUS_COLLECT_FEE_DISABLED: u8 = 1 << 2;
+/// Bit of the pool status disabling swaps.
+const STATUS_SWAP_DISABLED: u8 = 1 << 4;
+
+/// A token distributed to the liquidity providers of a pool.
+#[derive(Debug, Clone, Copy, AnchorDeserialize)]
+#[expect(dead_code)]
+pub struct RewardInfo {
+    pub reward_state: u8,
+    pub open_time: u64,
+    pub end_time: u64,
+    pub last_update_time: u64,
+    pub emissions_per_second_x64: u128,
+    pub reward_total_emissioned: u64,
+    pub reward_claimed: u64,
+    /// The mint of the reward, the default key if the reward is not initialized.
+    pub token_mint: Pubkey,
+    pub token_vault: Pubkey,
+    pub authority: Pubkey,
+    pub reward_growth_global_x64: u128,
+}
+
+impl RewardInfo {
+    /// Whether the reward is used by the pool.
+    pub fn initialized(&self) -> bool {
+        self.token_mint != Pubkey::default()
+    }
+}
+
+/// The state of a CLMM pool.
+///
+/// Only the fields up to the open time are decoded, the rest of the account is padding.
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[expect(dead_code)]
+pub struct PoolState {
+    pub bump: u8,
+    /// The fee configuration of the pool.
+    pub amm_config: Pubkey,
+    pub owner: Pubkey,
+    /// Mint of the first token of the pool (the lowest of the two mints).
+    pub token_mint_0: Pubkey,
+    pub token_mint_1: Pubkey,
+    pub token_vault_0: Pubkey,
+    pub token_vault_1: Pubkey,
+    pub observation_key: Pubkey,
+    pub mint_decimals_0: u8,
+    pub mint_decimals_1: u8,
+    /// The ticks of the positions must be multiples of the spacing.
+    pub tick_spacing: u16,
+    /// The liquidity of the positions in range of the current price.
+    pub liquidity: u128,
+    /// The square root of the current price (tokens 1 per token 0), as a Q64.64 number.
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub padding3: u16,
+    pub padding4: u16,
+    pub fee_growth_global_0_x64: u128,
+    pub fee_growth_global_1_x64: u128,
+    pub protocol_fees_token_0: u64,
+    pub protocol_fees_token_1: u64,
+    pub swap_in_amount_token_0: u128,
+    pub swap_out_amount_token_1: u128,
+    pub swap_in_amount_token_1: u128,
+    pub swap_out_amount_token_0: u128,
+    /// Operations disabled on the pool (see the `STATUS_*` bits).
+    pub status: u8,
+    pub padding: [u8; 7],
+    pub reward_infos: [RewardInfo; REWARD_NUM],
+    pub tick_array_bitmap: [u64; 16],
+    pub total_fees_token_0: u64,
+    pub total_fees_claimed_token_0: u64,
+    pub total_fees_token_1: u64,
+    pub total_fees_claimed_token_1: u64,
+    pub fund_fees_token_0: u64,
+    pub fund_fees_token_1: u64,
+    /// Timestamp from which the pool accepts swaps.
+    pub open_time: u64,
+}
+
+impl PoolState {
+    /// Whether an operation (one of the `STATUS_*` bits) is enabled.
+    const fn enabled(&self, bit: u8) -> bool {
+        self.status & bit == 0
+    }
+
+    /// The current price of the pool, in tokens 1 per token 0.
+    pub fn price(&self) -> f64 {
+        price_from_sqrt_price(
+            self.sqrt_price_x64,
+            self.mint_decimals_0,
+            self.mint_decimals_1,
+        )
+    }
+}
+
+impl Discriminator for PoolState {
+    const DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+}
+
+impl AccountDeserialize for PoolState {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_client::anchor_lang::Result<Self> {
+        try_deserialize(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_client::anchor_lang::Result<Self> {
+        try_deserialize_unchecked(buf)
+    }
+}
+
+impl fmt::Display for PoolState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "CLMM pool (config {})", self.amm_config)?;
+        writeln!(
+            f,
+            "  token 0: {} ({} decimals), vault {}",
+            self.token_mint_0, self.mint_decimals_0, self.token_vault_0
+        )?;
+        writeln!(
+            f,
+            "  token 1: {} ({} decimals), vault {}",
+            self.token_mint_1, self.mint_decimals_1, self.token_vault_1
+        )?;
+        writeln!(
+            f,
+            "  price: {} (tick {}, spacing {}), liquidity in range {}",
+            self.price(),
+            self.tick_current,
+            self.tick_spacing,
+            self.liquidity
+        )?;
+        write!(
+            f,
+            "  open position: {}, decrease liquidity: {}, collect fees: {}, swap: {}, open since {}",
+            self.enabled(STATUS_OPEN_POSITION_DISABLED),
+            self.enabled(STATUS_DECREASE_LIQUIDITY_DISABLED),
+            self.enabled(STATUS_COLLECT_FEE_DISABLED),
+            self.enabled(STATUS_SWAP_DISABLED),
+            self.open_time
+        )
+    }
+}
+
+/// The rewards of a position.
+#[derive(Debug, Clone, Copy, AnchorDeserialize)]
+#[expect(dead_code)]
+pub struct PositionRewardInfo {
+    pub growth_inside_last_x64: u128,
+    pub reward_amount_owed: u64,
+}
+
+/// A position in a CLMM pool, owned by the holder of its NFT.
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[expect(dead_code)]
+pub struct PersonalPositionState {
+    pub bump: u8,
+    /// The mint of the NFT representing the position.
+    pub nft_mint: Pubkey,
+    pub pool_id: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub liquidity: u128,
+    pub fee_growth_inside_0_last_x64: u128,
+    pub fee_growth_inside_1_last_x64: u128,
+    /// Fees owed to the position, as of its last update.
+    pub token_fees_owed_0: u64,
+    pub token_fees_owed_1: u64,
+    pub reward_infos: [PositionRewardInfo; REWARD_NUM],
+    pub recent_epoch: u64,
+    pub padding: [u64; 7],
+}
+
+impl PersonalPositionState {
+    /// Whether the position holds no liquidity, fees or rewards, and can be closed.
+    pub fn is_empty(&self) -> bool {
+        self.liquidity == 0
+            && self.token_fees_owed_0 == 0
+            && self.token_fees_owed_1 == 0
+            && self
+                .reward_infos
+                .iter()
+                .all(|reward| reward.reward_amount_owed == 0)
+    }
+}
+
+impl Discriminator for PersonalPositionState {
+    const DISCRIMINATOR: [u8; 8] = [70, 111, 150, 126, 230, 15, 25, 117];
+}
+
+impl AccountDeserialize for PersonalPositionState {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_client::anchor_lang::Result<Self> {
+        try_deserialize(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_client::anchor_lang::Result<Self> {
+        try_deserialize_unchecked(buf)
+    }
+}
+
+impl fmt::Display for PersonalPositionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "CLMM position {} (pool {})", self.nft_mint, self.pool_id)?;
+        writeln!(
+            f,
+            "  range: [{}, {}], liquidity {}",
+            self.tick_lower_index, self.tick_upper_index, self.liquidity
+        )?;
+        write!(
+            f,
+            "  fees owed: {} / {}",
+            self.token_fees_owed_0, self.token_fees_owed_1
+        )
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+
+    use test_log::test;
+
+    use super::*;
+    use crate::klend::state::decode;
+    type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
+
+    /// Size of a pool state account, discriminator included.
+    const POOL_STATE_LEN: usize = 1544;
+    /// Size of a personal position account, discriminator included.
+    const PERSONAL_POSITION_LEN: usize = 281;
+
+    #[test]
+    fn decode_pool_state() -> TestResult {
+        // Given
+        let address = Pubkey::new_unique();
+        let mint_1 = Pubkey::new_unique();
+        let mut data = PoolState::DISCRIMINATOR.to_vec();
+        data.resize(POOL_STATE_LEN, 0);
+        // the bump, then mint 1 is the 4th key
+        data[8 + 1 + 3 * 32..8 + 1 + 4 * 32].copy_from_slice(mint_1.as_ref());
+        // tick spacing, after the 7 keys and the decimals
+        let spacing = 8 + 1 + 7 * 32 + 2;
+        data[spacing..spacing + 2].copy_from_slice(&[10, 0]);
+
+        // When
+        let pool: PoolState = decode(&address, &data)?;
+
+        // Then
+        assert_eq!(pool.token_mint_1, mint_1);
+        assert_eq!(pool.tick_spacing, 10);
+        assert!(
+            pool.reward_infos.iter().all(|reward| !reward.initialized()),
+            "no rewards"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn decode_position() -> TestResult {
+        // Given
+        let address = Pubkey::new_unique();
+        let pool = Pubkey::new_unique();
+        let mut data = PersonalPositionState::DISCRIMINATOR.to_vec();
+        data.resize(PERSONAL_POSITION_LEN, 0);
+        // the bump and the NFT mint, then the pool and the ticks
+        data[8 + 1 + 32..8 + 1 + 2 * 32].copy_from_slice(pool.as_ref());
+        data[8 + 1 + 2 * 32..8 + 1 + 2 * 32 + 4].copy_from_slice(&[0xa8, 0xfd, 0xff, 0xff]);
+
+        // When
+        let position: PersonalPositionState = decode(&address, &data)?;
+
+        // Then
+        assert_eq!(position.pool_id, pool);
+        assert_eq!(position.tick_lower_index, -600);
+        assert!(position.is_empty(), "nothing in the position");
+        Ok(())
+    }
+}
@@ -0,0 +1,676 @@
+pub mod pda;
+pub mod state;
+
+use anchor_client::anchor_lang::{AnchorSerialize, Discriminator, InstructionData};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    system_program, sysvar,
+};
+use tracing::{debug, info, instrument};
+
+use crate::{
+    config::Context,
+    error::{Error, Result},
+    klend::state::fetch,
+    lending::{create_ata, get_token_balance},
+    raydium::quote::{ConstantProduct, DepositQuote, less_slippage, plus_slippage},
+    transaction::execute_instructions,
+};
+use state::{AmmConfig, PoolState};
+
+/// The SPL memo program, required by the withdraw instruction.
+const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+/// Arguments of the `initialize` instruction.
+#[derive(AnchorSerialize)]
+struct Initialize {
+    init_amount_0: u64,
+    init_amount_1: u64,
+    open_time: u64,
+}
+
+impl Discriminator for Initialize {
+    const DISCRIMINATOR: [u8; 8] = [175, 175, 109, 31, 13, 152, 155, 237];
+}
+
+impl InstructionData for Initialize {}
+
+/// Arguments of the `deposit` instruction.
+#[derive(AnchorSerialize)]
+#[expect(clippy::struct_field_names)]
+struct Deposit {
+    lp_token_amount: u64,
+    maximum_token_0_amount: u64,
+    maximum_token_1_amount: u64,
+}
+
+impl Discriminator for Deposit {
+    const DISCRIMINATOR: [u8; 8] = [242, 35, 198, 137, 82, 225, 242, 182];
+}
+
+impl InstructionData for Deposit {}
+
+/// Arguments of the `withdraw` instruction.
+#[derive(AnchorSerialize)]
+#[expect(clippy::struct_field_names)]
+struct Withdraw {
+    lp_token_amount: u64,
+    minimum_token_0_amount: u64,
+    minimum_token_1_amount: u64,
+}
+
+impl Discriminator for Withdraw {
+    const DISCRIMINATOR: [u8; 8] = [183, 18, 70, 156, 148, 109, 161, 34];
+}
+
+impl InstructionData for Withdraw {}
+
+/// Arguments of the `swap_base_input` instruction.
+#[derive(AnchorSerialize)]
+struct SwapBaseInput {
+    amount_in: u64,
+    minimum_amount_out: u64,
+}
+
+impl Discriminator for SwapBaseInput {
+    const DISCRIMINATOR: [u8; 8] = [143, 190, 90, 218, 196, 30, 51, 222];
+}
+
+impl InstructionData for SwapBaseInput {}
+
+/// Arguments of the `swap_base_output` instruction.
+#[derive(AnchorSerialize)]
+struct SwapBaseOutput {
+    max_amount_in: u64,
+    amount_out: u64,
+}
+
+impl Discriminator for SwapBaseOutput {
+    const DISCRIMINATOR: [u8; 8] = [55, 217, 98, 86, 163, 74, 180, 173];
+}
+
+impl InstructionData for SwapBaseOutput {}
+
+/// The addresses involved in operations on a CPMM pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolKeys {
+    /// The CPMM program owning the pool.
+    pub program: Pubkey,
+    /// The pool state account.
+    pub pool: Pubkey,
+    /// The fee configuration of the pool.
+    pub amm_config: Pubkey,
+    /// The authority owning the vaults and the LP mint.
+    pub authority: Pubkey,
+    /// Mint of the first token of the pool (the lowest of the two mints).
+    pub mint_0: Pubkey,
+    /// Mint of the second token of the pool.
+    pub mint_1: Pubkey,
+    /// Token program of the first token.
+    pub token_program_0: Pubkey,
+    /// Token program of the second token.
+    pub token_program_1: Pubkey,
+    /// Vault holding the first token.
+    pub vault_0: Pubkey,
+    /// Vault holding the second token.
+    pub vault_1: Pubkey,
+    /// Mint of the LP tokens.
+    pub lp_mint: Pubkey,
+    /// The price observations of the pool.
+    pub observation: Pubkey,
+}
+
+impl PoolKeys {
+    /// Derives the addresses of the pool of two classic SPL tokens using a fee configuration.
+    ///
+    /// # Parameters
+    /// * `program` - The CPMM program,
+    /// * `amm_config` - The fee configuration of the pool,
+    /// * `mint_a` - One of the pool's mints,
+    /// * `mint_b` - The other mint of the pool.
+    pub fn new(program: Pubkey, amm_config: Pubkey, mint_a: Pubkey, mint_b: Pubkey) -> Self {
+        let (mint_0, mint_1) = if mint_a < mint_b {
+            (mint_a, mint_b)
+        } else {
+            (mint_b, mint_a)
+        };
+        let pool = pda::pool(&program, &amm_config, &mint_0, &mint_1);
+
+        Self {
+            program,
+            pool,
+            amm_config,
+            authority: pda::authority(&program),
+            mint_0,
+            mint_1,
+            token_program_0: spl_token::ID,
+            token_program_1: spl_token::ID,
+            vault_0: pda::vault(&program, &pool, &mint_0),
+            vault_1: pda::vault(&program, &pool, &mint_1),
+            lp_mint: pda::lp_mint(&program, &pool),
+            observation: pda::observation(&program, &pool),
+        }
+    }
+
+    /// The addresses of an existing pool, from its state.
+    ///
+    /// # Parameters
+    /// * `program` - The CPMM program owning the pool,
+    /// * `pool` - The pool state account,
+    /// * `state` - Its decoded state.
+    pub fn from_state(program: Pubkey, pool: Pubkey, state: &PoolState) -> Self {
+        Self {
+            program,
+            pool,
+            amm_config: state.amm_config,
+            authority: pda::authority(&program),
+            mint_0: state.token_0_mint,
+            mint_1: state.token_1_mint,
+            token_program_0: state.token_0_program,
+            token_program_1: state.token_1_program,
+            vault_0: state.token_0_vault,
+            vault_1: state.token_1_vault,
+            lp_mint: state.lp_mint,
+            observation: state.observation_key,
+        }
+    }
+
+    /// Fetches the state of a pool to get its addresses.
+    ///
+    /// # Parameters
+    /// * `ctx` - The cluster and payer to use,
+    /// * `pool` - The pool state account.
+    ///
+    /// # Errors
+    /// If the account does not exist or is not a CPMM pool.
+    pub async fn fetch(ctx: &Context, pool: Pubkey) -> Result<Self> {
+        Ok(Self::from_state(
+            ctx.cpmm_program,
+            pool,
+            &fetch::<PoolState>(ctx, &pool).await?,
+        ))
+    }
+
+    /// Fetches the state, fee configuration and vault balances of the pool to quote its
+    /// operations.
+    ///
+    /// # Parameters
+    /// * `ctx` - The cluster and payer to use.
+    ///
+    /// # Errors
+    /// If the pool or its configuration could not be fetched.
+    pub async fn fetch_constant_product(&self, ctx: &Context) -> Result<ConstantProduct> {
+        let state = fetch::<PoolState>(ctx, &self.pool).await?;
+        let config = fetch::<AmmConfig>(ctx, &state.amm_config).await?;
+        Ok(state.constant_product(
+            &config,
+            get_token_balance(ctx, &self.vault_0).await?,
+            get_token_balance(ctx, &self.vault_1).await?,
+        ))
+    }
+
+    /// The accounts of the pool used by its instructions, to put in a lookup table.
+    pub fn addresses(&self) -> Vec<Pubkey> {
+        vec![
+            self.pool,
+            self.amm_config,
+            self.authority,
+            self.mint_0,
+            self.mint_1,
+            self.vault_0,
+            self.vault_1,
+            self.lp_mint,
+            self.observation,
+        ]
+    }
+
+    /// Whether a swap sells the first token of the pool.
+    ///
+    /// # Errors
+    /// If `input_mint` is not one of the pool's mints.
+    #[expect(clippy::result_large_err)]
+    pub fn zero_for_one(&self, input_mint: &Pubkey) -> Result<bool> {
+        if *input_mint == self.mint_0 {
+            Ok(true)
+        } else if *input_mint == self.mint_1 {
+            Ok(false)
+        } else {
+            Err(Error::Instruction(format!(
+                "{input_mint} is not a mint of the pool {}",
+                self.pool
+            )))
+        }
+    }
+
+    /// The accounts shared by the deposit and withdraw instructions.
+    fn liquidity_accounts(&self, owner: &Pubkey) -> Vec<AccountMeta> {
+        let ata = |mint: &Pubkey| create_ata(owner, owner, mint).0;
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(self.authority, false),
+            AccountMeta::new(self.pool, false),
+            AccountMeta::new(ata(&self.lp_mint), false),
+            AccountMeta::new(ata(&self.mint_0), false),
+            AccountMeta::new(ata(&self.mint_1), false),
+            AccountMeta::new(self.vault_0, false),
+            AccountMeta::new(self.vault_1, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_token_2022::ID, false),
+            AccountMeta::new_readonly(self.mint_0, false),
+            AccountMeta::new_readonly(self.mint_1, false),
+            AccountMeta::new(self.lp_mint, false),
+        ]
+    }
+
+    /// The accounts of the swap instructions, trading `input_mint` for the other token.
+    ///
+    /// # Errors
+    /// If `input_mint` is not one of the pool's mints.
+    #[expect(clippy::result_large_err)]
+    fn swap_accounts(&self, owner: &Pubkey, input_mint: &Pubkey) -> Result<Vec<AccountMeta>> {
+        let input_is_0 = self.zero_for_one(input_mint)?;
+        let token_0 = (self.mint_0, self.vault_0, self.token_program_0);
+        let token_1 = (self.mint_1, self.vault_1, self.token_program_1);
+        let ((sold_mint, sold_vault, sold_program), (bought_mint, bought_vault, bought_program)) =
+            if input_is_0 {
+                (token_0, token_1)
+            } else {
+                (token_1, token_0)
+            };
+        let ata = |mint: &Pubkey| create_ata(owner, owner, mint).0;
+
+        Ok(vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(self.authority, false),
+            AccountMeta::new_readonly(self.amm_config, false),
+            AccountMeta::new(self.pool, false),
+            AccountMeta::new(ata(&sold_mint), false),
+            AccountMeta::new(ata(&bought_mint), false),
+            AccountMeta::new(sold_vault, false),
+            AccountMeta::new(bought_vault, false),
+            AccountMeta::new_readonly(sold_program, false),
+            AccountMeta::new_readonly(bought_program, false),
+            AccountMeta::new_readonly(sold_mint, false),
+            AccountMeta::new_readonly(bought_mint, false),
+            AccountMeta::new(self.observation, false),
+        ])
+    }
+}
+
+/// Creates a pool and deposits its initial liquidity.
+///
+/// # Parameters
+/// * `ctx` - The cluster and payer to use,
+/// * `creator` - Creator of the pool, providing the initial liquidity,
+/// * `keys` - Addresses of the pool to create (see [`PoolKeys::new`]),
+/// * `init_amount_0` - Amount of the first token deposited,
+/// * `init_amount_1` - Amount of the second token deposited,
+/// * `open_time` - Timestamp from which swaps are allowed (0 for immediately).
+///
+/// # Errors
+/// If the transaction fails.
+#[instrument(skip(ctx, creator))]
+pub async fn initialize(
+    ctx: &Context,
+    creator: &Keypair,
+    keys: &PoolKeys,
+    init_amount_0: u64,
+    init_amount_1: u64,
+    open_time: u64,
+) -> Result<Signature> {
+    let owner = creator.pubkey();
+    let ata = |mint: &Pubkey| create_ata(&owner, &owner, mint).0;
+    let ix = Instruction::new_with_bytes(
+        keys.program,
+        &Initialize {
+            init_amount_0,
+            init_amount_1,
+            open_time,
+        }
+        .data(),
+        vec![
+            AccountMeta::new(owner, true),
+            AccountMeta::new_readonly(keys.amm_config, false),
+            AccountMeta::new_readonly(keys.authority, false),
+            AccountMeta::new(keys.pool, false),
+            AccountMeta::new_readonly(keys.mint_0, false),
+            AccountMeta::new_readonly(keys.mint_1, false),
+            AccountMeta::new(keys.lp_mint, false),
+            AccountMeta::new(ata(&keys.mint_0), false),
+            AccountMeta::new(ata(&keys.mint_1), false),
+            AccountMeta::new(ata(&keys.lp_mint), false),
+            AccountMeta::new(keys.vault_0, false),
+            AccountMeta::new(keys.vault_1, false),
+            AccountMeta::new(ctx.cpmm_fee_receiver, false),
+            AccountMeta::new(keys.observation, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(keys.token_program_0, false),
+            AccountMeta::new_readonly(keys.token_program_1, false),
+            AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(sysvar::rent::ID, false),
+        ],
+    );
+    let sig = execute_instructions(ctx, &[ix], &[creator]).await?;
+    info!(pool = %keys.pool, "Pool created: {sig}");
+
+    Ok(sig)
+}
+
+/// Builds the instructions depositing liquidity in a pool, creating the LP token account if
+/// needed.
+///
+/// # Parameters
+/// * `owner` - Owner of the deposited tokens,
+/// * `keys` - Addresses of the pool,
+/// * `quote` - The expected amounts of the deposit,
+/// * `slippage_bps` - Tolerance on the amounts of tokens deposited, in basis points.
+pub fn deposit_instructions(
+    owner: &Pubkey,
+    keys: &PoolKeys,
+    quote: &DepositQuote,
+    slippage_bps: u16,
+) -> Vec<Instruction> {
+    let maximum_token_0_amount = plus_slippage(quote.amount_0, slippage_bps);
+    let maximum_token_1_amount = plus_slippage(quote.amount_1, slippage_bps);
+    debug!(?quote, %maximum_token_0_amount, %maximum_token_1_amount, "Deposit quoted");
+
+    let create_lp_ata = create_ata(owner, owner, &keys.lp_mint).1;
+    let ix = Instruction::new_with_bytes(
+        keys.program,
+        &Deposit {
+            lp_token_amount: quote.lp_amount,
+            maximum_token_0_amount,
+            maximum_token_1_amount,
+        }
+        .data(),
+        keys.liquidity_accounts(owner),
+    );
+    vec![create_lp_ata, ix]
+}
+
+/// Deposits liquidity in a pool in exchange for LP tokens.
+///
+/// # Parameters
+/// * `ctx` - The cluster and payer to use,
+/// * `owner` - Owner of the deposited tokens,
+/// * `keys` - Addresses of the pool,
+/// * `lp_token_amount` - Amount of LP tokens to mint,
+/// * `slippage_bps` - Tolerance on the amounts of tokens deposited, in basis points.
+///
+/// # Errors
+/// If the pool could not be quoted, or the transaction fails.
+#[instrument(skip(ctx, owner))]
+pub async fn deposit(
+    ctx: &Context,
+    owner: &Keypair,
+    keys: &PoolKeys,
+    lp_token_amount: u64,
+    slippage_bps: u16,
+) -> Result<Signature> {
+    let quote = keys
+        .fetch_constant_product(ctx)
+        .await?
+        .deposit_lp(lp_token_amount)?;
+    let instructions = deposit_instructions(&owner.pubkey(), keys, &quote, slippage_bps);
+    let sig = execute_instructions(ctx, &instructions, &[owner]).await?;
+    info!("Deposited liquidity: {sig}");
+
+    Ok(sig)
+}
+
+/// Builds the instruction burning LP tokens to withdraw liquidity from a pool.
+///
+/// # Parameters
+/// * `owner` - Owner of the LP tokens,
+/// * `keys` - Addresses of the pool,
+/// * `lp_token_amount` - Amount of LP tokens to burn,
+/// * `amounts` - The expected amounts of both tokens received,
+/// * `slippage_bps` - Tolerance on the amounts of tokens received, in basis points.
+pub fn withdraw_instruction(
+    owner: &Pubkey,
+    keys: &PoolKeys,
+    lp_token_amount: u64,
+    (amount_0, amount_1): (u64, u64),
+    slippage_bps: u16,
+) -> Instruction {
+    let minimum_token_0_amount = less_slippage(amount_0, slippage_bps);
+    let minimum_token_1_amount = less_slippage(amount_1, slippage_bps);
+    debug!(%amount_0, %amount_1, %minimum_token_0_amount, %minimum_token_1_amount, "Withdrawal quoted");
+
+    let mut accounts = keys.liquidity_accounts(owner);
+    accounts.push(AccountMeta::new_readonly(MEMO_PROGRAM_ID, false));
+    Instruction::new_with_bytes(
+        keys.program,
+        &Withdraw {
+            lp_token_amount,
+            minimum_token_0_amount,
+            minimum_token_1_amount,
+        }
+        .data(),
+        accounts,
+    )
+}
+
+/// Burns LP tokens to withdraw liquidity from a pool.
+///
+/// # Parameters
+/// * `ctx` - The cluster and payer to use,
+/// * `owner` - Owner of the LP tokens,
+/// * `keys` - Addresses of the pool,
+/// * `lp_token_amount` - Amount of LP tokens to burn,
+/// * `slippage_bps` - Tolerance on the amounts of tokens received, in basis points.
+///
+/// # Errors
+/// If the pool could not be quoted, or the transaction fails.
+#[instrument(skip(ctx, owner))]
+pub async fn withdraw(
+    ctx: &Context,
+    owner: &Keypair,
+    keys: &PoolKeys,
+    lp_token_amount: u64,
+    slippage_bps: u16,
+) -> Result<Signature> {
+    let amounts = keys
+        .fetch_constant_product(ctx)
+        .await?
+        .withdraw(lp_token_amount)?;
+    let ix = withdraw_instruction(
+        &owner.pubkey(),
+        keys,
+        lp_token_amount,
+        amounts,
+        slippage_bps,
+    );
+    let sig = execute_instructions(ctx, &[ix], &[owner]).await?;
+    info!("Withdrew liquidity: {sig}");
+
+    Ok(sig)
+}
+
+/// Builds the instructions swapping an exact amount of tokens, creating the account receiving
+/// the output if needed.
+///
+/// # Parameters
+/// * `owner` - Owner of the swapped tokens,
+/// * `keys` - Addresses of the pool,
+/// * `input_mint` - Mint of the tokens sold,
+/// * `amount_in` - Amount of tokens sold,
+/// * `minimum_amount_out` - Minimum amount of tokens to receive.
+///
+/// # Errors
+/// If `input_mint` is not a mint of the pool.
+#[expect(clippy::result_large_err)]
+pub fn swap_base_input_instructions(
+    owner: &Pubkey,
+    keys: &PoolKeys,
+    input_mint: Pubkey,
+    amount_in: u64,
+    minimum_amount_out: u64,
+) -> Result<Vec<Instruction>> {
+    let ix = Instruction::new_with_bytes(
+        keys.program,
+        &SwapBaseInput {
+            amount_in,
+            minimum_amount_out,
+        }
+        .data(),
+        keys.swap_accounts(owner, &input_mint)?,
+    );
+    Ok(vec![create_output_ata(owner, keys, &input_mint), ix])
+}
+
+/// Swaps an exact amount of tokens for as many tokens of the other mint as possible.
+///
+/// # Parameters
+/// * `ctx` - The cluster and payer to use,
+/// * `owner` - Owner of the swapped tokens,
+/// * `keys` - Addresses of the pool,
+/// * `input_mint` - Mint of the tokens sold,
+/// * `amount_in` - Amount of tokens sold,
+/// * `slippage_bps` - Tolerance on the amount of tokens received, in basis points.
+///
+/// # Errors
+/// If `input_mint` is not a mint of the pool, the pool could not be quoted, or the transaction
+/// fails.
+#[instrument(skip(ctx, owner))]
+pub async fn swap_base_input(
+    ctx: &Context,
+    owner: &Keypair,
+    keys: &PoolKeys,
+    input_mint: Pubkey,
+    amount_in: u64,
+    slippage_bps: u16,
+) -> Result<Signature> {
+    let quote = keys
+        .fetch_constant_product(ctx)
+        .await?
+        .swap_base_input(keys.zero_for_one(&input_mint)?, amount_in)?;
+    let minimum_amount_out = quote.minimum_amount_out(slippage_bps);
+    info!(%minimum_amount_out, "Swap quoted: {quote}");
+
+    let instructions = swap_base_input_instructions(
+        &owner.pubkey(),
+        keys,
+        input_mint,
+        amount_in,
+        minimum_amount_out,
+    )?;
+    let sig = execute_instructions(ctx, &instructions, &[owner]).await?;
+    info!("Swapped: {sig}");
+
+    Ok(sig)
+}
+
+/// Swaps as few tokens as possible for an exact amount of tokens of the other mint.
+///
+/// # Parameters
+/// * `ctx` - The cluster and payer to use,
+/// * `owner` - Owner of the swapped tokens,
+/// * `keys` - Addresses of the pool,
+/// * `input_mint` - Mint of the tokens sold,
+/// * `amount_out` - Amount of tokens to receive,
+/// * `slippage_bps` - Tolerance on the amount of tokens sold, in basis points.
+///
+/// # Errors
+/// If `input_mint` is not a mint of the pool, the pool could not be quoted, or the transaction
+/// fails.
+#[instrument(skip(ctx, owner))]
+pub async fn swap_base_output(
+    ctx: &Context,
+    owner: &Keypair,
+    keys: &PoolKeys,
+    input_mint: Pubkey,
+    amount_out: u64,
+    slippage_bps: u16,
+) -> Result<Signature> {
+    let quote = keys
+        .fetch_constant_product(ctx)
+        .await?
+        .swap_base_output(keys.zero_for_one(&input_mint)?, amount_out)?;
+    let max_amount_in = quote.maximum_amount_in(slippage_bps);
+    info!(%max_amount_in, "Swap quoted: {quote}");
+
+    let ix = Instruction::new_with_bytes(
+        keys.program,
+        &SwapBaseOutput {
+            max_amount_in,
+            amount_out,
+        }
+        .data(),
+        keys.swap_accounts(&owner.pubkey(), &input_mint)?,
+    );
+    let sig = execute_instructions(
+        ctx,
+        &[create_output_ata(&owner.pubkey(), keys, &input_mint), ix],
+        &[owner],
+    )
+    .await?;
+    info!("Swapped: {sig}");
+
+    Ok(sig)
+}
+
+/// The instruction creating the account receiving the output of a swap, if needed.
+fn create_output_ata(owner: &Pubkey, keys: &PoolKeys, input_mint: &Pubkey) -> Instruction {
+    let output_mint = if *input_mint == keys.mint_0 {
+        keys.mint_1
+    } else {
+        keys.mint_0
+    };
+    create_ata(owner, owner, &output_mint).1
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+
+    use test_log::test;
+
+    use super::*;
+    use crate::config::{BSOL_MINT, WSOL_MINT, profile::DEVNET_CPMM_PROGRAM};
+    type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
+
+    #[test]
+    fn mints_are_sorted() {
+        // Given
+        let config = pda::amm_config(&DEVNET_CPMM_PROGRAM, 0);
+
+        // When
+        let keys = PoolKeys::new(DEVNET_CPMM_PROGRAM, config, WSOL_MINT, BSOL_MINT);
+        let swapped = PoolKeys::new(DEVNET_CPMM_PROGRAM, config, BSOL_MINT, WSOL_MINT);
+
+        // Then
+        assert_eq!(keys, swapped, "the order of the mints does not matter");
+        assert!(keys.mint_0 < keys.mint_1, "mint 0 is the lowest");
+    }
+
+    #[test]
+    fn swap_direction() -> TestResult {
+        // Given
+        let owner = Pubkey::new_unique();
+        let keys = PoolKeys::new(
+            DEVNET_CPMM_PROGRAM,
+            pda::amm_config(&DEVNET_CPMM_PROGRAM, 0),
+            WSOL_MINT,
+            BSOL_MINT,
+        );
+
+        // When
+        let zero_for_one = keys.swap_accounts(&owner, &keys.mint_0)?;
+        let one_for_zero = keys.swap_accounts(&owner, &keys.mint_1)?;
+        let res = keys.swap_accounts(&owner, &Pubkey::new_unique());
+
+        // Then
+        assert_eq!(zero_for_one[6].pubkey, keys.vault_0, "input vault");
+        assert_eq!(zero_for_one[7].pubkey, keys.vault_1, "output vault");
+        assert_eq!(one_for_zero[6].pubkey, keys.vault_1, "input vault");
+        assert_eq!(one_for_zero[10].pubkey, keys.mint_1, "input mint");
+        assert!(res.is_err(), "the input mint must belong to the pool");
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use solana_sdk::pubkey::Pubkey;
+
+const AUTH_SEED: &[u8] = b"vault_and_lp_mint_auth_seed";
+const AMM_CONFIG_SEED: &[u8] = b"amm_config";
+const POOL_SEED: &[u8] = b"pool";
+const POOL_VAULT_SEED: &[u8] = b"pool_vault";
+const POOL_LP_MINT_SEED: &[u8] = b"pool_lp_mint";
+const OBSERVATION_SEED: &[u8] = b"observation";
+
+fn find(program_id: &Pubkey, seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, program_id).0
+}
+
+/// The authority owning the vaults and LP mints of all the pools.
+pub fn authority(program_id: &Pubkey) -> Pubkey {
+    find(program_id, &[AUTH_SEED])
+}
+
+/// The fee configuration with the given index.
+#[expect(clippy::big_endian_bytes)]
+pub fn amm_config(program_id: &Pubkey, index: u16) -> Pubkey {
+    // The program uses the big endian representation of the index as seed.
+    find(program_id, &[AMM_CONFIG_SEED, &index.to_be_bytes()])
+}
+
+/// The state of the pool of two (sorted) mints using a fee configuration.
+pub fn pool(program_id: &Pubkey, amm_config: &Pubkey, mint_0: &Pubkey, mint_1: &Pubkey) -> Pubkey {
+    find(
+        program_id,
+        &[
+            POOL_SEED,
+            amm_config.as_ref(),
+            mint_0.as_ref(),
+            mint_1.as_ref(),
+        ],
+    )
+}
+
+/// The vault holding the tokens of a mint for a pool.
+pub fn vault(program_id: &Pubkey, pool: &Pubkey, mint: &Pubkey) -> Pubkey {
+    find(program_id, &[POOL_VAULT_SEED, pool.as_ref(), mint.as_ref()])
+}
+
+/// The mint of the LP tokens of a pool.
+pub fn lp_mint(program_id: &Pubkey, pool: &Pubkey) -> Pubkey {
+    find(program_id, &[POOL_LP_MINT_SEED, pool.as_ref()])
+}
+
+/// The price observations (oracle) of a pool.
+pub fn observation(program_id: &Pubkey, pool: &Pubkey) -> Pubkey {
+    find(program_id, &[OBSERVATION_SEED, pool.as_ref()])
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+
+    use solana_sdk::pubkey;
+    use test_log::test;
+
+    use super::*;
+
+    // The CPMM program on mainnet, whose accounts are well known.
+    const MAINNET_PROGRAM_ID: Pubkey = pubkey!("CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C");
+
+    #[test]
+    fn mainnet_authority() {
+        // Given
+        const AUTHORITY: Pubkey = pubkey!("GpMZbSM2GgvTKHJirzeGfMFoaZ8UR2X7F4v8vHTvxFbL");
+
+        // When
+        let derived = authority(&MAINNET_PROGRAM_ID);
+
+        // Then
+        assert_eq!(derived, AUTHORITY);
+    }
+
+    #[test]
+    fn mainnet_amm_config() {
+        // Given
+        const CONFIG: Pubkey = pubkey!("D4FPEruKEHrG5TenZ2mpDGEfu1iUvTiqBxvpU8HLBvC2");
+
+        // When
+        let derived = amm_config(&MAINNET_PROGRAM_ID, 0);
+
+        // Then
+        assert_eq!(derived, CONFIG);
+    }
+
+    #[test]
+    fn pool_accounts_are_distinct() {
+        // Given
+        let config = amm_config(&MAINNET_PROGRAM_ID, 0);
+        let (mint_0, mint_1) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        // When
+        let sorted = pool(&MAINNET_PROGRAM_ID, &config, &mint_0, &mint_1);
+        let swapped = pool(&MAINNET_PROGRAM_ID, &config, &mint_1, &mint_0);
+
+        // Then
+        assert_ne!(sorted, swapped, "the order of the mints matters");
+        assert_ne!(
+            vault(&MAINNET_PROGRAM_ID, &sorted, &mint_0),
+            vault(&MAINNET_PROGRAM_ID, &sorted, &mint_1),
+            "each mint has its vault"
+        );
+        assert_ne!(
+            lp_mint(&MAINNET_PROGRAM_ID, &sorted),
+            observation(&MAINNET_PROGRAM_ID, &sorted),
+            "seeds are prefixed"
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use core::fmt;
+
+use anchor_client::anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::raydium::{
+    quote::{ConstantProduct, Fees},
+    try_deserialize, try_deserialize_unchecked,
+};
+
+/// Denominator of the fee rates of a fee configuration.
+pub const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+/// Bit of the pool status disabling deposits.
+const STATUS_DEPOSIT_DISABLED: u8 = 1;
+/// Bit of the pool status disabling withdrawals.
+const STATUS_WITHDRAW_DISABLED: u8 = 1 << 1;
+/// Bit of the pool status disabling swaps.
+const STATUS_SWAP_DISABLED: u8 = 1 << 2;
+
+/// The state of a CPMM pool.
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[expect(dead_code)]
+pub struct PoolState {
+    /// The fee configuration of the pool.
+    pub amm_config: Pubkey,
+    pub pool_creator: Pubkey,
+    pub token_0_vault: Pubkey,
+    pub token_1_vault: Pubkey,
+    pub lp_mint: Pubkey,
+    pub token_0_mint: Pubkey,
+    pub token_1_mint: Pubkey,
+    pub token_0_program: Pubkey,
+    pub token_1_program: Pubkey,
+    pub observation_key: Pubkey,
+    pub auth_bump: u8,
+    /// Operations disabled on the pool (bit 0: deposit, 1: withdraw, 2: swap).
+    pub status: u8,
+    pub lp_mint_decimals: u8,
+    pub mint_0_decimals: u8,
+    pub mint_1_decimals: u8,
+    /// LP tokens in circulation.
+    pub lp_supply: u64,
+    /// Protocol fees held in the vaults, not part of the liquidity.
+    pub protocol_fees_token_0: u64,
+    pub protocol_fees_token_1: u64,
+    /// Fund fees held in the vaults, not part of the liquidity.
+    pub fund_fees_token_0: u64,
+    pub fund_fees_token_1: u64,
+    /// Timestamp from which the pool accepts swaps.
+    pub open_time: u64,
+    pub recent_epoch: u64,
+    pub padding: [u64; 31],
+}
+
+impl PoolState {
+    /// Whether an operation (one of the `STATUS_*` bits) is enabled.
+    const fn enabled(&self, bit: u8) -> bool {
+        self.status & bit == 0
+    }
+
+    /// The pool as a constant product, to quote its operations.
+    ///
+    /// # Parameters
+    /// * `config` - The fee configuration of the pool,
+    /// * `vault_0` - Balance of the vault of the first token,
+    /// * `vault_1` - Balance of the vault of the second token.
+    pub fn constant_product(
+        &self,
+        config: &AmmConfig,
+        vault_0: u64,
+        vault_1: u64,
+    ) -> ConstantProduct {
+        // The fees owed to the protocol and the fund are not part of the liquidity
+        let reserve = |vault: u64, protocol: u64, fund: u64| {
+            vault.saturating_sub(protocol).saturating_sub(fund)
+        };
+        ConstantProduct {
+            reserve_0: reserve(vault_0, self.protocol_fees_token_0, self.fund_fees_token_0),
+            reserve_1: reserve(vault_1, self.protocol_fees_token_1, self.fund_fees_token_1),
+            lp_supply: self.lp_supply,
+            fees: Fees {
+                trade_fee_numerator: config.trade_fee_rate,
+                trade_fee_denominator: FEE_RATE_DENOMINATOR,
+                protocol_share_numerator: config.protocol_fee_rate + config.fund_fee_rate,
+                protocol_share_denominator: FEE_RATE_DENOMINATOR,
+            },
+        }
+    }
+}
+
+impl Discriminator for PoolState {
+    const DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+}
+
+impl AccountDeserialize for PoolState {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_client::anchor_lang::Result<Self> {
+        try_deserialize(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_client::anchor_lang::Result<Self> {
+        try_deserialize_unchecked(buf)
+    }
+}
+
+impl fmt::Display for PoolState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "CPMM pool (config {})", self.amm_config)?;
+        writeln!(
+            f,
+            "  token 0: {} ({} decimals), vault {}",
+            self.token_0_mint, self.mint_0_decimals, self.token_0_vault
+        )?;
+        writeln!(
+            f,
+            "  token 1: {} ({} decimals), vault {}",
+            self.token_1_mint, self.mint_1_decimals, self.token_1_vault
+        )?;
+        writeln!(
+            f,
+            "  LP mint: {} ({} decimals), supply {}",
+            self.lp_mint, self.lp_mint_decimals, self.lp_supply
+        )?;
+        writeln!(
+            f,
+            "  fees held: protocol {} / {}, fund {} / {}",
+            self.protocol_fees_token_0,
+            self.protocol_fees_token_1,
+            self.fund_fees_token_0,
+            self.fund_fees_token_1
+        )?;
+        write!(
+            f,
+            "  deposit: {}, withdraw: {}, swap: {}, open since {}",
+            self.enabled(STATUS_DEPOSIT_DISABLED),
+            self.enabled(STATUS_WITHDRAW_DISABLED),
+            self.enabled(STATUS_SWAP_DISABLED),
+            self.open_time
+        )
+    }
+}
+
+/// A fee configuration shared by CPMM pools.
+#[derive(Debug, Clone, AnchorDeserialize)]
+#[expect(dead_code)]
+pub struct AmmConfig {
+    pub bump: u8,
+    pub disable_create_pool: bool,
+    pub index: u16,
+    /// Fee taken on swaps, out of [`FEE_RATE_DENOMINATOR`].
+    pub trade_fee_rate: u64,
+    /// Share of the trade fee going to the protocol, out of [`FEE_RATE_DENOMINATOR`].
+    pub protocol_fee_rate: u64,
+    /// Share of the trade fee going to the fund, out of [`FEE_RATE_DENOMINATOR`].
+    pub fund_fee_rate: u64,
+    /// Lamports paid to create a pool.
+    pub create_pool_fee: u64,
+    pub protocol_owner: Pubkey,
+    pub fund_owner: Pubkey,
+    pub padding: [u64; 16],
+}
+
+impl Discriminator for AmmConfig {
+    const DISCRIMINATOR: [u8; 8] = [218, 244, 33, 104, 203, 203, 43, 111];
+}
+
+impl AccountDeserialize for AmmConfig {
+    fn try_deserialize(buf: &mut &[u8]) -> anchor_client::anchor_lang::Result<Self> {
+        try_deserialize(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_client::anchor_lang::Result<Self> {
+        try_deserialize_unchecked(buf)
+    }
+}
+
+impl fmt::Display for AmmConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "CPMM config #{}: trade fee {}, protocol {}, fund {} (per {FEE_RATE_DENOMINATOR}), creation fee {} lamports{}",
+            self.index,
+            self.trade_fee_rate,
+            self.protocol_fee_rate,
+            self.fund_fee_rate,
+            self.create_pool_fee,
+            if self.disable_create_pool {
+                ", pool creation disabled"
+            } else {
+                ""
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+
+    use test_log::test;
+
+    use super::*;
+    use crate::klend::state::decode;
+    type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
+
+    /// Size of a pool state account, discriminator included.
+    const POOL_STATE_LEN: usize = 637;
+
+    #[test]
+    fn decode_pool_state() -> TestResult {
+        // Given
+        let address = Pubkey::new_unique();
+        let lp_mint = Pubkey::new_unique();
+        let mut data = PoolState::DISCRIMINATOR.to_vec();
+        data.resize(POOL_STATE_LEN, 0);
+        // lp_mint is the 5th key, after the 8 bytes of the discriminator
+        data[8 + 4 * 32..8 + 5 * 32].copy_from_slice(lp_mint.as_ref());
+        // status, right after the 10 keys and the auth bump
+        data[8 + 10 * 32 + 1] = STATUS_SWAP_DISABLED;
+
+        // When
+        let pool: PoolState = decode(&address, &data)?;
+
+        // Then
+        assert_eq!(pool.lp_mint, lp_mint);
+        assert!(pool.enabled(STATUS_DEPOSIT_DISABLED), "deposits are open");
+        assert!(!pool.enabled(STATUS_SWAP_DISABLED), "swaps are disabled");
+        Ok(())
+    }
+
+    #[test]
+    #[expect(clippy::little_endian_bytes)]
+    fn reserves_exclude_fees() -> TestResult {
+        // Given
+        let address = Pubkey::new_unique();
+        let mut data = PoolState::DISCRIMINATOR.to_vec();
+        data.resize(POOL_STATE_LEN, 0);
+        // lp supply, then the protocol fees, after the 10 keys and 5 bytes
+        let supply = 8 + 10 * 32 + 5;
+        data[supply..supply + 8].copy_from_slice(&1_000_u64.to_le_bytes());
+        data[supply + 8..supply + 16].copy_from_slice(&30_u64.to_le_bytes());
+        let mut config = AmmConfig::DISCRIMINATOR.to_vec();
+        config.resize(8 + 4 + 8 * 5 + 32 * 2 + 8 * 16, 0);
+        // the trade fee rate, after the bump, flag and index
+        config[8 + 4..8 + 12].copy_from_slice(&2_500_u64.to_le_bytes());
+
+        // When
+        let pool: PoolState = decode(&address, &data)?;
+        let config: AmmConfig = decode(&address, &config)?;
+        let product = pool.constant_product(&config, 2_030, 4_000);
+
+        // Then
+        assert_eq!(product.reserve_0, 2_000, "protocol fees are excluded");
+        assert_eq!(product.reserve_1, 4_000);
+        assert_eq!(product.lp_supply, 1_000);
+        assert_eq!(product.fees.trade_fee_numerator, 2_500);
+        Ok(())
+    }
+
+    #[test]
+    fn reject_other_accounts() {
+        // Given
+        let address = Pubkey::new_unique();
+        let mut data = AmmConfig::DISCRIMINATOR.to_vec();
+        data.resize(POOL_STATE_LEN, 0);
+
+        // When
+        let res = decode::<PoolState>(&address, &data);
+
+        // Then
+        assert!(res.is_err(), "a config is not a pool");
+    }
+}
@@ -0,0 +1,28 @@
+use anchor_client::anchor_lang::{AnchorDeserialize, Discriminator, error::ErrorCode};
+
+pub mod amm_v4;
+pub mod clmm;
+pub mod cpmm;
+pub mod quote;
+
+/// Deserializes a Raydium account, checking its discriminator.
+fn try_deserialize<T: AnchorDeserialize + Discriminator>(
+    buf: &[u8],
+) -> anchor_client::anchor_lang::Result<T> {
+    if !buf.starts_with(&T::DISCRIMINATOR) {
+        return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+    }
+    try_deserialize_unchecked(buf)
+}
+
+/// Deserializes a Raydium account, skipping its discriminator.
+///
+/// The `zero_copy` (packed) accounts have the same layout as their borsh encoding.
+fn try_deserialize_unchecked<T: AnchorDeserialize>(
+    buf: &[u8],
+) -> anchor_client::anchor_lang::Result<T> {
+    let mut data = buf
+        .get(8..)
+        .ok_or(ErrorCode::AccountDiscriminatorNotFound)?;
+    T::deserialize(&mut data).map_err(|_err| ErrorCode::AccountDidNotDeserialize.into())
+}
@@ -0,0 +1,499 @@
+use core::fmt;
+
+use crate::error::{Error, Result};
+
+/// Denominator of the slippage tolerances, in basis points.
+const BPS_DENOMINATOR: u64 = 10_000;
+
+/// The fees of a constant product pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fees {
+    /// Fee taken on the input of a swap, as a fraction.
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+    /// Share of the trade fee going to the protocol rather than the liquidity providers.
+    pub protocol_share_numerator: u64,
+    pub protocol_share_denominator: u64,
+}
+
+/// A constant product pool, as needed to quote its operations.
+///
+/// The reserves exclude the fees owed to the protocol that are still in the vaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConstantProduct {
+    /// Amount of the first token backing the liquidity.
+    pub reserve_0: u64,
+    /// Amount of the second token backing the liquidity.
+    pub reserve_1: u64,
+    /// LP tokens in circulation.
+    pub lp_supply: u64,
+    pub fees: Fees,
+}
+
+/// The expected result of a swap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SwapQuote {
+    /// Amount of tokens sold, fees included.
+    pub amount_in: u64,
+    /// Amount of tokens bought.
+    pub amount_out: u64,
+    /// Fee taken on the tokens sold.
+    pub trade_fee: u64,
+    /// Part of the trade fee going to the protocol.
+    pub protocol_fee: u64,
+    /// Relative loss of the swap compared to the spot price of the pool, fee included.
+    pub price_impact: f64,
+}
+
+impl SwapQuote {
+    /// The minimum amount of tokens to accept with a slippage tolerance.
+    pub fn minimum_amount_out(&self, slippage_bps: u16) -> u64 {
+        less_slippage(self.amount_out, slippage_bps)
+    }
+
+    /// The maximum amount of tokens to sell with a slippage tolerance.
+    pub fn maximum_amount_in(&self, slippage_bps: u16) -> u64 {
+        plus_slippage(self.amount_in, slippage_bps)
+    }
+}
+
+impl fmt::Display for SwapQuote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} in for {} out (trade fee {}, of which protocol {}), price impact {:.4}%",
+            self.amount_in,
+            self.amount_out,
+            self.trade_fee,
+            self.protocol_fee,
+            self.price_impact * 100.0
+        )
+    }
+}
+
+/// The expected amounts of a deposit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepositQuote {
+    /// Amount of the first token deposited.
+    pub amount_0: u64,
+    /// Amount of the second token deposited.
+    pub amount_1: u64,
+    /// LP tokens minted.
+    pub lp_amount: u64,
+}
+
+impl ConstantProduct {
+    /// Quotes selling an exact amount of tokens.
+    ///
+    /// # Parameters
+    /// * `zero_for_one` - Whether the first token is sold for the second one,
+    /// * `amount_in` - Amount of tokens sold, fees included.
+    ///
+    /// # Errors
+    /// If the pool is empty.
+    #[expect(clippy::result_large_err)]
+    pub fn swap_base_input(&self, zero_for_one: bool, amount_in: u64) -> Result<SwapQuote> {
+        let (reserve_in, reserve_out) = self.reserves(zero_for_one)?;
+        let trade_fee = mul_div_ceil(
+            amount_in,
+            self.fees.trade_fee_numerator,
+            u128::from(self.fees.trade_fee_denominator),
+        )?;
+        let amount_in_less_fee = amount_in.saturating_sub(trade_fee);
+        // dy = y * dx / (x + dx), rounded down as the pool does
+        let amount_out = mul_div_floor(
+            amount_in_less_fee,
+            reserve_out,
+            u128::from(reserve_in) + u128::from(amount_in_less_fee),
+        )?;
+
+        self.quote(reserve_in, reserve_out, amount_in, amount_out, trade_fee)
+    }
+
+    /// Quotes buying an exact amount of tokens.
+    ///
+    /// # Parameters
+    /// * `zero_for_one` - Whether the first token is sold for the second one,
+    /// * `amount_out` - Amount of tokens bought.
+    ///
+    /// # Errors
+    /// If the pool is empty or does not hold enough tokens.
+    #[expect(clippy::result_large_err)]
+    pub fn swap_base_output(&self, zero_for_one: bool, amount_out: u64) -> Result<SwapQuote> {
+        let (reserve_in, reserve_out) = self.reserves(zero_for_one)?;
+        if amount_out >= reserve_out {
+            return Err(Error::Math(format!(
+                "cannot buy {amount_out} tokens from a reserve of {reserve_out}"
+            )));
+        }
+        // dx = x * dy / (y - dy), rounded up as the pool does
+        let amount_in_less_fee =
+            mul_div_ceil(amount_out, reserve_in, u128::from(reserve_out - amount_out))?;
+        // The fee is taken on the whole input: dx_with_fee = dx / (1 - fee)
+        let amount_in = mul_div_ceil(
+            amount_in_less_fee,
+            self.fees.trade_fee_denominator,
+            u128::from(
+                self.fees
+                    .trade_fee_denominator
+                    .saturating_sub(self.fees.trade_fee_numerator),
+            ),
+        )?;
+
+        self.quote(
+            reserve_in,
+            reserve_out,
+            amount_in,
+            amount_out,
+            amount_in - amount_in_less_fee,
+        )
+    }
+
+    /// Quotes the tokens to deposit to mint an amount of LP tokens.
+    ///
+    /// # Errors
+    /// If the pool has no liquidity.
+    #[expect(clippy::result_large_err)]
+    pub fn deposit_lp(&self, lp_amount: u64) -> Result<DepositQuote> {
+        let supply = u128::from(self.lp_supply);
+        Ok(DepositQuote {
+            amount_0: mul_div_ceil(lp_amount, self.reserve_0, supply)?,
+            amount_1: mul_div_ceil(lp_amount, self.reserve_1, supply)?,
+            lp_amount,
+        })
+    }
+
+    /// Quotes the largest deposit possible with amounts of both tokens.
+    ///
+    /// # Errors
+    /// If the pool has no liquidity.
+    #[expect(clippy::result_large_err)]
+    pub fn deposit_amounts(&self, amount_0: u64, amount_1: u64) -> Result<DepositQuote> {
+        let lp_amount = u64::min(
+            mul_div_floor(amount_0, self.lp_supply, u128::from(self.reserve_0))?,
+            mul_div_floor(amount_1, self.lp_supply, u128::from(self.reserve_1))?,
+        );
+        self.deposit_lp(lp_amount)
+    }
+
+    /// Quotes a deposit of an exact amount of one of the tokens, the other one being added in
+    /// proportion.
+    ///
+    /// # Parameters
+    /// * `base_is_0` - Whether the exact amount is of the first token,
+    /// * `amount` - The exact amount deposited.
+    ///
+    /// # Errors
+    /// If the pool has no liquidity.
+    #[expect(clippy::result_large_err)]
+    pub fn deposit_base(&self, base_is_0: bool, amount: u64) -> Result<DepositQuote> {
+        let (base_reserve, other_reserve) = if base_is_0 {
+            (self.reserve_0, self.reserve_1)
+        } else {
+            (self.reserve_1, self.reserve_0)
+        };
+        let other = mul_div_ceil(amount, other_reserve, u128::from(base_reserve))?;
+        let lp_amount = mul_div_floor(amount, self.lp_supply, u128::from(base_reserve))?;
+        let (amount_0, amount_1) = if base_is_0 {
+            (amount, other)
+        } else {
+            (other, amount)
+        };
+
+        Ok(DepositQuote {
+            amount_0,
+            amount_1,
+            lp_amount,
+        })
+    }
+
+    /// Quotes the tokens received for burning LP tokens.
+    ///
+    /// # Errors
+    /// If the pool has no liquidity.
+    #[expect(clippy::result_large_err)]
+    pub fn withdraw(&self, lp_amount: u64) -> Result<(u64, u64)> {
+        let supply = u128::from(self.lp_supply);
+        Ok((
+            mul_div_floor(lp_amount, self.reserve_0, supply)?,
+            mul_div_floor(lp_amount, self.reserve_1, supply)?,
+        ))
+    }
+
+    /// The reserves of the input and output tokens of a swap.
+    #[expect(clippy::result_large_err)]
+    fn reserves(&self, zero_for_one: bool) -> Result<(u64, u64)> {
+        if self.reserve_0 == 0 || self.reserve_1 == 0 {
+            return Err(Error::Math("the pool is empty".to_owned()));
+        }
+        Ok(if zero_for_one {
+            (self.reserve_0, self.reserve_1)
+        } else {
+            (self.reserve_1, self.reserve_0)
+        })
+    }
+
+    #[expect(clippy::result_large_err)]
+    #[expect(clippy::cast_precision_loss)]
+    fn quote(
+        &self,
+        reserve_in: u64,
+        reserve_out: u64,
+        amount_in: u64,
+        amount_out: u64,
+        trade_fee: u64,
+    ) -> Result<SwapQuote> {
+        let protocol_fee = mul_div_floor(
+            trade_fee,
+            self.fees.protocol_share_numerator,
+            u128::from(self.fees.protocol_share_denominator),
+        )?;
+        // 1 - (dy / dx) / (y / x)
+        let price_impact = if amount_in == 0 {
+            0.0_f64
+        } else {
+            1.0_f64
+                - (amount_out as f64 * reserve_in as f64) / (amount_in as f64 * reserve_out as f64)
+        };
+
+        Ok(SwapQuote {
+            amount_in,
+            amount_out,
+            trade_fee,
+            protocol_fee,
+            price_impact,
+        })
+    }
+}
+
+/// An amount decreased by a slippage tolerance, rounded down.
+pub fn less_slippage(amount: u64, slippage_bps: u16) -> u64 {
+    let kept = BPS_DENOMINATOR.saturating_sub(u64::from(slippage_bps));
+    mul_div_floor(amount, kept, u128::from(BPS_DENOMINATOR)).unwrap_or_default()
+}
+
+/// An amount increased by a slippage tolerance, rounded up.
+pub fn plus_slippage(amount: u64, slippage_bps: u16) -> u64 {
+    let allowed = BPS_DENOMINATOR + u64::from(slippage_bps);
+    mul_div_ceil(amount, allowed, u128::from(BPS_DENOMINATOR)).unwrap_or(u64::MAX)
+}
+
+/// `a * b / denominator`, rounded down.
+#[expect(clippy::result_large_err)]
+fn mul_div_floor(a: u64, b: u64, denominator: u128) -> Result<u64> {
+    let product = u128::from(a) * u128::from(b);
+    to_u64(product.checked_div(denominator), denominator)
+}
+
+/// `a * b / denominator`, rounded up.
+#[expect(clippy::result_large_err)]
+fn mul_div_ceil(a: u64, b: u64, denominator: u128) -> Result<u64> {
+    let product = u128::from(a) * u128::from(b);
+    to_u64(
+        (denominator != 0).then(|| product.div_ceil(denominator)),
+        denominator,
+    )
+}
+
+#[expect(clippy::result_large_err)]
+fn to_u64(value: Option<u128>, denominator: u128) -> Result<u64> {
+    let value = value.ok_or_else(|| Error::Math(format!("division by {denominator}")))?;
+    u64::try_from(value).map_err(|_err| Error::Math(format!("{value} overflows a u64")))
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+
+    use proptest::prelude::*;
+    use test_log::test;
+
+    use super::*;
+    type TestResult = core::result::Result<(), Box<dyn core::error::Error>>;
+
+    /// Upper bound of the generated amounts, so that sums do not overflow.
+    const MAX_AMOUNT: u64 = 1 << 62;
+
+    /// A pool with the CPMM 0.25% fee tier, 12% of it going to the protocol.
+    const fn pool(reserve_0: u64, reserve_1: u64, lp_supply: u64) -> ConstantProduct {
+        ConstantProduct {
+            reserve_0,
+            reserve_1,
+            lp_supply,
+            fees: Fees {
+                trade_fee_numerator: 2_500,
+                trade_fee_denominator: 1_000_000,
+                protocol_share_numerator: 120_000,
+                protocol_share_denominator: 1_000_000,
+            },
+        }
+    }
+
+    #[test]
+    fn swap_known_values() -> TestResult {
+        // Given
+        let pool = pool(1_000_000, 2_000_000, 1_000_000);
+
+        // When
+        let quote = pool.swap_base_input(true, 10_000)?;
+
+        // Then
+        // fee: ceil(10_000 * 0.25%) = 25, out: 2_000_000 * 9_975 / 1_009_975 = 19_752.96
+        assert_eq!(quote.trade_fee, 25);
+        assert_eq!(quote.protocol_fee, 3);
+        assert_eq!(quote.amount_out, 19_752);
+        assert!(
+            (quote.price_impact - 0.012_4).abs() < 1e-6,
+            "impact of {}",
+            quote.price_impact
+        );
+        assert_eq!(quote.minimum_amount_out(50), 19_653);
+        Ok(())
+    }
+
+    #[test]
+    fn swap_output_matches_input() -> TestResult {
+        // Given
+        let pool = pool(1_000_000, 2_000_000, 1_000_000);
+
+        // When
+        let quote = pool.swap_base_output(false, 9_752)?;
+        let back = pool.swap_base_input(false, quote.amount_in)?;
+
+        // Then
+        assert!(back.amount_out >= 9_752, "the input buys the output");
+        assert!(
+            pool.swap_base_output(false, 1_000_000).is_err(),
+            "cannot empty the pool"
+        );
+        assert_eq!(
+            quote.maximum_amount_in(100),
+            plus_slippage(quote.amount_in, 100)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn deposits_and_withdrawals() -> TestResult {
+        // Given
+        let pool = pool(1_000_000, 3_000_000, 500_000);
+
+        // When
+        let by_lp = pool.deposit_lp(1_000)?;
+        let by_amounts = pool.deposit_amounts(2_000, 10_000)?;
+        let by_base = pool.deposit_base(false, 6_001)?;
+        let withdrawn = pool.withdraw(1_000)?;
+
+        // Then
+        assert_eq!(
+            by_lp,
+            DepositQuote {
+                amount_0: 2_000,
+                amount_1: 6_000,
+                lp_amount: 1_000
+            }
+        );
+        assert_eq!(by_amounts, by_lp, "the first token is the scarcest");
+        assert_eq!(
+            by_base,
+            DepositQuote {
+                amount_0: 2_001,
+                amount_1: 6_001,
+                lp_amount: 1_000
+            }
+        );
+        assert_eq!(withdrawn, (2_000, 6_000));
+        Ok(())
+    }
+
+    #[test]
+    fn empty_pool() {
+        // Given
+        let pool = pool(0, 0, 0);
+
+        // When
+        let swap = pool.swap_base_input(true, 1_000);
+        let deposit = pool.deposit_lp(1_000);
+
+        // Then
+        assert!(swap.is_err(), "nothing to swap");
+        assert!(deposit.is_err(), "no liquidity");
+    }
+
+    proptest! {
+        #[test]
+        fn swap_never_overestimates(
+            reserve_0 in 1_000..MAX_AMOUNT,
+            reserve_1 in 1_000..MAX_AMOUNT,
+            amount_in in 0..MAX_AMOUNT,
+            zero_for_one: bool,
+        ) {
+            let pool = pool(reserve_0, reserve_1, 1);
+            let quote = pool.swap_base_input(zero_for_one, amount_in)?;
+            let (x, y) = pool.reserves(zero_for_one)?;
+
+            // The pool keeps its invariant: (x + dx) * (y - dy) >= x * y
+            let dx = u128::from(amount_in - quote.trade_fee);
+            let dy = u128::from(quote.amount_out);
+            let (x, y) = (u128::from(x), u128::from(y));
+            prop_assert!((x + dx) * (y - dy) >= x * y, "{quote:?} breaks the invariant");
+            prop_assert!(quote.protocol_fee <= quote.trade_fee);
+        }
+
+        #[test]
+        fn exact_output_is_enough(
+            reserve_0 in 1_000..MAX_AMOUNT,
+            reserve_1 in 1_000..MAX_AMOUNT,
+            amount_out in 1..1_000_u64,
+            zero_for_one: bool,
+        ) {
+            let pool = pool(reserve_0, reserve_1, 1);
+            let quote = pool.swap_base_output(zero_for_one, amount_out)?;
+            let swap = pool.swap_base_input(zero_for_one, quote.amount_in)?;
+
+            prop_assert!(swap.amount_out >= amount_out, "{quote:?} does not buy {amount_out}");
+        }
+
+        #[test]
+        fn liquidity_never_overestimates(
+            reserve_0 in 1..MAX_AMOUNT,
+            reserve_1 in 1..MAX_AMOUNT,
+            lp_supply in 1..MAX_AMOUNT,
+            lp_amount in 0..MAX_AMOUNT,
+        ) {
+            let pool = pool(reserve_0, reserve_1, lp_supply);
+            let (lp_amount, supply) = (u128::from(lp_amount.min(lp_supply)), u128::from(lp_supply));
+            let lp = u64::try_from(lp_amount)?;
+
+            // Withdrawals never pay more than the share of the LP tokens burnt
+            let (amount_0, amount_1) = pool.withdraw(lp)?;
+            prop_assert!(u128::from(amount_0) * supply <= lp_amount * u128::from(reserve_0));
+            prop_assert!(u128::from(amount_1) * supply <= lp_amount * u128::from(reserve_1));
+
+            // Deposits never mint more than the share of the tokens deposited
+            let deposit = pool.deposit_lp(lp)?;
+            prop_assert!(u128::from(deposit.amount_0) * supply >= lp_amount * u128::from(reserve_0));
+            prop_assert!(u128::from(deposit.amount_1) * supply >= lp_amount * u128::from(reserve_1));
+        }
+
+        #[test]
+        fn deposit_fits_the_amounts(
+            reserve_0 in 1..MAX_AMOUNT,
+            reserve_1 in 1..MAX_AMOUNT,
+            lp_supply in 1..MAX_AMOUNT,
+            amount_0 in 0..MAX_AMOUNT,
+            amount_1 in 0..MAX_AMOUNT,
+        ) {
+            let pool = pool(reserve_0, reserve_1, lp_supply);
+            if let Ok(deposit) = pool.deposit_amounts(amount_0, amount_1) {
+                prop_assert!(deposit.amount_0 <= amount_0 && deposit.amount_1 <= amount_1);
+            }
+        }
+
+        #[test]
+        fn slippage_bounds(amount: u64, slippage_bps in 0..=10_000_u16) {
+            prop_assert!(less_slippage(amount, slippage_bps) <= amount);
+            prop_assert!(plus_slippage(amount, slippage_bps) >= amount);
+        }
+    }
+}
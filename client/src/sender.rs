@@ -0,0 +1,343 @@
+use core::{future::Future, time::Duration};
+
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig};
+use solana_sdk::{
+    clock::Slot,
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    signature::Signature,
+    transaction::{self, VersionedTransaction},
+};
+use tracing::{debug, info, instrument, warn};
+
+use crate::{
+    error::{Error, Result},
+    transaction::{process_rpc_error, transaction_error},
+};
+
+/// Time between two broadcasts of a transaction waiting for its confirmation.
+pub const REBROADCAST_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The RPC calls needed to land a transaction.
+///
+/// Implemented by the RPC client, and by scripted senders in the tests.
+pub trait TransactionSender {
+    /// The latest blockhash, with the last block height at which it is valid.
+    fn latest_blockhash(&self) -> impl Future<Output = Result<(Hash, u64)>> + Send;
+
+    /// Sends a transaction to the cluster, simulating it first if `preflight` is set.
+    fn send(
+        &self,
+        trx: &VersionedTransaction,
+        preflight: bool,
+    ) -> impl Future<Output = Result<Signature>> + Send;
+
+    /// The result of a transaction once it is confirmed, `None` before.
+    fn signature_status(
+        &self,
+        sig: &Signature,
+    ) -> impl Future<Output = Result<Option<transaction::Result<()>>>> + Send;
+
+    /// The current block height.
+    fn block_height(&self) -> impl Future<Output = Result<Slot>> + Send;
+}
+
+impl TransactionSender for RpcClient {
+    async fn latest_blockhash(&self) -> Result<(Hash, u64)> {
+        self.get_latest_blockhash_with_commitment(self.commitment())
+            .await
+            .map_err(process_rpc_error)
+    }
+
+    async fn send(&self, trx: &VersionedTransaction, preflight: bool) -> Result<Signature> {
+        self.send_transaction_with_config(
+            trx,
+            RpcSendTransactionConfig {
+                skip_preflight: !preflight,
+                preflight_commitment: Some(self.commitment().commitment),
+                // the transaction is rebroadcast by the client
+                max_retries: Some(0),
+                ..RpcSendTransactionConfig::default()
+            },
+        )
+        .await
+        .map_err(process_rpc_error)
+    }
+
+    async fn signature_status(&self, sig: &Signature) -> Result<Option<transaction::Result<()>>> {
+        self.get_signature_status_with_commitment(sig, CommitmentConfig::confirmed())
+            .await
+            .map_err(process_rpc_error)
+    }
+
+    async fn block_height(&self) -> Result<Slot> {
+        self.get_block_height().await.map_err(process_rpc_error)
+    }
+}
+
+/// Sends a transaction until it is confirmed.
+///
+/// The signed transaction is rebroadcast every `interval` until it is confirmed or its blockhash
+/// expires. It is then signed again with a fresh blockhash, up to `max_retries` times. The
+/// retryable errors (see [`Error::is_retryable`]) lead to a new attempt, the others are returned
+/// immediately.
+///
+/// # Parameters
+/// * `sender` - Sends the transaction,
+/// * `build` - Builds and signs the transaction with a blockhash,
+/// * `max_retries` - Maximum number of times the transaction is signed again,
+/// * `interval` - Time between two broadcasts of the same transaction.
+///
+/// # Errors
+/// If the transaction fails, or is not confirmed after all the attempts.
+#[instrument(skip_all)]
+pub async fn send_with_retries<S, F>(
+    sender: &S,
+    build: F,
+    max_retries: u8,
+    interval: Duration,
+) -> Result<Signature>
+where
+    S: TransactionSender + Sync,
+    F: Fn(Hash) -> Result<VersionedTransaction> + Sync,
+{
+    for attempt in 0..=max_retries {
+        if attempt > 0 {
+            warn!(%attempt, "signing the transaction again with a fresh blockhash");
+        }
+        match send_until_expired(sender, &build, interval).await {
+            Ok(Some(sig)) => return Ok(sig),
+            Ok(None) => warn!("the blockhash of the transaction expired"),
+            Err(err) if err.is_retryable() => warn!(%err, "transaction failed, retrying"),
+            Err(err) => return Err(err),
+        }
+    }
+
+    Err(Error::TransactionExpired(max_retries.saturating_add(1)))
+}
+
+/// Signs a transaction with the latest blockhash and broadcasts it until it is confirmed (its
+/// signature is returned) or its blockhash expires (`None` is returned).
+async fn send_until_expired<S, F>(
+    sender: &S,
+    build: &F,
+    interval: Duration,
+) -> Result<Option<Signature>>
+where
+    S: TransactionSender + Sync,
+    F: Fn(Hash) -> Result<VersionedTransaction> + Sync,
+{
+    let (block, last_valid_block_height) = sender.latest_blockhash().await?;
+    let trx = build(block)?;
+    let sig = sender.send(&trx, true).await?;
+    info!(
+        %last_valid_block_height,
+        "\u{eab2} Transaction {sig} has passed preflight, waiting for confirmation"
+    );
+
+    loop {
+        match sender.signature_status(&sig).await? {
+            Some(Ok(())) => return Ok(Some(sig)),
+            Some(Err(err)) => return Err(transaction_error(err, None)),
+            None => {}
+        }
+        if sender.block_height().await? > last_valid_block_height {
+            return Ok(None);
+        }
+
+        tokio::time::sleep(interval).await;
+        debug!(%sig, "rebroadcasting transaction");
+        match sender.send(&trx, false).await {
+            Ok(_sig) => {}
+            Err(err) if err.is_retryable() => warn!(%err, "could not rebroadcast transaction"),
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+#[expect(clippy::unwrap_used, clippy::unwrap_in_result)]
+mod tests {
+
+    use std::{collections::VecDeque, sync::Mutex};
+
+    use solana_sdk::{
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        system_instruction,
+        transaction::{Transaction, TransactionError},
+    };
+    use test_log::test;
+
+    use super::*;
+
+    /// A sender replaying scripted RPC responses.
+    #[derive(Default)]
+    struct ScriptedSender {
+        blockhashes: Mutex<VecDeque<(Hash, u64)>>,
+        sends: Mutex<VecDeque<Result<()>>>,
+        statuses: Mutex<VecDeque<Option<transaction::Result<()>>>>,
+        heights: Mutex<VecDeque<Slot>>,
+        /// The transactions sent, with whether they were simulated first.
+        sent: Mutex<Vec<(Hash, bool)>>,
+    }
+
+    impl ScriptedSender {
+        fn new(
+            blockhashes: &[(Hash, u64)],
+            sends: Vec<Result<()>>,
+            statuses: &[Option<transaction::Result<()>>],
+            heights: &[Slot],
+        ) -> Self {
+            Self {
+                blockhashes: Mutex::new(blockhashes.iter().copied().collect()),
+                sends: Mutex::new(sends.into()),
+                statuses: Mutex::new(statuses.iter().cloned().collect()),
+                heights: Mutex::new(heights.iter().copied().collect()),
+                sent: Mutex::default(),
+            }
+        }
+
+        fn sent(&self) -> Vec<(Hash, bool)> {
+            self.sent.lock().unwrap().clone()
+        }
+    }
+
+    impl TransactionSender for ScriptedSender {
+        async fn latest_blockhash(&self) -> Result<(Hash, u64)> {
+            Ok(self.blockhashes.lock().unwrap().pop_front().unwrap())
+        }
+
+        async fn send(&self, trx: &VersionedTransaction, preflight: bool) -> Result<Signature> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((*trx.message.recent_blockhash(), preflight));
+            self.sends
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or(Ok(()))
+                .map(|()| trx.signatures[0])
+        }
+
+        async fn signature_status(
+            &self,
+            _sig: &Signature,
+        ) -> Result<Option<transaction::Result<()>>> {
+            Ok(self.statuses.lock().unwrap().pop_front().flatten())
+        }
+
+        async fn block_height(&self) -> Result<Slot> {
+            Ok(self.heights.lock().unwrap().pop_front().unwrap_or_default())
+        }
+    }
+
+    #[expect(clippy::result_large_err)]
+    fn transfer(payer: &Keypair) -> impl Fn(Hash) -> Result<VersionedTransaction> {
+        let instruction = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        move |block| {
+            Ok(Transaction::new_signed_with_payer(
+                core::slice::from_ref(&instruction),
+                Some(&payer.pubkey()),
+                &[payer],
+                block,
+            )
+            .into())
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn rebroadcast_until_confirmed() -> Result<()> {
+        // Given
+        let payer = Keypair::new();
+        let block = Hash::new_unique();
+        let sender =
+            ScriptedSender::new(&[(block, 10)], vec![], &[None, None, Some(Ok(()))], &[8, 9]);
+
+        let build = transfer(&payer);
+
+        // When
+        let sig = send_with_retries(&sender, &build, 2, Duration::ZERO).await?;
+
+        // Then
+        assert_eq!(sig, build(block)?.signatures[0]);
+        assert_eq!(
+            sender.sent(),
+            [(block, true), (block, false), (block, false)],
+            "the same transaction is rebroadcast without preflight"
+        );
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn sign_again_when_expired() -> Result<()> {
+        // Given
+        let payer = Keypair::new();
+        let (expired, fresh) = (Hash::new_unique(), Hash::new_unique());
+        let sender = ScriptedSender::new(
+            &[(expired, 10), (fresh, 20)],
+            vec![Err(Error::SolanaTransaction(
+                TransactionError::BlockhashNotFound,
+            ))],
+            &[None, Some(Ok(()))],
+            &[11],
+        );
+
+        // When
+        let res = send_with_retries(&sender, transfer(&payer), 1, Duration::ZERO).await;
+
+        // Then
+        assert!(res.is_ok(), "{res:?}");
+        assert_eq!(
+            sender.sent(),
+            [(expired, true), (fresh, true), (fresh, false)],
+            "signed again after the blockhash was not found"
+        );
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn give_up_after_retries() {
+        // Given
+        let payer = Keypair::new();
+        let blocks = [(Hash::new_unique(), 10), (Hash::new_unique(), 20)];
+        let sender = ScriptedSender::new(&blocks, vec![], &[], &[11, 21]);
+
+        // When
+        let res = send_with_retries(&sender, transfer(&payer), 1, Duration::ZERO).await;
+
+        // Then
+        assert!(matches!(res, Err(Error::TransactionExpired(2))), "{res:?}");
+        assert_eq!(sender.sent().len(), 2, "one broadcast per blockhash");
+    }
+
+    #[test(tokio::test)]
+    async fn fatal_errors_are_not_retried() {
+        // Given
+        let payer = Keypair::new();
+        let sender = ScriptedSender::new(
+            &[(Hash::new_unique(), 10), (Hash::new_unique(), 20)],
+            vec![Err(Error::SolanaInstruction {
+                error: InstructionError::InsufficientFunds,
+                program_error: None,
+                logs: vec![],
+            })],
+            &[],
+            &[],
+        );
+
+        // When
+        let res = send_with_retries(&sender, transfer(&payer), 3, Duration::ZERO).await;
+
+        // Then
+        assert!(
+            matches!(res, Err(Error::SolanaInstruction { .. })),
+            "{res:?}"
+        );
+        assert_eq!(sender.sent().len(), 1, "not retried");
+    }
+}
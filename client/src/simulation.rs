@@ -0,0 +1,250 @@
+use core::{fmt, str::FromStr as _};
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount, pubkey::Pubkey, signature::Signature,
+    transaction::VersionedTransaction,
+};
+use spl_token_2022::{extension::StateWithExtensions, state};
+use tracing::{info, instrument};
+
+use crate::{
+    error::{Error, Result, program::Programs},
+    transaction::{process_rpc_error, simulate, transaction_error},
+};
+
+/// Maximum number of accounts fetched by a single `getMultipleAccounts` call.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// The balance of a token account before and after a simulated transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenBalance {
+    pub account: Pubkey,
+    pub mint: Pubkey,
+    pub pre: u64,
+    pub post: u64,
+}
+
+/// The outcome of a simulated transaction.
+#[derive(Debug)]
+pub struct Simulation {
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+    /// The token accounts used by the transaction.
+    pub token_balances: Vec<TokenBalance>,
+    /// Why the transaction failed, if it did.
+    pub error: Option<Error>,
+}
+
+impl fmt::Display for Simulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.units_consumed {
+            Some(units) => writeln!(f, "Simulation consumed {units} compute units")?,
+            None => writeln!(f, "Simulation")?,
+        }
+        // the logs of a failed instruction are displayed with its error
+        if !matches!(self.error, Some(Error::SolanaInstruction { .. })) {
+            writeln!(f, "  logs:")?;
+            for log in &self.logs {
+                writeln!(f, "    {log}")?;
+            }
+        }
+        writeln!(f, "  token balances:")?;
+        for balance in &self.token_balances {
+            writeln!(
+                f,
+                "    {} ({}): {} -> {}",
+                balance.account, balance.mint, balance.pre, balance.post
+            )?;
+        }
+        match &self.error {
+            Some(error) => write!(f, "  failed: {error}"),
+            None => write!(f, "  succeeded"),
+        }
+    }
+}
+
+/// Simulates a transaction instead of sending it, and displays its outcome.
+///
+/// The transaction is simulated with the latest blockhash and without checking its signatures.
+///
+/// # Parameters
+/// * `rpc` - The RPC client,
+/// * `trx` - The transaction to simulate,
+/// * `tables` - The lookup tables the transaction loads accounts from,
+/// * `programs` - The programs whose errors are decoded.
+///
+/// # Returns
+/// The signature of the transaction, which is never sent.
+///
+/// # Errors
+/// If the simulation could not be run, or the transaction fails.
+#[instrument(skip_all)]
+pub async fn dry_run(
+    rpc: &RpcClient,
+    trx: &VersionedTransaction,
+    tables: &[AddressLookupTableAccount],
+    programs: &Programs,
+) -> Result<Signature> {
+    let accounts = involved_accounts(trx, tables);
+    let mut pre = Vec::with_capacity(accounts.len());
+    for chunk in accounts.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        pre.extend(
+            rpc.get_multiple_accounts(chunk)
+                .await
+                .map_err(process_rpc_error)?
+                .into_iter()
+                .map(|account| {
+                    account.and_then(|account| token_amount(&account.owner, &account.data))
+                }),
+        );
+    }
+    let result = simulate(rpc, trx, &accounts).await?;
+    let post: Vec<_> = result
+        .accounts
+        .unwrap_or_default()
+        .into_iter()
+        .map(|account| {
+            let account = account?;
+            token_amount(
+                &Pubkey::from_str(&account.owner).ok()?,
+                &account.data.decode()?,
+            )
+        })
+        .collect();
+
+    let logs = result.logs.unwrap_or_default();
+    let simulation = Simulation {
+        error: result
+            .err
+            .map(|error| transaction_error(error, Some(logs.clone())).with_program_error(programs)),
+        logs,
+        units_consumed: result.units_consumed,
+        token_balances: token_balances(&accounts, &pre, &post),
+    };
+    info!("Dry run, nothing was sent\n{simulation}");
+
+    simulation.error.map_or(Ok(trx.signatures[0]), Err)
+}
+
+/// The accounts used by a transaction, those loaded from lookup tables included.
+fn involved_accounts(
+    trx: &VersionedTransaction,
+    tables: &[AddressLookupTableAccount],
+) -> Vec<Pubkey> {
+    let mut accounts = trx.message.static_account_keys().to_vec();
+    for lookup in trx.message.address_table_lookups().unwrap_or_default() {
+        let Some(table) = tables.iter().find(|table| table.key == lookup.account_key) else {
+            continue;
+        };
+        accounts.extend(
+            lookup
+                .writable_indexes
+                .iter()
+                .chain(&lookup.readonly_indexes)
+                .filter_map(|index| table.addresses.get(usize::from(*index))),
+        );
+    }
+    accounts
+}
+
+/// The mint and amount of a token account, `None` if the account is not one.
+fn token_amount(owner: &Pubkey, data: &[u8]) -> Option<(Pubkey, u64)> {
+    if *owner != spl_token::ID && *owner != spl_token_2022::ID {
+        return None;
+    }
+    StateWithExtensions::<state::Account>::unpack(data)
+        .ok()
+        .map(|account| (account.base.mint, account.base.amount))
+}
+
+/// The balances of the token accounts before and after a transaction.
+///
+/// # Parameters
+/// * `accounts` - The accounts of the transaction,
+/// * `pre` - The mint and amount of the token accounts before the transaction,
+/// * `post` - The mint and amount of the token accounts after the transaction.
+fn token_balances(
+    accounts: &[Pubkey],
+    pre: &[Option<(Pubkey, u64)>],
+    post: &[Option<(Pubkey, u64)>],
+) -> Vec<TokenBalance> {
+    accounts
+        .iter()
+        .enumerate()
+        .filter_map(|(index, account)| {
+            let pre = pre.get(index).copied().flatten();
+            let post = post.get(index).copied().flatten();
+            let mint = pre.or(post)?.0;
+            Some(TokenBalance {
+                account: *account,
+                mint,
+                pre: pre.map_or(0, |(_mint, amount)| amount),
+                post: post.map_or(0, |(_mint, amount)| amount),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+
+    use solana_sdk::program_pack::Pack as _;
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn token_balance_changes() {
+        // Given
+        let mint = Pubkey::new_unique();
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint,
+            owner: Pubkey::new_unique(),
+            amount: 42,
+            state: spl_token::state::AccountState::Initialized,
+            ..spl_token::state::Account::default()
+        }
+        .pack_into_slice(&mut data);
+        let (existing, created, other) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        // When
+        let token = token_amount(&spl_token::ID, &data);
+        let balances = token_balances(
+            &[existing, created, other],
+            &[token, None, None],
+            &[Some((mint, 40)), token, None],
+        );
+
+        // Then
+        assert_eq!(token, Some((mint, 42)));
+        assert_eq!(
+            token_amount(&Pubkey::new_unique(), &data),
+            None,
+            "not owned by a token program"
+        );
+        assert_eq!(
+            balances,
+            [
+                TokenBalance {
+                    account: existing,
+                    mint,
+                    pre: 42,
+                    post: 40
+                },
+                TokenBalance {
+                    account: created,
+                    mint,
+                    pre: 0,
+                    post: 42
+                }
+            ]
+        );
+    }
+}
@@ -1,57 +1,569 @@
 use solana_client::{
-    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig},
     rpc_request::{RpcError, RpcResponseErrorData},
     rpc_response::RpcSimulateTransactionResult,
 };
 use solana_rpc_client_api::client_error::{self, ErrorKind};
 use solana_sdk::{
+    address_lookup_table::{
+        self, AddressLookupTableAccount,
+        state::{AddressLookupTable, LOOKUP_TABLE_MAX_ADDRESSES},
+    },
+    clock::Slot,
     commitment_config::{CommitmentConfig, CommitmentLevel},
+    compute_budget::ComputeBudgetInstruction,
     hash::Hash,
     instruction::Instruction,
+    message::{VersionedMessage, v0},
+    pubkey::Pubkey,
     signature::{Keypair, Signature},
     signer::Signer,
-    transaction::{Transaction, TransactionError},
+    transaction::{Transaction, TransactionError, VersionedTransaction},
 };
 use tokio::select;
 use tracing::{debug, error, info, instrument, trace, warn};
 
 use crate::{
-    config::{COMMITMENT_LEVEL, RPC_HTTP, RPC_WS, TRX_PAYER},
+    config::Context,
     error::{Error, Result},
+    sender::{REBROADCAST_INTERVAL, send_with_retries},
+    simulation::dry_run,
 };
 
+/// Maximum number of addresses added to a lookup table by a single transaction.
+///
+/// Each address takes 32 bytes of the 1232 bytes of a transaction.
+const MAX_EXTEND_ADDRESSES: usize = 20;
+
+/// The maximum compute unit limit of a transaction.
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+/// Margin added to the compute units consumed by a simulation, in percent.
+const COMPUTE_UNIT_MARGIN_PERCENT: u64 = 10;
+/// Maximum number of accounts `getRecentPrioritizationFees` accepts.
+const MAX_PRIORITIZATION_FEE_ACCOUNTS: usize = 128;
+
+/// How the compute budget of the transactions is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionOptions {
+    /// Compute unit limit, the default one (200k per instruction) if unset.
+    pub compute_unit_limit: Option<u32>,
+    /// Compute unit price in micro-lamports, no priority fee if unset.
+    pub compute_unit_price: Option<u64>,
+    /// Whether the unset limit and price are derived from a simulation and the recent
+    /// priority fees.
+    pub auto: bool,
+    /// Percentile of the recent priority fees of the writable accounts paid in auto mode.
+    pub priority_fee_percentile: u8,
+    /// Number of times a transaction is signed again with a fresh blockhash if it expired.
+    pub max_retries: u8,
+    /// Whether the transactions are only simulated, and never sent.
+    pub dry_run: bool,
+}
+
+impl Default for TransactionOptions {
+    fn default() -> Self {
+        Self {
+            compute_unit_limit: None,
+            compute_unit_price: None,
+            auto: false,
+            priority_fee_percentile: 75,
+            max_retries: 2,
+            dry_run: false,
+        }
+    }
+}
+
+impl TransactionOptions {
+    /// The number of compute budget instructions put before the instructions of a transaction.
+    ///
+    /// The programs reading the instructions sysvar (e.g. klend flash loans) reference the
+    /// instructions of a transaction by their index, shifted by as many.
+    pub fn compute_budget_instructions(&self) -> usize {
+        if self.auto {
+            // the missing values are always found
+            return 2;
+        }
+        usize::from(self.compute_unit_limit.is_some())
+            + usize::from(self.compute_unit_price.is_some())
+    }
+}
+
+/// The compute budget of a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct ComputeBudget {
+    unit_limit: Option<u32>,
+    unit_price: Option<u64>,
+}
+
+impl ComputeBudget {
+    /// The compute budget of a simulated transaction.
+    ///
+    /// In auto mode, the missing limit is the highest one and the missing price is zero, so
+    /// that the simulated transaction holds the same instructions as the sent one.
+    const fn simulated(options: &TransactionOptions) -> Self {
+        let mut budget = Self {
+            unit_limit: options.compute_unit_limit,
+            unit_price: options.compute_unit_price,
+        };
+        if options.auto {
+            if budget.unit_limit.is_none() {
+                budget.unit_limit = Some(MAX_COMPUTE_UNIT_LIMIT);
+            }
+            if budget.unit_price.is_none() {
+                budget.unit_price = Some(0);
+            }
+        }
+        budget
+    }
+
+    /// The compute budget instructions, to put before the other instructions.
+    fn instructions(self) -> Vec<Instruction> {
+        self.unit_limit
+            .map(ComputeBudgetInstruction::set_compute_unit_limit)
+            .into_iter()
+            .chain(
+                self.unit_price
+                    .map(ComputeBudgetInstruction::set_compute_unit_price),
+            )
+            .collect()
+    }
+
+    /// The instructions of a transaction preceded by the compute budget ones.
+    fn prepend(self, instructions: &[Instruction]) -> Vec<Instruction> {
+        let mut all_instructions = self.instructions();
+        all_instructions.extend_from_slice(instructions);
+        all_instructions
+    }
+}
+
 /// Packages instructions into a transaction and executes it.
 ///
+/// The transaction is always paid for and signed by the configured payer. It is a v0 transaction
+/// using the configured lookup tables if there are any, a legacy transaction otherwise. Its
+/// compute budget follows the configured [`TransactionOptions`]. It is rebroadcast until it is
+/// confirmed, and signed again with a fresh blockhash when it expires (see [`send_with_retries`]).
+/// In dry-run mode, it is only simulated and its outcome displayed (see [`dry_run`]), with the
+/// highest compute unit limit in auto mode.
+///
+/// * `ctx` - The cluster and payer of the transaction,
 /// * `instructions` - Instructions to execute in the transaction,
+/// * `signers` - Additional signers required by the instructions (owners, new accounts…).
 ///
 /// # Errors
 /// If the transaction fails to execute.
-#[expect(clippy::expect_used)]
 #[instrument(skip_all)]
-pub async fn execute_instructions(instructions: &[Instruction]) -> Result<Signature> {
+pub async fn execute_instructions(
+    ctx: &Context,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<Signature> {
     debug!("executing transaction");
-    let rpc = get_rpc();
-
-    let payer = Keypair::from_bytes(TRX_PAYER.get().expect("trx payer is not set"))
-        .map_err(|_err| Error::Keypair)?;
-    let block = get_blockhash(&rpc).await?;
-    let trx =
-        Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &[&payer], block);
+    let rpc = &ctx.rpc;
+    let options = ctx.options;
 
-    trace!(
-        signature = ?trx.signatures.first(),
-        "transaction was created, sending it for execution",
+    let mut all_signers = vec![&ctx.payer];
+    for signer in signers {
+        if all_signers
+            .iter()
+            .all(|known| known.pubkey() != signer.pubkey())
+        {
+            all_signers.push(signer);
+        }
+    }
+    let tables = get_lookup_tables(ctx).await?;
+    #[expect(clippy::result_large_err)]
+    let build = |budget: ComputeBudget, block: Hash| {
+        build_transaction(
+            &ctx.payer,
+            &budget.prepend(instructions),
+            &all_signers,
+            &tables,
+            block,
+        )
+    };
+    // the blockhash of the simulations is replaced by the latest one
+    #[expect(clippy::result_large_err)]
+    let simulated = |budget| build(budget, Hash::default());
+    let programs = ctx.programs();
+    if options.dry_run {
+        // before the auto-budget simulation, which would fail without the outcome
+        let budget = ComputeBudget::simulated(&options);
+        return dry_run(rpc, &simulated(budget)?, &tables, &programs).await;
+    }
+    let budget = compute_budget(rpc, &options, simulated)
+        .await
+        .map_err(|err| err.with_program_error(&programs))?;
+    debug!("transaction was created, sending it for execution");
+    #[expect(clippy::result_large_err)]
+    let signed = |block| build(budget, block);
+    let sig = send_with_retries(rpc, signed, options.max_retries, REBROADCAST_INTERVAL)
+        .await
+        .map_err(|err| err.with_program_error(&programs))?;
+    info!(
+        %sig,
+        compute_unit_limit = ?budget.unit_limit,
+        compute_unit_price = ?budget.unit_price,
+        "transaction was sent",
     );
-    let sig = rpc
-        .send_and_confirm_transaction(&trx)
+    wait_for_finalization(rpc, &sig).await?;
+
+    Ok(sig)
+}
+
+/// Chooses the compute budget of a transaction.
+///
+/// The explicit values of the options are always used. In auto mode, the transaction is
+/// simulated to set the limit to the units it consumes plus a margin, and its price is a
+/// percentile of the recent priority fees paid to write to its accounts.
+///
+/// # Parameters
+/// * `rpc` - The RPC client,
+/// * `options` - The compute budget options,
+/// * `build` - Builds the transaction with a compute budget.
+///
+/// # Errors
+/// If the simulation fails, or the recent priority fees could not be fetched.
+async fn compute_budget<F>(
+    rpc: &RpcClient,
+    options: &TransactionOptions,
+    build: F,
+) -> Result<ComputeBudget>
+where
+    F: Fn(ComputeBudget) -> Result<VersionedTransaction>,
+{
+    let mut budget = ComputeBudget {
+        unit_limit: options.compute_unit_limit,
+        unit_price: options.compute_unit_price,
+    };
+    if !options.auto {
+        return Ok(budget);
+    }
+
+    if budget.unit_limit.is_none() {
+        let simulated = build(ComputeBudget::simulated(options))?;
+        let result = simulate(rpc, &simulated, &[]).await?;
+        if let Some(error) = result.err {
+            return Err(transaction_error(error, result.logs));
+        }
+        let consumed = result
+            .units_consumed
+            .unwrap_or_else(|| u64::from(MAX_COMPUTE_UNIT_LIMIT));
+        debug!(%consumed, "transaction simulated");
+        budget.unit_limit = Some(limit_with_margin(consumed));
+    }
+
+    if budget.unit_price.is_none() {
+        let accounts = writable_accounts(&build(budget)?);
+        let mut fees: Vec<u64> = rpc
+            .get_recent_prioritization_fees(&accounts)
+            .await
+            .map_err(process_rpc_error)?
+            .into_iter()
+            .map(|fee| fee.prioritization_fee)
+            .collect();
+        let price = percentile(&mut fees, options.priority_fee_percentile);
+        debug!(fees = fees.len(), %price, "recent priority fees");
+        budget.unit_price = Some(price);
+    }
+
+    Ok(budget)
+}
+
+/// Simulates a transaction, without checking its signatures and with the latest blockhash.
+///
+/// # Parameters
+/// * `rpc` - The RPC client,
+/// * `trx` - The transaction to simulate,
+/// * `accounts` - Accounts whose state after the transaction is returned.
+///
+/// # Errors
+/// If the simulation could not be run.
+pub async fn simulate(
+    rpc: &RpcClient,
+    trx: &VersionedTransaction,
+    accounts: &[Pubkey],
+) -> Result<RpcSimulateTransactionResult> {
+    let accounts = (!accounts.is_empty()).then(|| RpcSimulateTransactionAccountsConfig {
+        // base64 by default
+        encoding: None,
+        addresses: accounts.iter().map(ToString::to_string).collect(),
+    });
+    Ok(rpc
+        .simulate_transaction_with_config(
+            trx,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(rpc.commitment()),
+                accounts,
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )
+        .await
+        .map_err(process_rpc_error)?
+        .value)
+}
+
+/// The compute unit limit covering the units consumed by a simulation, with a margin.
+fn limit_with_margin(consumed: u64) -> u32 {
+    let limit = consumed
+        .saturating_mul(100 + COMPUTE_UNIT_MARGIN_PERCENT)
+        .div_ceil(100);
+    u32::try_from(limit).map_or(MAX_COMPUTE_UNIT_LIMIT, |limit| {
+        limit.min(MAX_COMPUTE_UNIT_LIMIT)
+    })
+}
+
+/// The static accounts written by a transaction, its payer excluded.
+fn writable_accounts(trx: &VersionedTransaction) -> Vec<Pubkey> {
+    let keys = trx.message.static_account_keys();
+    (1..keys.len())
+        .filter(|index| trx.message.is_maybe_writable(*index))
+        .map(|index| keys[index])
+        .take(MAX_PRIORITIZATION_FEE_ACCOUNTS)
+        .collect()
+}
+
+/// The value at a percentile (nearest rank) of a list of values, 0 if it is empty.
+fn percentile(values: &mut [u64], percentile: u8) -> u64 {
+    values.sort_unstable();
+    let rank = (values.len() * usize::from(percentile.min(100))).div_ceil(100);
+    values
+        .get(rank.saturating_sub(1))
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Compiles and signs a transaction.
+///
+/// The transaction is a v0 transaction loading its accounts from the lookup tables if any are
+/// given, and falls back to a legacy transaction if there are none or the message cannot be
+/// compiled with them.
+///
+/// # Parameters
+/// * `payer` - The payer of the transaction, also its first signer,
+/// * `instructions` - Instructions to execute in the transaction,
+/// * `signers` - All the signers of the transaction, the payer included,
+/// * `tables` - The lookup tables available to the transaction,
+/// * `block` - A recent blockhash.
+///
+/// # Errors
+/// If the transaction could not be signed.
+#[expect(clippy::result_large_err)]
+fn build_transaction(
+    payer: &Keypair,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+    tables: &[AddressLookupTableAccount],
+    block: Hash,
+) -> Result<VersionedTransaction> {
+    if !tables.is_empty() {
+        match v0::Message::try_compile(&payer.pubkey(), instructions, tables, block) {
+            Ok(message) => {
+                trace!(
+                    lookups = message.address_table_lookups.len(),
+                    "compiled a v0 message"
+                );
+                return Ok(VersionedTransaction::try_new(
+                    VersionedMessage::V0(message),
+                    signers,
+                )?);
+            }
+            Err(err) => warn!(%err, "could not compile a v0 message, using a legacy transaction"),
+        }
+    }
+
+    Ok(
+        Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), signers, block)
+            .into(),
+    )
+}
+
+/// Fetches the lookup tables of the context, skipping the deactivated ones.
+///
+/// # Errors
+/// If a lookup table could not be fetched.
+async fn get_lookup_tables(ctx: &Context) -> Result<Vec<AddressLookupTableAccount>> {
+    let mut tables = vec![];
+    for address in &ctx.lookup_tables {
+        let (table, deactivation_slot) = fetch_lookup_table(&ctx.rpc, address).await?;
+        if deactivation_slot == Slot::MAX {
+            tables.push(table);
+        } else {
+            warn!(%address, "lookup table is deactivated, not using it");
+        }
+    }
+    Ok(tables)
+}
+
+/// Fetches a lookup table.
+///
+/// # Parameters
+/// * `rpc` - The RPC client,
+/// * `address` - Address of the lookup table.
+///
+/// # Returns
+/// The addresses in the table, and the slot it was deactivated at (`Slot::MAX` if active).
+///
+/// # Errors
+/// If the account does not exist or is not a lookup table.
+async fn fetch_lookup_table(
+    rpc: &RpcClient,
+    address: &Pubkey,
+) -> Result<(AddressLookupTableAccount, Slot)> {
+    let data = rpc
+        .get_account_data(address)
         .await
         .map_err(process_rpc_error)?;
-    info!(" Transaction has passed preflight, waiting for confirmation");
-    wait_for_finalization(&rpc, &sig).await?;
+    let table = AddressLookupTable::deserialize(&data).map_err(|err| Error::AccountDecode {
+        address: *address,
+        reason: err.to_string(),
+    })?;
+
+    Ok((
+        AddressLookupTableAccount {
+            key: *address,
+            addresses: table.addresses.to_vec(),
+        },
+        table.meta.deactivation_slot,
+    ))
+}
+
+/// Creates an empty lookup table.
+///
+/// # Parameters
+/// * `ctx` - The cluster and payer of the table,
+/// * `authority` - The authority of the table, allowed to extend and close it.
+///
+/// # Errors
+/// If the transaction fails.
+#[instrument(skip_all)]
+pub async fn create_lookup_table(ctx: &Context, authority: &Keypair) -> Result<Pubkey> {
+    // The table address derives from a slot that must be in the recent slot hashes
+    let slot = ctx
+        .rpc
+        .get_slot_with_commitment(CommitmentConfig::finalized())
+        .await
+        .map_err(process_rpc_error)?;
+    let (ix, table) = address_lookup_table::instruction::create_lookup_table(
+        authority.pubkey(),
+        ctx.payer.pubkey(),
+        slot,
+    );
+    let sig = execute_instructions(ctx, &[ix], &[authority]).await?;
+    info!(%table, "Lookup table created: {sig}");
+
+    Ok(table)
+}
+
+/// Adds addresses to a lookup table, skipping the ones it already holds.
+///
+/// The addresses are added by batches, in as many transactions as needed.
+///
+/// # Parameters
+/// * `ctx` - The cluster and payer of the transactions,
+/// * `authority` - The authority of the table,
+/// * `table` - Address of the lookup table,
+/// * `addresses` - Addresses to add.
+///
+/// # Errors
+/// If the table cannot hold all the addresses, or a transaction fails.
+#[instrument(skip(ctx, authority, addresses))]
+pub async fn extend_lookup_table(
+    ctx: &Context,
+    authority: &Keypair,
+    table: &Pubkey,
+    addresses: &[Pubkey],
+) -> Result<Vec<Signature>> {
+    let (current, _deactivation_slot) = fetch_lookup_table(&ctx.rpc, table).await?;
+    let new_addresses = missing_addresses(&current.addresses, addresses);
+    if current.addresses.len() + new_addresses.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+        return Err(Error::Instruction(format!(
+            "the lookup table {table} cannot hold {} more addresses",
+            new_addresses.len()
+        )));
+    }
+
+    let mut signatures = vec![];
+    for batch in new_addresses.chunks(MAX_EXTEND_ADDRESSES) {
+        let ix = address_lookup_table::instruction::extend_lookup_table(
+            *table,
+            authority.pubkey(),
+            Some(ctx.payer.pubkey()),
+            batch.to_vec(),
+        );
+        signatures.push(execute_instructions(ctx, &[ix], &[authority]).await?);
+    }
+    info!(
+        added = new_addresses.len(),
+        total = current.addresses.len() + new_addresses.len(),
+        "Lookup table extended"
+    );
+
+    Ok(signatures)
+}
+
+/// Deactivates a lookup table, so that it can be closed once it is no longer in the recent
+/// slot hashes.
+///
+/// # Parameters
+/// * `ctx` - The cluster and payer of the transaction,
+/// * `authority` - The authority of the table,
+/// * `table` - Address of the lookup table.
+///
+/// # Errors
+/// If the transaction fails.
+#[instrument(skip(ctx, authority))]
+pub async fn deactivate_lookup_table(
+    ctx: &Context,
+    authority: &Keypair,
+    table: &Pubkey,
+) -> Result<Signature> {
+    let ix = address_lookup_table::instruction::deactivate_lookup_table(*table, authority.pubkey());
+    let sig = execute_instructions(ctx, &[ix], &[authority]).await?;
+    info!("Lookup table deactivated: {sig}");
+
+    Ok(sig)
+}
+
+/// Closes a deactivated lookup table, its rent going back to its authority.
+///
+/// # Parameters
+/// * `ctx` - The cluster and payer of the transaction,
+/// * `authority` - The authority of the table,
+/// * `table` - Address of the lookup table.
+///
+/// # Errors
+/// If the table is still active or was deactivated too recently, or the transaction fails.
+#[instrument(skip(ctx, authority))]
+pub async fn close_lookup_table(
+    ctx: &Context,
+    authority: &Keypair,
+    table: &Pubkey,
+) -> Result<Signature> {
+    let ix = address_lookup_table::instruction::close_lookup_table(
+        *table,
+        authority.pubkey(),
+        authority.pubkey(),
+    );
+    let sig = execute_instructions(ctx, &[ix], &[authority]).await?;
+    info!("Lookup table closed: {sig}");
 
     Ok(sig)
 }
 
+/// The addresses not held by a lookup table yet, without duplicates.
+fn missing_addresses(current: &[Pubkey], addresses: &[Pubkey]) -> Vec<Pubkey> {
+    let mut missing: Vec<Pubkey> = Vec::with_capacity(addresses.len());
+    for address in addresses {
+        if !current.contains(address) && !missing.contains(address) {
+            missing.push(*address);
+        }
+    }
+    missing
+}
+
 /// Given a transaction’s signature, waits for its finalization on the blockchain.
 ///
 /// # Parameters
@@ -89,43 +601,6 @@ async fn wait_for_finalization(rpc: &RpcClient, sig: &Signature) -> Result<()> {
     Ok(())
 }
 
-/// Get the Solana RPC client.
-///
-/// There is a rate limiter associated to it, to prevent too many instructions from being
-/// sent to the blockchain through the Quick Node API.
-#[expect(clippy::expect_used)]
-#[instrument]
-pub fn get_rpc() -> RpcClient {
-    let url = RPC_HTTP
-        .get()
-        .cloned()
-        .expect("The RPC_HTTP address was not set");
-
-    debug!("getting RPC client at {url}");
-    RpcClient::new_with_commitment(
-        url,
-        CommitmentConfig {
-            commitment: COMMITMENT_LEVEL,
-        },
-    )
-}
-
-/// Get the client to subscribe to for event monitoring.
-#[expect(clippy::expect_used)]
-#[instrument]
-pub async fn get_pubsub() -> Result<PubsubClient> {
-    let url = RPC_WS
-        .get()
-        .cloned()
-        .expect("The RPC_WS address was not set");
-    debug!("getting pubsub client at {url}");
-    PubsubClient::new(&url).await.map_err(Error::Pubsub)
-}
-
-async fn get_blockhash(rpc: &RpcClient) -> Result<Hash> {
-    rpc.get_latest_blockhash().await.map_err(process_rpc_error)
-}
-
 /// Get a precise error from an `RpcError`
 ///
 /// Deconstructing the error as much as possible to get to the root of the issue
@@ -153,40 +628,232 @@ pub fn process_rpc_error(error: client_error::Error) -> Error {
         err => return Error::RpcMisc(err.to_string()),
     };
 
+    transaction_error(error, logs)
+}
+
+/// The error of a failed transaction, with the logs of its execution.
+///
+/// The error of the failing program is left to [`Error::with_program_error`], which knows the
+/// programs of the context.
+pub fn transaction_error(error: TransactionError, logs: Option<Vec<String>>) -> Error {
     match error {
-        TransactionError::InstructionError(_, instr_error) => Error::SolanaInstruction {
-            error: instr_error,
-            logs: logs.unwrap_or_default(),
-        },
+        TransactionError::InstructionError(_, instr_error) => {
+            let logs = logs.unwrap_or_default();
+            Error::SolanaInstruction {
+                program_error: None,
+                error: instr_error,
+                logs,
+            }
+        }
         error => Error::SolanaTransaction(error),
     }
 }
 
 #[cfg(test)]
 #[cfg_attr(coverage_nightly, coverage(off))]
+#[expect(clippy::unwrap_in_result)]
 mod tests {
 
-    use std::assert_matches::assert_matches;
+    use std::assert_matches;
 
+    use solana_client::rpc_request::RpcRequest;
     use solana_sdk::{signature::Keypair, signer::Signer};
     use test_log::test;
 
-    use crate::config::{SOURCE, TARGET, set_config};
+    use crate::{
+        config::{TARGET, mock::MockRpc, test_context},
+        error::program::{KlendError, ProgramError},
+        sender::TransactionSender as _,
+    };
 
     use super::*;
     type Result<T> = core::result::Result<T, Box<dyn core::error::Error>>;
 
+    #[test]
+    fn legacy_without_tables() -> Result<()> {
+        // Given
+        let payer = Keypair::new();
+        let target = Pubkey::new_unique();
+        let ix = solana_sdk::system_instruction::transfer(&payer.pubkey(), &target, 10);
+
+        // When
+        let trx = build_transaction(&payer, &[ix], &[&payer], &[], Hash::new_unique())?;
+
+        // Then
+        assert_matches!(trx.message, VersionedMessage::Legacy(_));
+        assert!(trx.verify_with_results().iter().all(|ok| *ok), "signed");
+        Ok(())
+    }
+
+    #[test]
+    fn v0_with_tables() -> Result<()> {
+        // Given
+        let payer = Keypair::new();
+        let target = Pubkey::new_unique();
+        let ix = solana_sdk::system_instruction::transfer(&payer.pubkey(), &target, 10);
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![Pubkey::new_unique(), target],
+        };
+
+        // When
+        let trx = build_transaction(&payer, &[ix], &[&payer], &[table], Hash::new_unique())?;
+
+        // Then
+        let VersionedMessage::V0(message) = &trx.message else {
+            return Err("expected a v0 message".into());
+        };
+        assert_eq!(message.address_table_lookups.len(), 1);
+        assert_eq!(
+            message.address_table_lookups[0].writable_indexes,
+            [1],
+            "the target is loaded from the table"
+        );
+        assert!(
+            !message.account_keys.contains(&target),
+            "the target is not a static key"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compute_budget_instructions() -> Result<()> {
+        // Given
+        let payer = Keypair::new();
+        let target = Pubkey::new_unique();
+        let ix = solana_sdk::system_instruction::transfer(&payer.pubkey(), &target, 10);
+        let budget = ComputeBudget {
+            unit_limit: Some(limit_with_margin(1_000)),
+            unit_price: Some(5),
+        };
+
+        // When
+        let instructions = budget.prepend(&[ix]);
+        let trx = build_transaction(&payer, &instructions, &[&payer], &[], Hash::new_unique())?;
+
+        // Then
+        assert_eq!(instructions.len(), 3);
+        assert_eq!(
+            instructions[0],
+            ComputeBudgetInstruction::set_compute_unit_limit(1_100),
+            "10% margin"
+        );
+        assert_eq!(
+            instructions[1],
+            ComputeBudgetInstruction::set_compute_unit_price(5)
+        );
+        assert!(
+            ComputeBudget::default().instructions().is_empty(),
+            "nothing set by default"
+        );
+        assert_eq!(
+            writable_accounts(&trx),
+            [target],
+            "only the target is written besides the payer"
+        );
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn compute_budget_instruction_count() -> Result<()> {
+        // Given
+        let (ctx, _requests) = test_context(MockRpc::fixture("transfer")?)?;
+        let ix =
+            solana_sdk::system_instruction::transfer(&ctx.payer.pubkey(), &ctx.payer.pubkey(), 1);
+        #[expect(clippy::result_large_err)]
+        let build = |budget: ComputeBudget| {
+            build_transaction(
+                &ctx.payer,
+                &budget.prepend(core::slice::from_ref(&ix)),
+                &[&ctx.payer],
+                &[],
+                Hash::default(),
+            )
+        };
+        let all_options = [
+            TransactionOptions::default(),
+            TransactionOptions {
+                compute_unit_price: Some(10),
+                ..TransactionOptions::default()
+            },
+            TransactionOptions {
+                compute_unit_limit: Some(10_000),
+                compute_unit_price: Some(10),
+                ..TransactionOptions::default()
+            },
+            TransactionOptions {
+                auto: true,
+                ..TransactionOptions::default()
+            },
+            TransactionOptions {
+                auto: true,
+                compute_unit_limit: Some(10_000),
+                ..TransactionOptions::default()
+            },
+        ];
+
+        for options in all_options {
+            // When
+            let budget = compute_budget(&ctx.rpc, &options, build).await?;
+
+            // Then
+            assert_eq!(
+                budget.instructions().len(),
+                options.compute_budget_instructions(),
+                "{options:?}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn fee_percentiles() {
+        // Given
+        let mut fees = vec![40, 10, 0, 30, 20];
+
+        // When
+        let median = percentile(&mut fees, 50);
+        let high = percentile(&mut fees, 75);
+        let max = percentile(&mut fees, 100);
+
+        // Then
+        assert_eq!((median, high, max), (20, 30, 40));
+        assert_eq!(percentile(&mut [], 75), 0, "no recent fees");
+        assert_eq!(
+            limit_with_margin(u64::MAX),
+            MAX_COMPUTE_UNIT_LIMIT,
+            "the limit is capped"
+        );
+    }
+
+    #[test]
+    fn only_missing_addresses() {
+        // Given
+        let (known, new) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        // When
+        let missing = missing_addresses(&[known], &[known, new, new]);
+
+        // Then
+        assert_eq!(missing, [new]);
+    }
+
     #[test(tokio::test)]
     async fn get_latest_blockhash() -> Result<()> {
         // Given
-        set_config()?;
-        let rpc = get_rpc();
+        let (ctx, requests) = test_context(MockRpc::fixture("transfer")?)?;
 
         // When
-        let hash = get_blockhash(&rpc).await;
+        let hash = ctx.rpc.latest_blockhash().await;
 
         // Then
-        assert_matches!(hash, Ok(_hash), "{hash:?}");
+        assert_matches!(
+            hash,
+            Ok((hash, 300_000_150)) if hash.to_string() == "EkSnNWid2cvwEVnVx9aBqawnmiCNiDgp3gUdkDPTKN1N",
+            "{hash:?}"
+        );
+        assert_eq!(requests.methods(), [RpcRequest::GetLatestBlockhash]);
 
         Ok(())
     }
@@ -195,17 +862,123 @@ mod tests {
     async fn execute_transaction() -> Result<()> {
         // Given
         const LAMPORTS: u64 = 10;
-        set_config()?;
-        let source_pk = Keypair::from_bytes(SOURCE)?;
+        let (mut ctx, requests) = test_context(MockRpc::fixture("transfer")?)?;
+        ctx.options.auto = true;
         let target = Keypair::from_bytes(TARGET)?.pubkey();
         let instruction =
-            solana_sdk::system_instruction::transfer(&source_pk.pubkey(), &target, LAMPORTS);
+            solana_sdk::system_instruction::transfer(&ctx.payer.pubkey(), &target, LAMPORTS);
 
         // When
-        let res = execute_instructions(&[instruction]).await;
+        let res = execute_instructions(&ctx, &[instruction], &[]).await;
 
         // Then
         assert_matches!(res, Ok(_sig), "{res:?}");
+        let methods = requests.methods();
+        assert_eq!(
+            methods.get(..4),
+            Some(
+                [
+                    RpcRequest::SimulateTransaction,
+                    RpcRequest::GetRecentPrioritizationFees,
+                    RpcRequest::GetLatestBlockhash,
+                    RpcRequest::SendTransaction,
+                ]
+                .as_slice()
+            ),
+            "{methods:?}"
+        );
+        assert!(
+            methods
+                .iter()
+                .skip(4)
+                .all(|method| *method == RpcRequest::GetSignatureStatuses),
+            "{methods:?}"
+        );
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn dry_run_before_the_auto_budget() -> Result<()> {
+        // Given
+        let simulation = serde_json::json!({
+            "context": { "slot": 312_345_678_u64 },
+            "value": {
+                "err": { "InstructionError": [0_u8, { "Custom": 1_u32 }] },
+                "logs": ["Program log: Error: insufficient funds"],
+                "accounts": null,
+                "unitsConsumed": 150_u64,
+                "returnData": null
+            }
+        });
+        let (mut ctx, requests) =
+            test_context(MockRpc::default().respond(RpcRequest::SimulateTransaction, simulation))?;
+        ctx.options.auto = true;
+        ctx.options.dry_run = true;
+        let ix =
+            solana_sdk::system_instruction::transfer(&ctx.payer.pubkey(), &ctx.payer.pubkey(), 1);
+
+        // When
+        let res = execute_instructions(&ctx, &[ix], &[]).await;
+
+        // Then
+        assert_matches!(res, Err(Error::SolanaInstruction { .. }), "{res:?}");
+        assert_eq!(
+            requests.methods(),
+            [
+                RpcRequest::GetMultipleAccounts,
+                RpcRequest::SimulateTransaction
+            ],
+            "only the dry-run simulation"
+        );
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn program_error_of_the_context() -> Result<()> {
+        // Given
+        let klend_program = Pubkey::new_unique();
+        let simulation = serde_json::json!({
+            "context": { "slot": 312_345_678_u64 },
+            "value": {
+                "err": { "InstructionError": [0_u8, { "Custom": 6017_u32 }] },
+                "logs": [
+                    format!("Program {klend_program} invoke [1]"),
+                    format!("Program {klend_program} failed: custom program error: 0x1781"),
+                ],
+                "accounts": null,
+                "unitsConsumed": 150_u64,
+                "returnData": null
+            }
+        });
+        let (mut ctx, _requests) =
+            test_context(MockRpc::default().respond(RpcRequest::SimulateTransaction, simulation))?;
+        ctx.options.auto = true;
+        let ix =
+            solana_sdk::system_instruction::transfer(&ctx.payer.pubkey(), &ctx.payer.pubkey(), 1);
+
+        // When
+        let res = execute_instructions(&ctx, core::slice::from_ref(&ix), &[]).await;
+        ctx.klend_program = klend_program;
+        let decoded = execute_instructions(&ctx, &[ix], &[]).await;
+
+        // Then
+        assert_matches!(
+            res,
+            Err(Error::SolanaInstruction {
+                program_error: None,
+                ..
+            }),
+            "not the klend program of the context"
+        );
+        assert_matches!(
+            decoded,
+            Err(Error::SolanaInstruction {
+                program_error: Some(ProgramError::Klend(KlendError::ObligationStale)),
+                ..
+            })
+        );
 
         Ok(())
     }